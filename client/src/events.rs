@@ -0,0 +1,88 @@
+//! Decoding of the events deposited by the runtime.
+
+use codec::Decode;
+use node_template_runtime::{Hash, RuntimeEvent};
+use sp_core::twox_128;
+use sp_runtime::DispatchError;
+
+/// An event together with the phase of the block it was deposited in.
+pub type EventRecord = frame_system::EventRecord<RuntimeEvent, Hash>;
+
+/// The storage key of `System::Events`.
+pub fn events_key() -> Vec<u8> {
+	[twox_128(b"System"), twox_128(b"Events")].concat()
+}
+
+/// Decodes the value of `System::Events`.
+pub fn decode_events(mut encoded: &[u8]) -> Result<Vec<EventRecord>, codec::Error> {
+	Vec::<EventRecord>::decode(&mut encoded)
+}
+
+/// The events deposited by the extrinsic at `index` in its block.
+pub fn extrinsic_events(records: Vec<EventRecord>, index: u32) -> Vec<RuntimeEvent> {
+	records
+		.into_iter()
+		.filter(|record| record.phase == frame_system::Phase::ApplyExtrinsic(index))
+		.map(|record| record.event)
+		.collect()
+}
+
+/// The outcome of an extrinsic, read from the `ExtrinsicSuccess` or `ExtrinsicFailed` event among
+/// its `events`. `None` if neither is present.
+pub fn dispatch_result(events: &[RuntimeEvent]) -> Option<Result<(), DispatchError>> {
+	events.iter().find_map(|event| match event {
+		RuntimeEvent::System(frame_system::Event::ExtrinsicSuccess { .. }) => Some(Ok(())),
+		RuntimeEvent::System(frame_system::Event::ExtrinsicFailed { dispatch_error, .. }) =>
+			Some(Err(*dispatch_error)),
+		_ => None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_support::dispatch::DispatchInfo;
+	use frame_system::Phase;
+	use node_template_runtime::pallet_template;
+	use sp_keyring::Sr25519Keyring;
+
+	fn record(phase: Phase, event: impl Into<RuntimeEvent>) -> EventRecord {
+		EventRecord { phase, event: event.into(), topics: vec![] }
+	}
+
+	#[test]
+	fn filters_events_of_an_extrinsic() {
+		let stored = pallet_template::Event::SomethingStored {
+			something: 42,
+			who: Sr25519Keyring::Alice.to_account_id(),
+		};
+		let failed = frame_system::Event::ExtrinsicFailed {
+			dispatch_error: DispatchError::BadOrigin,
+			dispatch_info: DispatchInfo::default(),
+		};
+		let records = vec![
+			record(
+				Phase::ApplyExtrinsic(0),
+				frame_system::Event::ExtrinsicSuccess { dispatch_info: DispatchInfo::default() },
+			),
+			record(Phase::ApplyExtrinsic(1), stored.clone()),
+			record(
+				Phase::ApplyExtrinsic(1),
+				frame_system::Event::ExtrinsicSuccess { dispatch_info: DispatchInfo::default() },
+			),
+			record(Phase::ApplyExtrinsic(2), failed),
+			record(Phase::Finalization, stored.clone()),
+		];
+		let records = decode_events(&records.encode()).unwrap();
+
+		let events = extrinsic_events(records.clone(), 1);
+		assert_eq!(events[0], stored.into());
+		assert_eq!(dispatch_result(&events), Some(Ok(())));
+		assert_eq!(
+			dispatch_result(&extrinsic_events(records.clone(), 2)),
+			Some(Err(DispatchError::BadOrigin))
+		);
+		assert_eq!(dispatch_result(&extrinsic_events(records, 3)), None);
+	}
+}
@@ -0,0 +1,435 @@
+//! Typed Rust client for the node template.
+//!
+//! Transactions are built from the runtime's own types: any pallet call converts into a
+//! `RuntimeCall`, and [`tx::ExtraBuilder`] builds the full `SignedExtra` tuple, so a client
+//! compiled against this workspace cannot drift from the runtime it talks to. [`Client`] adds
+//! what needs a node: nonces, submission over WebSocket with inclusion and finalization tracking,
+//! and decoding of the events of included transactions. Storage items are read by name through
+//! the runtime metadata with [`metadata`].
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), node_template_client::Error> {
+//! use node_template_client::{Client, TxParams, WaitFor};
+//! use node_template_runtime::pallet_template;
+//! use sp_keyring::Sr25519Keyring;
+//!
+//! let client = Client::connect("ws://127.0.0.1:9944").await?;
+//! let call = pallet_template::Call::do_something { something: 42 };
+//! let xt = client.sign(call, &Sr25519Keyring::Alice.pair(), TxParams::default()).await?;
+//! let included = client.submit(&xt, WaitFor::Finalized).await?;
+//! println!("{:?}", included.events);
+//! # Ok(())
+//! # }
+//! ```
+
+use codec::Encode;
+use jsonrpsee::{
+	core::client::{ClientT, SubscriptionClientT},
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template_runtime::{
+	AccountId, AssetId, Balance, Block, BlockNumber, Hash, Header, Index, RuntimeEvent, Signature,
+	UncheckedExtrinsic,
+};
+use sc_transaction_pool_api::TransactionStatus;
+use sp_core::{storage::StorageChangeSet, Bytes, Pair};
+use sp_runtime::{
+	generic::SignedBlock,
+	traits::{Header as HeaderT, IdentifyAccount},
+	DispatchError, MultiSigner,
+};
+use sp_version::RuntimeVersion;
+
+pub mod events;
+pub mod metadata;
+pub mod payment;
+pub mod tx;
+pub mod utilization;
+
+use events::EventRecord;
+use metadata::Metadata;
+use payment::TipEstimate;
+use tx::{ChainContext, Checkpoint, ExtraBuilder};
+use utilization::BlockUtilization;
+
+/// The number of keys requested at once by [`Client::storage_pairs`].
+const KEYS_PAGE_SIZE: u32 = 1000;
+
+/// Errors of the [`Client`].
+#[derive(Debug)]
+pub enum Error {
+	/// The RPC request failed.
+	Rpc(jsonrpsee::core::Error),
+	/// Data returned by the node cannot be decoded.
+	Codec(codec::Error),
+	/// The transaction was dropped, found invalid or replaced before being included.
+	Rejected(String),
+	/// The node does not have data that it is expected to have.
+	Missing(String),
+	/// The runtime metadata cannot be used.
+	Metadata(metadata::Error),
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Error::Rpc(e) => write!(f, "RPC error: {e}"),
+			Error::Codec(e) => write!(f, "decoding error: {e}"),
+			Error::Rejected(reason) => write!(f, "transaction rejected: {reason}"),
+			Error::Missing(what) => write!(f, "missing {what}"),
+			Error::Metadata(e) => write!(f, "metadata error: {e}"),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<jsonrpsee::core::Error> for Error {
+	fn from(e: jsonrpsee::core::Error) -> Self {
+		Error::Rpc(e)
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Error::Codec(e)
+	}
+}
+
+impl From<metadata::Error> for Error {
+	fn from(e: metadata::Error) -> Self {
+		Error::Metadata(e)
+	}
+}
+
+/// How long [`Client::submit`] waits for a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitFor {
+	/// Until it is included in a block.
+	InBlock,
+	/// Until the block it is included in is finalized.
+	Finalized,
+}
+
+/// Signing options of [`Client::sign`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxParams {
+	/// The nonce to use, the next one of the signer's account when `None`.
+	pub nonce: Option<Index>,
+	/// The tip for the block author.
+	pub tip: Balance,
+	/// The asset paying the fee and the tip, the native balance when `None`.
+	pub asset_id: Option<AssetId>,
+	/// The account paying the fee, the signer when `None`. Sponsored transactions carry neither
+	/// `tip` nor `asset_id`.
+	pub sponsor: Option<AccountId>,
+	/// The number of blocks the transaction stays valid for after the best block, immortal when
+	/// `None`.
+	pub mortality: Option<u64>,
+}
+
+impl Default for TxParams {
+	fn default() -> Self {
+		Self {
+			nonce: None,
+			tip: 0,
+			asset_id: None,
+			sponsor: None,
+			mortality: Some(tx::default_period()),
+		}
+	}
+}
+
+/// A transaction included in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxInBlock {
+	/// The hash of the block including the transaction.
+	pub block_hash: Hash,
+	/// The index of the transaction in the block.
+	pub extrinsic_index: u32,
+	/// The events deposited by the transaction.
+	pub events: Vec<RuntimeEvent>,
+}
+
+impl TxInBlock {
+	/// Whether the transaction was dispatched successfully.
+	pub fn dispatch_result(&self) -> Result<(), DispatchError> {
+		events::dispatch_result(&self.events).unwrap_or(Ok(()))
+	}
+}
+
+/// A WebSocket connection to a node template.
+pub struct Client {
+	rpc: WsClient,
+	context: ChainContext,
+}
+
+impl Client {
+	/// Connects to the node at `url`, e.g. `ws://127.0.0.1:9944`.
+	pub async fn connect(url: &str) -> Result<Self, Error> {
+		let rpc = WsClientBuilder::default().build(url).await?;
+		let genesis_hash: Option<Hash> = rpc.request("chain_getBlockHash", rpc_params![0]).await?;
+		let genesis_hash = genesis_hash.ok_or_else(|| Error::Missing("genesis block".into()))?;
+		let version: RuntimeVersion = rpc.request("state_getRuntimeVersion", rpc_params![]).await?;
+
+		let context = ChainContext {
+			genesis_hash,
+			spec_version: version.spec_version,
+			transaction_version: version.transaction_version,
+		};
+		Ok(Self { rpc, context })
+	}
+
+	/// The chain data transactions signed by this client commit to.
+	pub fn context(&self) -> &ChainContext {
+		&self.context
+	}
+
+	/// The underlying RPC client, for methods this client does not wrap.
+	pub fn rpc(&self) -> &WsClient {
+		&self.rpc
+	}
+
+	/// The next nonce of `account`, including the transactions in the pool.
+	pub async fn next_nonce(&self, account: &AccountId) -> Result<Index, Error> {
+		Ok(self.rpc.request("system_accountNextIndex", rpc_params![account]).await?)
+	}
+
+	/// The header of the best block.
+	pub async fn best_header(&self) -> Result<Header, Error> {
+		let header: Option<Header> = self.rpc.request("chain_getHeader", rpc_params![]).await?;
+		header.ok_or_else(|| Error::Missing("best header".into()))
+	}
+
+	/// The block with the hash `hash`.
+	pub async fn block(&self, hash: Hash) -> Result<Block, Error> {
+		let block: Option<SignedBlock<Block>> =
+			self.rpc.request("chain_getBlock", rpc_params![hash]).await?;
+		Ok(block.ok_or_else(|| Error::Missing(format!("block {hash:?}")))?.block)
+	}
+
+	/// The hash of the block `number`, of the best block when `None`.
+	pub async fn block_hash(&self, number: Option<BlockNumber>) -> Result<Hash, Error> {
+		let hash: Option<Hash> =
+			self.rpc.request("chain_getBlockHash", rpc_params![number]).await?;
+		hash.ok_or_else(|| Error::Missing(format!("block #{}", number.unwrap_or_default())))
+	}
+
+	/// The hash of the last finalized block.
+	pub async fn finalized_hash(&self) -> Result<Hash, Error> {
+		Ok(self.rpc.request("chain_getFinalizedHead", rpc_params![]).await?)
+	}
+
+	/// The metadata of the runtime of the block with the hash `at`.
+	pub async fn metadata(&self, at: Hash) -> Result<Metadata, Error> {
+		let metadata: Bytes = self.rpc.request("state_getMetadata", rpc_params![at]).await?;
+		Ok(Metadata::decode(&metadata)?)
+	}
+
+	/// The value stored under `key` in the state of the block with the hash `at`.
+	pub async fn storage(&self, key: &[u8], at: Hash) -> Result<Option<Vec<u8>>, Error> {
+		let value: Option<Bytes> = self
+			.rpc
+			.request("state_getStorage", rpc_params![Bytes(key.to_vec()), at])
+			.await?;
+		Ok(value.map(|value| value.0))
+	}
+
+	/// Up to `count` keys starting with `prefix` and following `start_key`, in the state of the
+	/// block with the hash `at`.
+	pub async fn storage_keys_paged(
+		&self,
+		prefix: &[u8],
+		count: u32,
+		start_key: Option<&[u8]>,
+		at: Hash,
+	) -> Result<Vec<Vec<u8>>, Error> {
+		let keys: Vec<Bytes> = self
+			.rpc
+			.request(
+				"state_getKeysPaged",
+				rpc_params![
+					Bytes(prefix.to_vec()),
+					count,
+					start_key.map(|k| Bytes(k.to_vec())),
+					at
+				],
+			)
+			.await?;
+		Ok(keys.into_iter().map(|key| key.0).collect())
+	}
+
+	/// The key-value pairs starting with `prefix` in the state of the block with the hash `at`.
+	pub async fn storage_pairs(
+		&self,
+		prefix: &[u8],
+		at: Hash,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+		let mut pairs = Vec::new();
+		let mut start_key = None;
+		loop {
+			let keys = self
+				.storage_keys_paged(prefix, KEYS_PAGE_SIZE, start_key.as_deref(), at)
+				.await?;
+			let params: Vec<Bytes> = keys.iter().cloned().map(Bytes).collect();
+			let changes: Vec<StorageChangeSet<Hash>> =
+				self.rpc.request("state_queryStorageAt", rpc_params![params, at]).await?;
+			pairs.extend(
+				changes
+					.into_iter()
+					.flat_map(|set| set.changes)
+					.filter_map(|(key, value)| value.map(|value| (key.0, value.0))),
+			);
+
+			if keys.len() < KEYS_PAGE_SIZE as usize {
+				return Ok(pairs)
+			}
+			start_key = keys.last().cloned();
+		}
+	}
+
+	/// The key-value pairs of the child trie stored under `prefixed_key`, e.g.
+	/// `:child_storage:default:` followed by the trie id of a contract, in the state of the block
+	/// with the hash `at`.
+	pub async fn child_storage_pairs(
+		&self,
+		prefixed_key: &[u8],
+		at: Hash,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+		let child = Bytes(prefixed_key.to_vec());
+		let mut pairs = Vec::new();
+		let mut start_key: Option<Bytes> = None;
+		loop {
+			let keys: Vec<Bytes> = self
+				.rpc
+				.request(
+					"childstate_getKeysPaged",
+					rpc_params![child.clone(), Bytes(Vec::new()), KEYS_PAGE_SIZE, start_key, at],
+				)
+				.await?;
+			let values: Vec<Option<Bytes>> = self
+				.rpc
+				.request(
+					"childstate_getStorageEntries",
+					rpc_params![child.clone(), keys.clone(), at],
+				)
+				.await?;
+			pairs.extend(
+				keys.iter()
+					.zip(values)
+					.filter_map(|(key, value)| value.map(|value| (key.0.clone(), value.0))),
+			);
+
+			if keys.len() < KEYS_PAGE_SIZE as usize {
+				return Ok(pairs)
+			}
+			start_key = keys.last().cloned();
+		}
+	}
+
+	/// The events deposited in the block with the hash `hash`.
+	pub async fn events(&self, hash: Hash) -> Result<Vec<EventRecord>, Error> {
+		Ok(self
+			.storage(&events::events_key(), hash)
+			.await?
+			.map(|events| events::decode_events(&events))
+			.transpose()?
+			.unwrap_or_default())
+	}
+
+	/// The utilization of the blocks `from..=to` of the best chain, up to the best block when `to`
+	/// is `None`, with their `top` heaviest calls, from the `utilization_blocks` RPC of the node.
+	pub async fn utilization(
+		&self,
+		from: BlockNumber,
+		to: Option<BlockNumber>,
+		top: Option<u32>,
+	) -> Result<Vec<BlockUtilization>, Error> {
+		Ok(self.rpc.request("utilization_blocks", rpc_params![from, to, top]).await?)
+	}
+
+	/// The tip giving `call` a chance of at least `probability` to be included in the next block,
+	/// 0.9 when `None`, from the `payment_estimate` RPC of the node.
+	pub async fn estimate_tip(
+		&self,
+		call: impl Into<node_template_runtime::RuntimeCall>,
+		probability: Option<f64>,
+	) -> Result<TipEstimate, Error> {
+		let call = Bytes(call.into().encode());
+		Ok(self.rpc.request("payment_estimate", rpc_params![call, probability]).await?)
+	}
+
+	/// Signs `call` with `signer`, filling the nonce and the mortality checkpoint from the node.
+	pub async fn sign<P>(
+		&self,
+		call: impl Into<node_template_runtime::RuntimeCall>,
+		signer: &P,
+		params: TxParams,
+	) -> Result<UncheckedExtrinsic, Error>
+	where
+		P: Pair,
+		Signature: From<P::Signature>,
+		MultiSigner: From<P::Public>,
+	{
+		let nonce = match params.nonce {
+			Some(nonce) => nonce,
+			None => {
+				let account = MultiSigner::from(signer.public()).into_account();
+				self.next_nonce(&account).await?
+			},
+		};
+
+		let mut extra = ExtraBuilder::new(nonce)
+			.tip(params.tip)
+			.asset_id(params.asset_id)
+			.sponsor(params.sponsor);
+		if let Some(period) = params.mortality {
+			let best = self.best_header().await?;
+			extra = extra.mortal(period, Checkpoint { number: best.number, hash: best.hash() });
+		}
+
+		Ok(tx::sign(call, signer, &extra, &self.context))
+	}
+
+	/// Submits `xt` and waits for it to be included, or finalized, in a block.
+	pub async fn submit(&self, xt: &UncheckedExtrinsic, wait: WaitFor) -> Result<TxInBlock, Error> {
+		let encoded = xt.encode();
+		let mut statuses = self
+			.rpc
+			.subscribe::<TransactionStatus<Hash, Hash>, _>(
+				"author_submitAndWatchExtrinsic",
+				rpc_params![Bytes(encoded.clone())],
+				"author_unwatchExtrinsic",
+			)
+			.await?;
+
+		let block_hash = loop {
+			let status = statuses
+				.next()
+				.await
+				.ok_or_else(|| Error::Rejected("subscription closed".into()))??;
+			match status {
+				TransactionStatus::InBlock(hash) if wait == WaitFor::InBlock => break hash,
+				TransactionStatus::Finalized(hash) => break hash,
+				TransactionStatus::Future |
+				TransactionStatus::Ready |
+				TransactionStatus::Broadcast(_) |
+				TransactionStatus::InBlock(_) |
+				TransactionStatus::Retracted(_) => continue,
+				status => return Err(Error::Rejected(format!("{status:?}"))),
+			}
+		};
+
+		let block = self.block(block_hash).await?;
+		let extrinsic_index = block
+			.extrinsics
+			.iter()
+			.position(|included| included.encode() == encoded)
+			.ok_or_else(|| Error::Missing(format!("transaction in block {block_hash:?}")))?
+			as u32;
+		let events = events::extrinsic_events(self.events(block_hash).await?, extrinsic_index);
+
+		Ok(TxInBlock { block_hash, extrinsic_index, events })
+	}
+}
@@ -0,0 +1,598 @@
+//! Storage keys and JSON decoding of SCALE values, driven by the runtime metadata.
+//!
+//! Unlike the rest of this crate, nothing here depends on the native runtime types: storage
+//! items and their types are looked up by name in the metadata of the block being inspected, so
+//! the state written by older runtimes decodes as well.
+
+use codec::{Compact, Decode, Encode};
+use frame_metadata::{
+	v14::{RuntimeMetadataV14, StorageEntryModifier, StorageEntryType, StorageHasher},
+	RuntimeMetadata, RuntimeMetadataPrefixed,
+};
+use scale_info::{form::PortableForm, Field, TypeDef, TypeDefPrimitive};
+use serde_json::{Map, Value};
+use sp_core::{
+	bytes::{from_hex, to_hex},
+	crypto::{AccountId32, Ss58Codec},
+	hashing::{blake2_128, blake2_256, twox_128, twox_256, twox_64},
+};
+
+/// Errors of the metadata driven encoding and decoding.
+#[derive(Debug)]
+pub enum Error {
+	/// The metadata is not in the V14 format.
+	UnsupportedVersion(u32),
+	/// The metadata, or a value, cannot be decoded.
+	Codec(codec::Error),
+	/// The pallet, the storage item or a type is not in the metadata.
+	NotFound(String),
+	/// A storage key argument does not match the type of the key.
+	InvalidKey(String),
+	/// The type cannot be decoded into JSON.
+	Unsupported(String),
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Error::UnsupportedVersion(version) =>
+				write!(f, "unsupported metadata version V{version}, expected V14"),
+			Error::Codec(e) => write!(f, "decoding error: {e}"),
+			Error::NotFound(what) => write!(f, "{what} not found in the metadata"),
+			Error::InvalidKey(reason) => write!(f, "invalid storage key: {reason}"),
+			Error::Unsupported(what) => write!(f, "unsupported type: {what}"),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Error::Codec(e)
+	}
+}
+
+/// The V14 metadata of a runtime.
+#[derive(Clone, Debug)]
+pub struct Metadata(RuntimeMetadataV14);
+
+impl Metadata {
+	/// Decodes the metadata returned by the `Metadata_metadata` runtime API, or by the
+	/// `state_getMetadata` RPC.
+	pub fn decode(mut encoded: &[u8]) -> Result<Self, Error> {
+		match RuntimeMetadataPrefixed::decode(&mut encoded)?.1 {
+			RuntimeMetadata::V14(metadata) => Ok(Self(metadata)),
+			other => Err(Error::UnsupportedVersion(other.version())),
+		}
+	}
+
+	/// The decoded metadata.
+	pub fn inner(&self) -> &RuntimeMetadataV14 {
+		&self.0
+	}
+
+	/// Looks up the storage item `item` of `pallet`, keyed by the first `keys.len()` keys of the
+	/// item, e.g. `storage("System", "Account", &["5Grw…"])`.
+	///
+	/// Each key is either the SS58 address of an account, a number, a string, or the
+	/// SCALE encoding of the key in hex.
+	pub fn storage(
+		&self,
+		pallet: &str,
+		item: &str,
+		keys: &[String],
+	) -> Result<StorageQuery, Error> {
+		let storage = self
+			.0
+			.pallets
+			.iter()
+			.find(|p| p.name == pallet)
+			.ok_or_else(|| Error::NotFound(format!("pallet `{pallet}`")))?
+			.storage
+			.as_ref()
+			.ok_or_else(|| Error::NotFound(format!("storage of `{pallet}`")))?;
+		let entry = storage
+			.entries
+			.iter()
+			.find(|e| e.name == item)
+			.ok_or_else(|| Error::NotFound(format!("storage item `{pallet}::{item}`")))?;
+
+		let (hashers, key_types, value_type) = match &entry.ty {
+			StorageEntryType::Plain(value) => (vec![], vec![], value.id),
+			StorageEntryType::Map { hashers, key, value } => {
+				let key_types = match (hashers.len(), &self.resolve(key.id)?.type_def) {
+					(1, _) => vec![key.id],
+					(_, TypeDef::Tuple(tuple)) => tuple.fields.iter().map(|ty| ty.id).collect(),
+					_ => return Err(Error::Unsupported(format!("key of `{pallet}::{item}`"))),
+				};
+				(hashers.clone(), key_types, value.id)
+			},
+		};
+		if keys.len() > hashers.len() {
+			return Err(Error::InvalidKey(format!(
+				"`{pallet}::{item}` has {} keys, {} given",
+				hashers.len(),
+				keys.len()
+			)))
+		}
+
+		let mut key = [twox_128(storage.prefix.as_bytes()), twox_128(item.as_bytes())].concat();
+		for ((hasher, ty), arg) in hashers.iter().zip(&key_types).zip(keys) {
+			key.extend(hash(hasher, &self.encode_arg(*ty, arg)?));
+		}
+		let default = match entry.modifier {
+			StorageEntryModifier::Default => Some(entry.default.clone()),
+			StorageEntryModifier::Optional => None,
+		};
+
+		Ok(StorageQuery {
+			metadata: self,
+			key,
+			partial: keys.len() < hashers.len(),
+			hashers,
+			key_types,
+			value_type,
+			default,
+		})
+	}
+
+	/// The SCALE encoding of the constant `name` of `pallet`, e.g.
+	/// `constant("System", "BlockWeights")`.
+	pub fn constant(&self, pallet: &str, name: &str) -> Result<&[u8], Error> {
+		self.0
+			.pallets
+			.iter()
+			.find(|p| p.name == pallet)
+			.ok_or_else(|| Error::NotFound(format!("pallet `{pallet}`")))?
+			.constants
+			.iter()
+			.find(|c| c.name == name)
+			.map(|c| &c.value[..])
+			.ok_or_else(|| Error::NotFound(format!("constant `{pallet}::{name}`")))
+	}
+
+	/// Decodes a value of the type `ty` from `input` into JSON.
+	///
+	/// Composites with named fields become objects, other composites and tuples arrays, enums
+	/// the name of their variant or an object keyed by it, byte arrays hex strings and accounts
+	/// SS58 addresses. Integers wider than 64 bits are strings, as JSON numbers lose precision.
+	pub fn decode_value(&self, ty: u32, input: &mut &[u8]) -> Result<Value, Error> {
+		let resolved = self.resolve(ty)?;
+		if resolved.path.segments.last().map_or(false, |name| name == "AccountId32") {
+			return Ok(AccountId32::new(Decode::decode(input)?).to_ss58check().into())
+		}
+
+		Ok(match &resolved.type_def {
+			TypeDef::Composite(composite) => self.decode_fields(&composite.fields, input)?,
+			TypeDef::Variant(variant) => {
+				let index = u8::decode(input)?;
+				let variant = variant
+					.variants
+					.iter()
+					.find(|v| v.index == index)
+					.ok_or_else(|| Error::Codec("unknown enum variant".into()))?;
+				match (resolved.path.segments.last().map(String::as_str), &variant.fields[..]) {
+					(Some("Option"), []) => Value::Null,
+					(Some("Option"), [some]) => self.decode_value(some.ty.id, input)?,
+					(_, []) => variant.name.clone().into(),
+					(_, fields) => {
+						let mut object = Map::new();
+						object.insert(variant.name.clone(), self.decode_fields(fields, input)?);
+						object.into()
+					},
+				}
+			},
+			TypeDef::Sequence(sequence) => {
+				let len = Compact::<u32>::decode(input)?.0;
+				self.decode_sequence(sequence.type_param.id, len, input)?
+			},
+			TypeDef::Array(array) => self.decode_sequence(array.type_param.id, array.len, input)?,
+			TypeDef::Tuple(tuple) if tuple.fields.is_empty() => Value::Null,
+			TypeDef::Tuple(tuple) => tuple
+				.fields
+				.iter()
+				.map(|ty| self.decode_value(ty.id, input))
+				.collect::<Result<Vec<_>, _>>()?
+				.into(),
+			TypeDef::Primitive(primitive) => decode_primitive(primitive, input)?,
+			TypeDef::Compact(compact) => self.decode_compact(compact.type_param.id, input)?,
+			TypeDef::BitSequence(_) => return Err(Error::Unsupported("bit sequence".into())),
+		})
+	}
+
+	fn decode_fields(
+		&self,
+		fields: &[Field<PortableForm>],
+		input: &mut &[u8],
+	) -> Result<Value, Error> {
+		match fields {
+			[field] if field.name.is_none() => self.decode_value(field.ty.id, input),
+			_ if fields.iter().all(|field| field.name.is_some()) => {
+				let mut object = Map::new();
+				for field in fields {
+					let name = field.name.clone().expect("all fields are named; qed");
+					object.insert(name, self.decode_value(field.ty.id, input)?);
+				}
+				Ok(object.into())
+			},
+			_ => Ok(fields
+				.iter()
+				.map(|field| self.decode_value(field.ty.id, input))
+				.collect::<Result<Vec<_>, _>>()?
+				.into()),
+		}
+	}
+
+	fn decode_sequence(&self, ty: u32, len: u32, input: &mut &[u8]) -> Result<Value, Error> {
+		if let TypeDef::Primitive(TypeDefPrimitive::U8) = self.resolve(ty)?.type_def {
+			if input.len() < len as usize {
+				return Err(Error::Codec("not enough data to fill the byte sequence".into()))
+			}
+			let mut bytes = vec![0; len as usize];
+			codec::Input::read(input, &mut bytes)?;
+			return Ok(to_hex(&bytes, false).into())
+		}
+		Ok((0..len)
+			.map(|_| self.decode_value(ty, input))
+			.collect::<Result<Vec<_>, _>>()?
+			.into())
+	}
+
+	fn decode_compact(&self, ty: u32, input: &mut &[u8]) -> Result<Value, Error> {
+		let resolved = self.resolve(ty)?;
+		Ok(match &resolved.type_def {
+			TypeDef::Primitive(TypeDefPrimitive::U8) => Compact::<u8>::decode(input)?.0.into(),
+			TypeDef::Primitive(TypeDefPrimitive::U16) => Compact::<u16>::decode(input)?.0.into(),
+			TypeDef::Primitive(TypeDefPrimitive::U32) => Compact::<u32>::decode(input)?.0.into(),
+			TypeDef::Primitive(TypeDefPrimitive::U64) => Compact::<u64>::decode(input)?.0.into(),
+			TypeDef::Primitive(TypeDefPrimitive::U128) =>
+				Compact::<u128>::decode(input)?.0.to_string().into(),
+			// `#[codec(compact)]` on a single field wrapper, e.g. `Perbill`.
+			TypeDef::Composite(composite) if composite.fields.len() == 1 =>
+				self.decode_compact(composite.fields[0].ty.id, input)?,
+			_ => return Err(Error::Unsupported(format!("compact encoding of type {ty}"))),
+		})
+	}
+
+	/// Encodes a storage key argument of the type `ty`, see [`Self::storage`].
+	fn encode_arg(&self, ty: u32, arg: &str) -> Result<Vec<u8>, Error> {
+		if arg.starts_with("0x") {
+			return from_hex(arg).map_err(|e| Error::InvalidKey(format!("`{arg}`: {e}")))
+		}
+		let invalid =
+			|expected: &str| Error::InvalidKey(format!("expected {expected}, found `{arg}`"));
+
+		let resolved = self.resolve(ty)?;
+		if resolved.path.segments.last().map_or(false, |name| name == "AccountId32") {
+			return AccountId32::from_ss58check(arg)
+				.map(|account| account.encode())
+				.map_err(|_| invalid("an SS58 address"))
+		}
+		Ok(match &resolved.type_def {
+			TypeDef::Primitive(primitive) => match primitive {
+				TypeDefPrimitive::Bool =>
+					arg.parse::<bool>().map_err(|_| invalid("a bool"))?.encode(),
+				TypeDefPrimitive::Str => arg.encode(),
+				TypeDefPrimitive::U8 => arg.parse::<u8>().map_err(|_| invalid("a u8"))?.encode(),
+				TypeDefPrimitive::U16 => arg.parse::<u16>().map_err(|_| invalid("a u16"))?.encode(),
+				TypeDefPrimitive::U32 => arg.parse::<u32>().map_err(|_| invalid("a u32"))?.encode(),
+				TypeDefPrimitive::U64 => arg.parse::<u64>().map_err(|_| invalid("a u64"))?.encode(),
+				TypeDefPrimitive::U128 =>
+					arg.parse::<u128>().map_err(|_| invalid("a u128"))?.encode(),
+				TypeDefPrimitive::I8 => arg.parse::<i8>().map_err(|_| invalid("an i8"))?.encode(),
+				TypeDefPrimitive::I16 =>
+					arg.parse::<i16>().map_err(|_| invalid("an i16"))?.encode(),
+				TypeDefPrimitive::I32 =>
+					arg.parse::<i32>().map_err(|_| invalid("an i32"))?.encode(),
+				TypeDefPrimitive::I64 =>
+					arg.parse::<i64>().map_err(|_| invalid("an i64"))?.encode(),
+				TypeDefPrimitive::I128 =>
+					arg.parse::<i128>().map_err(|_| invalid("an i128"))?.encode(),
+				_ => return Err(invalid("the SCALE encoding in hex")),
+			},
+			TypeDef::Composite(composite) if composite.fields.len() == 1 =>
+				self.encode_arg(composite.fields[0].ty.id, arg)?,
+			_ => return Err(invalid("the SCALE encoding in hex")),
+		})
+	}
+
+	/// Decodes an encoded extrinsic, e.g. an `OpaqueExtrinsic` of a block, into JSON.
+	pub fn decode_extrinsic(&self, mut encoded: &[u8]) -> Result<DecodedExtrinsic, Error> {
+		let extrinsic = Vec::<u8>::decode(&mut encoded)?;
+		let mut input = &extrinsic[..];
+		let version = u8::decode(&mut input)?;
+		if version & 0b0111_1111 != self.0.extrinsic.version {
+			return Err(Error::Unsupported(format!("extrinsic version {}", version & 0b0111_1111)))
+		}
+
+		let signature = if version & 0b1000_0000 != 0 {
+			let address = self.decode_value(self.extrinsic_param("Address")?, &mut input)?;
+			let signature = self.decode_value(self.extrinsic_param("Signature")?, &mut input)?;
+			let mut extensions = Map::new();
+			for extension in &self.0.extrinsic.signed_extensions {
+				let value = self.decode_value(extension.ty.id, &mut input)?;
+				extensions.insert(extension.identifier.clone(), value);
+			}
+			Some(ExtrinsicSignature { address, signature, extensions })
+		} else {
+			None
+		};
+		let call = self.decode_value(self.extrinsic_param("Call")?, &mut input)?;
+
+		Ok(DecodedExtrinsic { signature, call })
+	}
+
+	/// The type of the generic parameter `name` of the runtime's `UncheckedExtrinsic`.
+	fn extrinsic_param(&self, name: &str) -> Result<u32, Error> {
+		self.resolve(self.0.extrinsic.ty.id)?
+			.type_params
+			.iter()
+			.find(|param| param.name == name)
+			.and_then(|param| param.ty.as_ref())
+			.map(|ty| ty.id)
+			.ok_or_else(|| Error::NotFound(format!("extrinsic parameter `{name}`")))
+	}
+
+	fn resolve(&self, ty: u32) -> Result<&scale_info::Type<PortableForm>, Error> {
+		self.0.types.resolve(ty).ok_or_else(|| Error::NotFound(format!("type {ty}")))
+	}
+}
+
+/// An extrinsic decoded by [`Metadata::decode_extrinsic`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedExtrinsic {
+	/// The signature of signed extrinsics.
+	pub signature: Option<ExtrinsicSignature>,
+	/// The dispatched call, keyed by the name of its pallet and of the call.
+	pub call: Value,
+}
+
+/// The signature part of a signed extrinsic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtrinsicSignature {
+	/// The address of the signer.
+	pub address: Value,
+	/// The signature.
+	pub signature: Value,
+	/// The signed extensions, keyed by their identifier, e.g. `CheckNonce`.
+	pub extensions: Map<String, Value>,
+}
+
+impl ExtrinsicSignature {
+	/// The signing account, unwrapped from its `MultiAddress` when possible.
+	pub fn signer(&self) -> &Value {
+		self.address.get("Id").unwrap_or(&self.address)
+	}
+
+	/// The nonce given with `CheckNonce`.
+	pub fn nonce(&self) -> Option<&Value> {
+		self.extensions.get("CheckNonce")
+	}
+
+	/// The tip given with `ChargeAssetTxPayment` or `ChargeTransactionPayment`, wrapped in
+	/// `ChargeSponsored` or not. Sponsored transactions give none.
+	pub fn tip(&self) -> Option<&Value> {
+		let payment = match self.extensions.get("ChargeSponsored") {
+			Some(charge) => charge.get("Unsponsored")?,
+			None => self
+				.extensions
+				.get("ChargeAssetTxPayment")
+				.or_else(|| self.extensions.get("ChargeTransactionPayment"))?,
+		};
+		// `ChargeTransactionPayment` is the tip itself.
+		Some(payment.get("tip").unwrap_or(payment))
+	}
+
+	/// The sponsor named with `ChargeSponsored` to pay the fee.
+	pub fn sponsor(&self) -> Option<&Value> {
+		self.extensions.get("ChargeSponsored")?.get("Sponsored")
+	}
+}
+
+/// A storage item, or the prefix of the entries of a map, found by [`Metadata::storage`].
+pub struct StorageQuery<'a> {
+	metadata: &'a Metadata,
+	/// The storage key of the item, or the prefix of the entries when keys are missing.
+	pub key: Vec<u8>,
+	/// Whether [`Self::key`] is a prefix, fewer keys than the map has having been given.
+	pub partial: bool,
+	hashers: Vec<StorageHasher>,
+	key_types: Vec<u32>,
+	value_type: u32,
+	default: Option<Vec<u8>>,
+}
+
+impl<'a> StorageQuery<'a> {
+	/// Decodes the value stored under the key of the query, `null` or the default value of the
+	/// item when there is none.
+	pub fn decode_value(&self, value: Option<&[u8]>) -> Result<Value, Error> {
+		match value.or(self.default.as_deref()) {
+			Some(mut value) => self.metadata.decode_value(self.value_type, &mut value),
+			None => Ok(Value::Null),
+		}
+	}
+
+	/// Decodes the keys of the entry stored under `key`, which starts with the prefix of the
+	/// query. Keys hashed with a hasher that does not keep the key are shown as their hash.
+	pub fn decode_keys(&self, key: &[u8]) -> Result<Vec<Value>, Error> {
+		let mut input = key
+			.get(32..)
+			.ok_or_else(|| Error::Codec("storage key shorter than its prefix".into()))?;
+		let mut keys = Vec::with_capacity(self.hashers.len());
+		for (hasher, ty) in self.hashers.iter().zip(&self.key_types) {
+			let (hash_len, concat) = match hasher {
+				StorageHasher::Blake2_128 | StorageHasher::Twox128 => (16, false),
+				StorageHasher::Blake2_256 | StorageHasher::Twox256 => (32, false),
+				StorageHasher::Blake2_128Concat => (16, true),
+				StorageHasher::Twox64Concat => (8, true),
+				StorageHasher::Identity => (0, true),
+			};
+			if input.len() < hash_len {
+				return Err(Error::Codec("storage key shorter than its hashes".into()))
+			}
+			let (hash, rest) = input.split_at(hash_len);
+			input = rest;
+			keys.push(if concat {
+				self.metadata.decode_value(*ty, &mut input)?
+			} else {
+				to_hex(hash, false).into()
+			});
+		}
+		Ok(keys)
+	}
+}
+
+fn hash(hasher: &StorageHasher, encoded: &[u8]) -> Vec<u8> {
+	match hasher {
+		StorageHasher::Blake2_128 => blake2_128(encoded).to_vec(),
+		StorageHasher::Blake2_256 => blake2_256(encoded).to_vec(),
+		StorageHasher::Blake2_128Concat => [&blake2_128(encoded)[..], encoded].concat(),
+		StorageHasher::Twox128 => twox_128(encoded).to_vec(),
+		StorageHasher::Twox256 => twox_256(encoded).to_vec(),
+		StorageHasher::Twox64Concat => [&twox_64(encoded)[..], encoded].concat(),
+		StorageHasher::Identity => encoded.to_vec(),
+	}
+}
+
+fn decode_primitive(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<Value, Error> {
+	Ok(match primitive {
+		TypeDefPrimitive::Bool => bool::decode(input)?.into(),
+		TypeDefPrimitive::Char => char::from_u32(u32::decode(input)?)
+			.ok_or_else(|| Error::Codec("invalid char".into()))?
+			.to_string()
+			.into(),
+		TypeDefPrimitive::Str => String::decode(input)?.into(),
+		TypeDefPrimitive::U8 => u8::decode(input)?.into(),
+		TypeDefPrimitive::U16 => u16::decode(input)?.into(),
+		TypeDefPrimitive::U32 => u32::decode(input)?.into(),
+		TypeDefPrimitive::U64 => u64::decode(input)?.into(),
+		TypeDefPrimitive::U128 => u128::decode(input)?.to_string().into(),
+		TypeDefPrimitive::I8 => i8::decode(input)?.into(),
+		TypeDefPrimitive::I16 => i16::decode(input)?.into(),
+		TypeDefPrimitive::I32 => i32::decode(input)?.into(),
+		TypeDefPrimitive::I64 => i64::decode(input)?.into(),
+		TypeDefPrimitive::I128 => i128::decode(input)?.to_string().into(),
+		TypeDefPrimitive::U256 | TypeDefPrimitive::I256 =>
+			to_hex(&<[u8; 32]>::decode(input)?, false).into(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tx::{self, ChainContext, ExtraBuilder};
+	use frame_support::{StorageMap as _, StorageValue as _};
+	use node_template_runtime::{pallet_template, Hash, Runtime};
+	use serde_json::json;
+	use sp_keyring::Sr25519Keyring;
+
+	fn metadata() -> Metadata {
+		Metadata::decode(&Runtime::metadata().encode()).unwrap()
+	}
+
+	#[test]
+	fn builds_the_keys_of_the_runtime() {
+		let metadata = metadata();
+		let alice = Sr25519Keyring::Alice.to_account_id();
+
+		let something = metadata.storage("TemplateModule", "Something", &[]).unwrap();
+		assert_eq!(something.key, pallet_template::Something::<Runtime>::hashed_key().to_vec());
+		assert!(!something.partial);
+
+		let account = metadata.storage("System", "Account", &[alice.to_ss58check()]).unwrap();
+		assert_eq!(account.key, frame_system::Account::<Runtime>::hashed_key_for(&alice));
+		let hex = metadata.storage("System", "Account", &[to_hex(alice.as_ref(), false)]).unwrap();
+		assert_eq!(hex.key, account.key);
+
+		let accounts = metadata.storage("System", "Account", &[]).unwrap();
+		assert!(accounts.partial);
+		assert!(account.key.starts_with(&accounts.key));
+		assert_eq!(accounts.decode_keys(&account.key).unwrap(), vec![json!(alice.to_ss58check())]);
+	}
+
+	#[test]
+	fn rejects_unknown_items_and_invalid_keys() {
+		let metadata = metadata();
+
+		assert!(matches!(metadata.storage("Unknown", "Something", &[]), Err(Error::NotFound(_))));
+		assert!(matches!(
+			metadata.storage("TemplateModule", "Unknown", &[]),
+			Err(Error::NotFound(_))
+		));
+		assert!(matches!(
+			metadata.storage("TemplateModule", "Something", &["1".into()]),
+			Err(Error::InvalidKey(_))
+		));
+		assert!(matches!(
+			metadata.storage("System", "Account", &["not an address".into()]),
+			Err(Error::InvalidKey(_))
+		));
+	}
+
+	#[test]
+	fn decodes_values_into_json() {
+		let metadata = metadata();
+		let alice = Sr25519Keyring::Alice.to_account_id();
+
+		let something = metadata.storage("TemplateModule", "Something", &[]).unwrap();
+		assert_eq!(something.decode_value(None).unwrap(), Value::Null);
+		assert_eq!(something.decode_value(Some(&42u32.encode())).unwrap(), json!(42));
+
+		let account = metadata.storage("System", "Account", &[alice.to_ss58check()]).unwrap();
+		let info = frame_system::AccountInfo {
+			nonce: 3u32,
+			consumers: 0u32,
+			providers: 1u32,
+			sufficients: 0u32,
+			data: pallet_balances::AccountData { free: u128::MAX, ..Default::default() },
+		};
+		let decoded = account.decode_value(Some(&info.encode())).unwrap();
+		assert_eq!(decoded["nonce"], json!(3));
+		assert_eq!(decoded["data"]["free"], json!(u128::MAX.to_string()));
+		// Missing accounts decode to the default value of the item.
+		assert_eq!(account.decode_value(None).unwrap()["data"]["free"], json!("0"));
+	}
+
+	#[test]
+	fn decodes_extrinsics_into_json() {
+		let metadata = metadata();
+		let alice = Sr25519Keyring::Alice;
+		let extra = ExtraBuilder::new(7).tip(100);
+		let xt = tx::sign(
+			pallet_template::Call::do_something { something: 42 },
+			&alice.pair(),
+			&extra,
+			&ChainContext::native(Hash::repeat_byte(1)),
+		);
+
+		let decoded = metadata.decode_extrinsic(&xt.encode()).unwrap();
+		assert_eq!(
+			decoded.call,
+			json!({ "TemplateModule": { "do_something": { "something": 42 } } })
+		);
+		let signature = decoded.signature.unwrap();
+		assert_eq!(signature.signer(), &json!(alice.to_account_id().to_ss58check()));
+		assert_eq!(signature.nonce(), Some(&json!(7)));
+		assert_eq!(signature.tip(), Some(&json!("100")));
+		assert_eq!(signature.sponsor(), None);
+
+		// Sponsored transactions give no tip.
+		let bob = Sr25519Keyring::Bob.to_account_id();
+		let xt = tx::sign(
+			pallet_template::Call::do_something { something: 42 },
+			&alice.pair(),
+			&ExtraBuilder::new(8).sponsor(Some(bob.clone())),
+			&ChainContext::native(Hash::repeat_byte(1)),
+		);
+		let signature = metadata.decode_extrinsic(&xt.encode()).unwrap().signature.unwrap();
+		assert_eq!(signature.sponsor(), Some(&json!(bob.to_ss58check())));
+		assert_eq!(signature.tip(), None);
+
+		let call: node_template_runtime::RuntimeCall = pallet_template::Call::cause_error {}.into();
+		let unsigned = node_template_runtime::UncheckedExtrinsic::new_unsigned(call);
+		let decoded = metadata.decode_extrinsic(&unsigned.encode()).unwrap();
+		assert_eq!(decoded.signature, None);
+		assert_eq!(decoded.call, json!({ "TemplateModule": "cause_error" }));
+	}
+}
@@ -0,0 +1,296 @@
+//! Tip suggestions reaching a target probability of inclusion in the next block.
+//!
+//! The transaction pool orders ready transactions by priority, which `ChargeTransactionPayment`
+//! derives from the tip, the fee and how many transactions like this one would fit in a block.
+//! The next block includes the ready transactions with the highest priorities, as many as fit.
+//! How many fit is read from recent blocks: a block whose normal weight or length is nearly
+//! exhausted held as many normal extrinsics as fit, while a block that was not full included all
+//! the ready ones and bounds nothing. The suggested tip is the smallest one giving the transaction
+//! a priority above enough of the pool to be included in the target share of recent blocks.
+//! Other signed extensions add to the priority of some calls, see [`priority_boost`].
+
+use frame_support::{
+	dispatch::{DispatchClass, DispatchInfo, Pays},
+	traits::Get,
+};
+use node_template_runtime::{pallet_template, Balance, Runtime, RuntimeCall, Weight};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+
+use crate::utilization::BlockUtilization;
+
+/// The share of its maximum weight or length from which a block counts as full.
+const FULL: f64 = 0.9;
+
+/// The suggested tip of a transaction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TipEstimate {
+	/// The suggested tip.
+	#[serde(with = "balance")]
+	pub tip: Balance,
+	/// The fee of the transaction without the tip.
+	#[serde(with = "balance")]
+	pub partial_fee: Balance,
+	/// The priority of the transaction with the suggested tip.
+	pub priority: TransactionPriority,
+	/// The number of ready transactions in the pool with at least this priority.
+	pub ahead: u32,
+	/// The number of ready transactions in the pool.
+	pub pool: u32,
+	/// The number of recent blocks the estimate is based on.
+	pub blocks: u32,
+	/// The number of these blocks that were full.
+	pub full_blocks: u32,
+	/// The share of recent blocks that would have included the transaction with the suggested
+	/// tip, between 0 and 1.
+	pub probability: f64,
+}
+
+/// The priority `ChargeTransactionPayment` gives to a transaction of `weight`, `class` and `len`
+/// paying `fee` and `tip`.
+pub fn priority(
+	weight: Weight,
+	class: DispatchClass,
+	len: u32,
+	fee: Balance,
+	tip: Balance,
+) -> TransactionPriority {
+	let info = DispatchInfo { weight, class, pays_fee: Pays::Yes };
+	ChargeTransactionPayment::<Runtime>::get_priority(&info, len as usize, tip, fee + tip)
+}
+
+/// The priority the signed extensions of the runtime add to the one of the fee of a transaction
+/// calling `call`: `CheckTemplateRateLimit` boosts the calls of `TemplateModule`.
+pub fn priority_boost(call: &RuntimeCall) -> TransactionPriority {
+	match call {
+		RuntimeCall::TemplateModule(_) =>
+			<Runtime as pallet_template::Config>::PriorityBoost::get(),
+		_ => 0,
+	}
+}
+
+/// Suggests the smallest tip giving a transaction of `info` and `len`, paying `partial_fee`
+/// without tip and with a priority raised by `boost`, a chance of at least `probability` to be
+/// included in the next block.
+///
+/// `recent` are the recent blocks the capacity of a block is estimated from, those flagged as
+/// undecodable left out, and `pool` the priorities of the ready transactions in the pool.
+pub fn estimate_tip(
+	info: &DispatchInfo,
+	len: u32,
+	partial_fee: Balance,
+	boost: TransactionPriority,
+	recent: &[BlockUtilization],
+	pool: &[TransactionPriority],
+	probability: f64,
+) -> TipEstimate {
+	let mut pool = pool.to_vec();
+	pool.sort_unstable_by(|a, b| b.cmp(a));
+	let recent: Vec<_> = recent.iter().filter(|block| !block.undecodable).collect();
+	let mut capacities: Vec<u32> = recent.iter().copied().filter_map(capacity).collect();
+	capacities.sort_unstable_by(|a, b| b.cmp(a));
+	let unbounded = recent.len() - capacities.len();
+
+	// The most transactions that may be ahead in the pool for the target share of blocks to
+	// include this one: a full block with a capacity of `c` includes it with at most `c - 1`.
+	let needed = (probability.clamp(0.0, 1.0) * recent.len() as f64).ceil() as usize;
+	let max_ahead = match needed.checked_sub(unbounded) {
+		None | Some(0) => None,
+		Some(bounded) => Some(capacities[bounded - 1].saturating_sub(1) as usize),
+	};
+
+	let priority_with =
+		|tip| priority(info.weight, info.class, len, partial_fee, tip).saturating_add(boost);
+	let tip = match max_ahead.and_then(|max_ahead| pool.get(max_ahead)) {
+		// Few enough transactions compete whatever the tip.
+		None => 0,
+		// The smallest tip whose priority is above the one of the first transaction that must
+		// not be ahead, the priority growing with the tip.
+		Some(&threshold) => {
+			let (mut low, mut high) = (0, Balance::from(u64::MAX));
+			while low < high {
+				let mid = low + (high - low) / 2;
+				if priority_with(mid) > threshold {
+					high = mid;
+				} else {
+					low = mid + 1;
+				}
+			}
+			low
+		},
+	};
+
+	let priority = priority_with(tip);
+	let ahead = pool.iter().take_while(|&&other| other >= priority).count();
+	let included = unbounded + capacities.iter().filter(|&&c| c as usize > ahead).count();
+	TipEstimate {
+		tip,
+		partial_fee,
+		priority,
+		ahead: ahead as u32,
+		pool: pool.len() as u32,
+		blocks: recent.len() as u32,
+		full_blocks: capacities.len() as u32,
+		probability: match recent.len() {
+			0 => 1.0,
+			blocks => included as f64 / blocks as f64,
+		},
+	}
+}
+
+/// The number of normal extrinsics `block` held if it was full, `None` if it was not.
+fn capacity(block: &BlockUtilization) -> Option<u32> {
+	let normal = &block.normal;
+	let max_weight = normal.max_weight.unwrap_or(block.max_block);
+	let share = |used: u64, max: u64| used as f64 / max.max(1) as f64;
+	let full = share(normal.weight.ref_time(), max_weight.ref_time()) >= FULL ||
+		share(normal.weight.proof_size(), max_weight.proof_size()) >= FULL ||
+		share(normal.length.into(), normal.max_length.into()) >= FULL;
+	full.then_some(normal.extrinsics)
+}
+
+/// Serializes balances as strings, which JSON numbers cannot represent in full.
+mod balance {
+	use node_template_runtime::Balance;
+	use serde::{Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(value: &Balance, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&value.to_string())
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Balance, D::Error> {
+		String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utilization::ClassUtilization;
+	use node_template_runtime::{BlockLength, BlockWeights, Hash};
+
+	const WEIGHT: Weight = Weight::from_parts(100_000_000, 1_000);
+	const LEN: u32 = 150;
+	const FEE: Balance = 1_000_000;
+
+	/// A block with `extrinsics` normal extrinsics, full or not.
+	fn block(extrinsics: u32, full: bool) -> BlockUtilization {
+		let weights = BlockWeights::get();
+		let max_weight = weights.get(DispatchClass::Normal).max_total;
+		let weight = match full {
+			true => max_weight.unwrap_or(weights.max_block),
+			false => Weight::zero(),
+		};
+		BlockUtilization {
+			number: 1,
+			hash: Hash::zero(),
+			max_block: weights.max_block,
+			normal: ClassUtilization {
+				weight,
+				max_weight,
+				extrinsics,
+				length: 0,
+				max_length: *BlockLength::get().max.get(DispatchClass::Normal),
+			},
+			operational: Default::default(),
+			mandatory: Default::default(),
+			top_calls: vec![],
+			undecodable: false,
+		}
+	}
+
+	fn estimate_boosted(
+		boost: TransactionPriority,
+		recent: &[BlockUtilization],
+		pool: &[u64],
+		probability: f64,
+	) -> TipEstimate {
+		let info = DispatchInfo { weight: WEIGHT, ..Default::default() };
+		estimate_tip(&info, LEN, FEE, boost, recent, pool, probability)
+	}
+
+	fn estimate(recent: &[BlockUtilization], pool: &[u64], probability: f64) -> TipEstimate {
+		estimate_boosted(0, recent, pool, probability)
+	}
+
+	#[test]
+	fn no_tip_without_congestion() {
+		let pool: Vec<_> = (1..100).collect();
+
+		let suggestion = estimate(&[block(3, false), block(5, false)], &pool, 0.9);
+
+		assert_eq!(suggestion.tip, 0);
+		assert_eq!(suggestion.full_blocks, 0);
+		assert_eq!(suggestion.probability, 1.0);
+		assert_eq!(estimate(&[], &pool, 1.0).tip, 0);
+	}
+
+	#[test]
+	fn tip_outbids_the_transactions_that_fit() {
+		// Each full block held 3 transactions; 5 higher priority transactions compete.
+		let base = priority(WEIGHT, DispatchClass::Normal, LEN, FEE, 0);
+		let pool: Vec<_> = (1..=5).map(|i| base + i * 1_000_000).collect();
+		let recent = [block(3, true), block(3, true)];
+
+		let suggestion = estimate(&recent, &pool, 1.0);
+
+		// Two transactions at most may be ahead: the third highest priority must be beaten.
+		assert!(suggestion.tip > 0);
+		assert!(suggestion.priority > pool[2]);
+		assert!(priority(WEIGHT, DispatchClass::Normal, LEN, FEE, suggestion.tip - 1) <= pool[2]);
+		assert_eq!((suggestion.ahead, suggestion.pool, suggestion.full_blocks), (2, 5, 2));
+		assert_eq!(suggestion.probability, 1.0);
+	}
+
+	#[test]
+	fn boosted_calls_need_smaller_tips() {
+		let base = priority(WEIGHT, DispatchClass::Normal, LEN, FEE, 0);
+		let pool: Vec<_> = (1..=5).map(|i| base + i * 1_000_000).collect();
+		let recent = [block(3, true), block(3, true)];
+
+		// A boost above the third highest priority is enough on its own.
+		let boost = pool[2] - base + 1;
+		let suggestion = estimate_boosted(boost, &recent, &pool, 1.0);
+		assert_eq!((suggestion.tip, suggestion.priority), (0, base + boost));
+		assert!(estimate_boosted(boost - 1, &recent, &pool, 1.0).tip > 0);
+
+		let template = RuntimeCall::TemplateModule(pallet_template::Call::cause_error {});
+		let remark = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+		assert_eq!(priority_boost(&template), node_template_runtime::TemplatePriorityBoost::get());
+		assert_eq!(priority_boost(&remark), 0);
+	}
+
+	#[test]
+	fn lower_probability_counts_blocks_that_were_not_full() {
+		let base = priority(WEIGHT, DispatchClass::Normal, LEN, FEE, 0);
+		let pool: Vec<_> = (1..=5).map(|i| base + i * 1_000_000).collect();
+		let recent = [block(3, true), block(10, false)];
+
+		assert_eq!(estimate(&recent, &pool, 0.5).tip, 0);
+		assert_eq!(estimate(&recent, &pool, 0.5).probability, 0.5);
+		assert!(estimate(&recent, &pool, 0.9).tip > 0);
+	}
+
+	#[test]
+	fn leaves_undecodable_blocks_out() {
+		let base = priority(WEIGHT, DispatchClass::Normal, LEN, FEE, 0);
+		let pool: Vec<_> = (1..=5).map(|i| base + i * 1_000_000).collect();
+		let undecodable = BlockUtilization { undecodable: true, ..block(0, true) };
+
+		let suggestion = estimate(&[undecodable, block(10, false)], &pool, 1.0);
+
+		assert_eq!((suggestion.tip, suggestion.blocks, suggestion.full_blocks), (0, 1, 0));
+	}
+
+	#[test]
+	fn balances_serialize_as_strings() {
+		let suggestion = estimate(&[], &[], 1.0);
+		let json = serde_json::to_value(&suggestion).unwrap();
+
+		assert_eq!(json["tip"], "0");
+		assert_eq!(json["partialFee"], FEE.to_string());
+		assert_eq!(serde_json::from_value::<TipEstimate>(json).unwrap(), suggestion);
+	}
+}
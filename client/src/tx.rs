@@ -0,0 +1,234 @@
+//! Offline construction and signing of transactions.
+//!
+//! Any pallet call converts into a [`RuntimeCall`], so every dispatchable of the runtime can be
+//! signed with [`sign`], e.g. `pallet_template::Call::do_something { something: 42 }`.
+
+use codec::Encode;
+use node_template_runtime::{
+	pallet_sponsorship, pallet_template, AccountId, AssetId, Balance, BlockHashCount, BlockNumber,
+	Hash, Index, Runtime, RuntimeCall, Signature, SignedExtra, SignedPayload, UncheckedExtrinsic,
+};
+use sp_core::Pair;
+use sp_runtime::{
+	generic::Era,
+	traits::{IdentifyAccount, SignedExtension},
+	MultiSigner,
+};
+
+/// The chain data that every transaction commits to through its `SignedExtra`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainContext {
+	/// The hash of the genesis block.
+	pub genesis_hash: Hash,
+	/// The `spec_version` of the runtime the transaction is built for.
+	pub spec_version: u32,
+	/// The `transaction_version` of the runtime the transaction is built for.
+	pub transaction_version: u32,
+}
+
+impl ChainContext {
+	/// The context of a chain running the native runtime of this workspace.
+	pub fn native(genesis_hash: Hash) -> Self {
+		Self {
+			genesis_hash,
+			spec_version: node_template_runtime::VERSION.spec_version,
+			transaction_version: node_template_runtime::VERSION.transaction_version,
+		}
+	}
+}
+
+/// The block a mortal transaction is anchored to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+	/// The number of the block.
+	pub number: BlockNumber,
+	/// The hash of the block.
+	pub hash: Hash,
+}
+
+/// Builder of the [`SignedExtra`] tuple of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtraBuilder {
+	nonce: Index,
+	tip: Balance,
+	asset_id: Option<AssetId>,
+	sponsor: Option<AccountId>,
+	mortality: Option<(u64, Checkpoint)>,
+}
+
+impl ExtraBuilder {
+	/// An immortal transaction with the given nonce, paying fees in the native balance.
+	pub fn new(nonce: Index) -> Self {
+		Self { nonce, tip: 0, asset_id: None, sponsor: None, mortality: None }
+	}
+
+	/// Tips the block author with `tip`.
+	pub fn tip(mut self, tip: Balance) -> Self {
+		self.tip = tip;
+		self
+	}
+
+	/// Pays the fee and the tip in the asset `asset_id` instead of the native balance.
+	pub fn asset_id(mut self, asset_id: Option<AssetId>) -> Self {
+		self.asset_id = asset_id;
+		self
+	}
+
+	/// Has `sponsor` pay the fee, in the native balance: a sponsored transaction carries neither
+	/// the tip nor the asset.
+	pub fn sponsor(mut self, sponsor: Option<AccountId>) -> Self {
+		self.sponsor = sponsor;
+		self
+	}
+
+	/// Makes the transaction valid for `period` blocks after `checkpoint` only.
+	pub fn mortal(mut self, period: u64, checkpoint: Checkpoint) -> Self {
+		self.mortality = Some((period, checkpoint));
+		self
+	}
+
+	/// The nonce of the transaction.
+	pub fn nonce(&self) -> Index {
+		self.nonce
+	}
+
+	/// Builds the signed extensions, in the order of the runtime's [`SignedExtra`].
+	pub fn build(&self) -> SignedExtra {
+		let era = match self.mortality {
+			Some((period, checkpoint)) => Era::mortal(period, checkpoint.number.into()),
+			None => Era::Immortal,
+		};
+		(
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(era),
+			frame_system::CheckNonce::<Runtime>::from(self.nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_template::CheckTemplateRateLimit::<Runtime>::new(),
+			match &self.sponsor {
+				Some(sponsor) => pallet_sponsorship::ChargeSponsored::Sponsored(sponsor.clone()),
+				None => pallet_sponsorship::ChargeSponsored::<Runtime, _>::Unsponsored(
+					pallet_asset_tx_payment::ChargeAssetTxPayment::<Runtime>::from(
+						self.tip,
+						self.asset_id,
+					),
+				),
+			}
+			.into(),
+		)
+	}
+
+	/// The data the runtime adds to the signed payload for the extensions of [`Self::build`].
+	pub fn additional_signed(
+		&self,
+		context: &ChainContext,
+	) -> <SignedExtra as SignedExtension>::AdditionalSigned {
+		let era_hash = match self.mortality {
+			Some((_, checkpoint)) => checkpoint.hash,
+			None => context.genesis_hash,
+		};
+		(
+			(),
+			context.spec_version,
+			context.transaction_version,
+			context.genesis_hash,
+			era_hash,
+			(),
+			(),
+			(),
+			(),
+		)
+	}
+}
+
+/// The mortality period used when none is given: half the number of block hashes the runtime
+/// keeps, rounded to a power of two.
+pub fn default_period() -> u64 {
+	BlockHashCount::get().checked_next_power_of_two().map(|c| c / 2).unwrap_or(2) as u64
+}
+
+/// Signs `call` with `signer`.
+pub fn sign<P>(
+	call: impl Into<RuntimeCall>,
+	signer: &P,
+	extra: &ExtraBuilder,
+	context: &ChainContext,
+) -> UncheckedExtrinsic
+where
+	P: Pair,
+	Signature: From<P::Signature>,
+	MultiSigner: From<P::Public>,
+{
+	let call = call.into();
+	let signed_extra = extra.build();
+	let payload = SignedPayload::from_raw(
+		call.clone(),
+		signed_extra.clone(),
+		extra.additional_signed(context),
+	);
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let account = MultiSigner::from(signer.public()).into_account();
+
+	UncheckedExtrinsic::new_signed(call, account.into(), signature.into(), signed_extra)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use node_template_runtime::Address;
+	use sp_keyring::Sr25519Keyring;
+	use sp_runtime::{generic, traits::Verify};
+
+	/// The layout of the runtime's extrinsics, with or without the `evm` feature.
+	type GenericExtrinsic =
+		generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
+
+	#[test]
+	fn signs_the_payload_the_runtime_checks() {
+		let context = ChainContext::native(Hash::repeat_byte(1));
+		let checkpoint = Checkpoint { number: 10, hash: Hash::repeat_byte(2) };
+		let extra = ExtraBuilder::new(3)
+			.tip(5)
+			.asset_id(Some(7))
+			.sponsor(Some(Sr25519Keyring::Bob.to_account_id()))
+			.mortal(default_period(), checkpoint);
+		let call: RuntimeCall = pallet_template::Call::do_something { something: 42 }.into();
+
+		let xt = sign(call.clone(), &Sr25519Keyring::Alice.pair(), &extra, &context);
+		let xt = GenericExtrinsic::decode(&mut &xt.encode()[..]).unwrap();
+		let (address, signature, signed_extra) = xt.signature.unwrap();
+
+		assert_eq!(address, Address::Id(Sr25519Keyring::Alice.to_account_id()));
+		assert_eq!(signed_extra.encode(), extra.build().encode());
+		let payload = SignedPayload::from_raw(
+			call,
+			signed_extra,
+			(
+				(),
+				context.spec_version,
+				context.transaction_version,
+				context.genesis_hash,
+				checkpoint.hash,
+				(),
+				(),
+				(),
+				(),
+			),
+		);
+		assert!(payload.using_encoded(|payload| {
+			signature.verify(payload, &Sr25519Keyring::Alice.to_account_id())
+		}));
+	}
+
+	#[test]
+	fn immortal_transactions_commit_to_genesis() {
+		let context = ChainContext::native(Hash::repeat_byte(1));
+		let extra = ExtraBuilder::new(0);
+
+		assert_eq!(extra.additional_signed(&context).4, context.genesis_hash);
+		assert_eq!(extra.build().4, frame_system::CheckEra::from(Era::Immortal));
+	}
+}
@@ -0,0 +1,353 @@
+//! How full blocks are, per dispatch class, against the limits of the runtime.
+//!
+//! The consumed weight of a class is the one `frame_system` accounts for in `System::BlockWeight`,
+//! which includes the base weight of the block and of each extrinsic. The weight of a single
+//! extrinsic is the actual weight of its dispatch, read from its `ExtrinsicSuccess` or
+//! `ExtrinsicFailed` event, and its length the length of its encoding in the block.
+//!
+//! Events, calls and limits are decoded with the metadata of the runtime that built the block, so
+//! that the blocks of older runtimes are covered as well.
+
+use codec::Decode;
+use frame_support::dispatch::{DispatchClass, PerDispatchClass};
+use frame_system::limits::{BlockLength, BlockWeights};
+use node_template_runtime::{BlockNumber, Hash, Weight};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_core::twox_128;
+
+use crate::metadata::{Error, Metadata};
+
+/// The storage key of `System::BlockWeight`.
+pub fn block_weight_key() -> Vec<u8> {
+	[twox_128(b"System"), twox_128(b"BlockWeight")].concat()
+}
+
+/// The utilization of a block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockUtilization {
+	/// The number of the block.
+	pub number: BlockNumber,
+	/// The hash of the block.
+	pub hash: Hash,
+	/// The maximum weight of a block, all classes together.
+	pub max_block: Weight,
+	/// The utilization by `Normal` extrinsics.
+	pub normal: ClassUtilization,
+	/// The utilization by `Operational` extrinsics.
+	pub operational: ClassUtilization,
+	/// The utilization by `Mandatory` extrinsics, i.e. inherents.
+	pub mandatory: ClassUtilization,
+	/// The heaviest extrinsics of the block, heaviest first.
+	pub top_calls: Vec<CallWeight>,
+	/// Whether `System::BlockWeight` or `System::Events` could not be decoded, in which case the
+	/// consumed weights, or the extrinsics and the top calls, are missing.
+	pub undecodable: bool,
+}
+
+/// The utilization of a block by the extrinsics of one dispatch class.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassUtilization {
+	/// The weight consumed, as accounted in `System::BlockWeight`.
+	pub weight: Weight,
+	/// The maximum weight of the class, `None` when it is unlimited.
+	pub max_weight: Option<Weight>,
+	/// The number of extrinsics.
+	pub extrinsics: u32,
+	/// The encoded length of the extrinsics.
+	pub length: u32,
+	/// The maximum encoded length of the extrinsics.
+	pub max_length: u32,
+}
+
+/// The weight and the length of an extrinsic.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallWeight {
+	/// The index of the extrinsic in its block.
+	pub extrinsic_index: u32,
+	/// The name of the pallet of the call, empty if the call cannot be decoded.
+	pub pallet: String,
+	/// The name of the call, empty if the call cannot be decoded.
+	pub call: String,
+	/// The dispatch class of the call.
+	pub class: DispatchClass,
+	/// The actual weight of the dispatch.
+	pub weight: Weight,
+	/// The encoded length of the extrinsic.
+	pub length: u32,
+}
+
+impl BlockUtilization {
+	/// Computes the utilization of a block from its encoded `extrinsics`, the value of
+	/// `System::BlockWeight` and of `System::Events` in its state, keeping the `top` heaviest
+	/// calls.
+	///
+	/// `metadata` is the one of the runtime that built the block, which the limits are read from.
+	/// A state that does not decode with it flags the block as [`Self::undecodable`] rather than
+	/// failing, only missing limits do.
+	pub fn compute(
+		metadata: &Metadata,
+		number: BlockNumber,
+		hash: Hash,
+		extrinsics: &[Vec<u8>],
+		block_weight: Option<&[u8]>,
+		events: Option<&[u8]>,
+		top: usize,
+	) -> Result<Self, Error> {
+		let weights = BlockWeights::decode(&mut metadata.constant("System", "BlockWeights")?)?;
+		let lengths = BlockLength::decode(&mut metadata.constant("System", "BlockLength")?)?;
+		let consumed = block_weight
+			.map(|mut encoded| PerDispatchClass::<Weight>::decode(&mut encoded))
+			.transpose();
+		let dispatched = dispatch_infos(metadata, events);
+		let undecodable = consumed.is_err() || dispatched.is_err();
+		let consumed = consumed.ok().flatten().unwrap_or_default();
+
+		let limits = |class: DispatchClass| ClassUtilization {
+			weight: *consumed.get(class),
+			max_weight: weights.get(class).max_total,
+			max_length: *lengths.max.get(class),
+			..Default::default()
+		};
+		let mut utilization = Self {
+			number,
+			hash,
+			max_block: weights.max_block,
+			normal: limits(DispatchClass::Normal),
+			operational: limits(DispatchClass::Operational),
+			mandatory: limits(DispatchClass::Mandatory),
+			top_calls: Vec::new(),
+			undecodable,
+		};
+
+		for (index, class, weight) in dispatched.unwrap_or_default() {
+			let Some(encoded) = extrinsics.get(index as usize) else { continue };
+			let length = encoded.len() as u32;
+			let class_utilization = utilization.class_mut(class);
+			class_utilization.extrinsics += 1;
+			class_utilization.length += length;
+
+			let (pallet, call) = metadata
+				.decode_extrinsic(encoded)
+				.ok()
+				.and_then(|xt| call_name(&xt.call))
+				.unwrap_or_default();
+			utilization.top_calls.push(CallWeight {
+				extrinsic_index: index,
+				pallet,
+				call,
+				class,
+				weight,
+				length,
+			});
+		}
+		utilization
+			.top_calls
+			.sort_by_key(|call| (std::cmp::Reverse(call.weight.ref_time()), call.extrinsic_index));
+		utilization.top_calls.truncate(top);
+
+		Ok(utilization)
+	}
+
+	/// The utilization by the extrinsics of `class`.
+	pub fn class(&self, class: DispatchClass) -> &ClassUtilization {
+		match class {
+			DispatchClass::Normal => &self.normal,
+			DispatchClass::Operational => &self.operational,
+			DispatchClass::Mandatory => &self.mandatory,
+		}
+	}
+
+	fn class_mut(&mut self, class: DispatchClass) -> &mut ClassUtilization {
+		match class {
+			DispatchClass::Normal => &mut self.normal,
+			DispatchClass::Operational => &mut self.operational,
+			DispatchClass::Mandatory => &mut self.mandatory,
+		}
+	}
+}
+
+/// The index, dispatch class and actual weight of the extrinsics, reported by their
+/// `ExtrinsicSuccess` or `ExtrinsicFailed` event among the encoded `events`.
+fn dispatch_infos(
+	metadata: &Metadata,
+	events: Option<&[u8]>,
+) -> Result<Vec<(u32, DispatchClass, Weight)>, Error> {
+	let records = metadata.storage("System", "Events", &[])?.decode_value(events)?;
+	Ok(records
+		.as_array()
+		.into_iter()
+		.flatten()
+		.filter_map(|record| {
+			let index = record["phase"].get("ApplyExtrinsic")?.as_u64()? as u32;
+			let event = record["event"].get("System")?;
+			let info = event
+				.get("ExtrinsicSuccess")
+				.or_else(|| event.get("ExtrinsicFailed"))?
+				.get("dispatch_info")?;
+			let class = match info["class"].as_str()? {
+				"Normal" => DispatchClass::Normal,
+				"Operational" => DispatchClass::Operational,
+				"Mandatory" => DispatchClass::Mandatory,
+				_ => return None,
+			};
+			let weight = Weight::from_parts(
+				info["weight"]["ref_time"].as_u64()?,
+				info["weight"]["proof_size"].as_u64()?,
+			);
+			Some((index, class, weight))
+		})
+		.collect())
+}
+
+/// The names of the pallet and of the call of a call decoded into JSON.
+fn call_name(call: &Value) -> Option<(String, String)> {
+	let (pallet, call) = call.as_object()?.iter().next()?;
+	let call = match call {
+		Value::String(name) => name.clone(),
+		Value::Object(call) => call.keys().next()?.clone(),
+		_ => return None,
+	};
+	Some((pallet.clone(), call))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::events::EventRecord;
+	use codec::Encode;
+	use frame_support::dispatch::{DispatchInfo, Pays};
+	use frame_system::Phase;
+	use node_template_runtime::{
+		BlockLength, BlockWeights, Runtime, RuntimeCall, SystemCall, TimestampCall,
+		UncheckedExtrinsic,
+	};
+	use sp_runtime::DispatchError;
+
+	fn metadata() -> Metadata {
+		Metadata::decode(&Runtime::metadata().encode()).unwrap()
+	}
+
+	fn record(
+		index: u32,
+		event: frame_system::Event<node_template_runtime::Runtime>,
+	) -> EventRecord {
+		EventRecord { phase: Phase::ApplyExtrinsic(index), event: event.into(), topics: vec![] }
+	}
+
+	fn info(ref_time: u64, class: DispatchClass) -> DispatchInfo {
+		DispatchInfo { weight: Weight::from_parts(ref_time, 0), class, pays_fee: Pays::Yes }
+	}
+
+	fn unsigned(call: impl Into<RuntimeCall>) -> Vec<u8> {
+		UncheckedExtrinsic::new_unsigned(call.into()).encode()
+	}
+
+	#[test]
+	fn computes_the_utilization_per_class() {
+		let extrinsics = vec![
+			unsigned(TimestampCall::set { now: 0 }),
+			unsigned(SystemCall::remark { remark: vec![0; 100] }),
+			unsigned(SystemCall::remark { remark: vec![] }),
+		];
+		let events = vec![
+			record(
+				0,
+				frame_system::Event::ExtrinsicSuccess {
+					dispatch_info: info(10, DispatchClass::Mandatory),
+				},
+			),
+			record(
+				1,
+				frame_system::Event::ExtrinsicSuccess {
+					dispatch_info: info(300, DispatchClass::Normal),
+				},
+			),
+			record(
+				2,
+				frame_system::Event::ExtrinsicFailed {
+					dispatch_error: DispatchError::BadOrigin,
+					dispatch_info: info(200, DispatchClass::Normal),
+				},
+			),
+		];
+		let mut consumed = PerDispatchClass::<Weight>::default();
+		consumed.set(Weight::from_parts(1_000, 0), DispatchClass::Normal);
+		consumed.set(Weight::from_parts(50, 0), DispatchClass::Mandatory);
+
+		let utilization = BlockUtilization::compute(
+			&metadata(),
+			7,
+			Hash::repeat_byte(1),
+			&extrinsics,
+			Some(&consumed.encode()),
+			Some(&events.encode()),
+			2,
+		)
+		.unwrap();
+
+		assert!(!utilization.undecodable);
+		assert_eq!(utilization.max_block, BlockWeights::get().max_block);
+		let normal = utilization.class(DispatchClass::Normal);
+		assert_eq!(normal.weight, Weight::from_parts(1_000, 0));
+		assert_eq!(normal.extrinsics, 2);
+		assert_eq!(normal.length, (extrinsics[1].len() + extrinsics[2].len()) as u32);
+		assert_eq!(normal.max_length, *BlockLength::get().max.get(DispatchClass::Normal));
+		assert_eq!(normal.max_weight, BlockWeights::get().get(DispatchClass::Normal).max_total);
+		let mandatory = utilization.class(DispatchClass::Mandatory);
+		assert_eq!((mandatory.weight, mandatory.extrinsics), (Weight::from_parts(50, 0), 1));
+		assert_eq!(
+			utilization.class(DispatchClass::Operational),
+			&ClassUtilization {
+				max_weight: BlockWeights::get().get(DispatchClass::Operational).max_total,
+				max_length: *BlockLength::get().max.get(DispatchClass::Operational),
+				..Default::default()
+			}
+		);
+
+		let top = &utilization.top_calls;
+		assert_eq!(top.len(), 2);
+		assert_eq!(
+			(top[0].extrinsic_index, top[0].pallet.as_str(), top[0].call.as_str()),
+			(1, "System", "remark")
+		);
+		assert_eq!((top[1].extrinsic_index, top[1].weight), (2, Weight::from_parts(200, 0)));
+	}
+
+	#[test]
+	fn empty_state_is_an_empty_block() {
+		let utilization =
+			BlockUtilization::compute(&metadata(), 0, Hash::repeat_byte(0), &[], None, None, 10)
+				.unwrap();
+
+		assert_eq!(utilization.normal.weight, Weight::zero());
+		assert!(utilization.top_calls.is_empty());
+		assert!(!utilization.undecodable);
+	}
+
+	#[test]
+	fn flags_blocks_whose_events_do_not_decode() {
+		let extrinsics = vec![unsigned(TimestampCall::set { now: 0 })];
+		let mut consumed = PerDispatchClass::<Weight>::default();
+		consumed.set(Weight::from_parts(50, 0), DispatchClass::Mandatory);
+
+		let utilization = BlockUtilization::compute(
+			&metadata(),
+			7,
+			Hash::repeat_byte(1),
+			&extrinsics,
+			Some(&consumed.encode()),
+			Some(&[4, 0xff][..]),
+			10,
+		)
+		.unwrap();
+
+		assert!(utilization.undecodable);
+		assert_eq!(utilization.mandatory.weight, Weight::from_parts(50, 0));
+		assert_eq!(utilization.mandatory.extrinsics, 0);
+		assert_eq!(utilization.max_block, BlockWeights::get().max_block);
+	}
+}
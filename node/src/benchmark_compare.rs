@@ -0,0 +1,360 @@
+//! The `benchmark-compare` subcommand, catching committed weight files drifting from benchmarks.
+//!
+//! Each configured pallet is benchmarked with `benchmark pallet` into a temporary weight file,
+//! and the weight formula of every extrinsic in it is compared with the one in the committed
+//! file: the base and per-component ref time and proof size, and the base and per-component
+//! numbers of database reads and writes. Only the first `WeightInfo` implementation of a file is
+//! read, which is `SubstrateWeight` in the files written with the pallet weight template.
+
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	fs,
+	path::PathBuf,
+};
+
+use clap::Parser;
+use frame_benchmarking_cli::PalletCmd;
+use node_template_runtime::Block;
+use sc_cli::{CliConfiguration, SharedParams, SubstrateCli};
+use tokio::runtime::Handle;
+
+use crate::service::ExecutorDispatch;
+
+/// Benchmark pallets and compare the results with their committed weight files.
+///
+/// Fails when the weight or the number of database reads or writes of an extrinsic changes by
+/// more than `--threshold` percent, printing the changes as a diff.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct BenchmarkCompareCmd {
+	/// A pallet to benchmark and its committed weight file, as `<pallet>=<path>`. May be given
+	/// several times. Every pallet of the workspace with benchmarks by default.
+	#[arg(
+		long = "weights",
+		value_name = "PALLET=PATH",
+		value_parser = parse_weights_arg,
+		default_values = [
+			"pallet_template=pallets/template/src/weights.rs",
+			"pallet_feeless=pallets/feeless/src/weights.rs",
+			"pallet_sponsorship=pallets/sponsorship/src/weights.rs",
+		]
+	)]
+	pub weights: Vec<(String, PathBuf)>,
+
+	/// The largest relative change of a term of a weight formula that is not a regression, in
+	/// percent.
+	#[arg(long, default_value_t = 10.0)]
+	pub threshold: f64,
+
+	/// Select how many samples we should take across the variable components.
+	#[arg(long, default_value_t = 50)]
+	pub steps: u32,
+
+	/// Select how many repetitions of this benchmark should run from within the wasm.
+	#[arg(long, default_value_t = 20)]
+	pub repeat: u32,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// A term of a weight formula.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Metric {
+	RefTime,
+	ProofSize,
+	Reads,
+	Writes,
+}
+
+impl std::fmt::Display for Metric {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str(match self {
+			Metric::RefTime => "ref time",
+			Metric::ProofSize => "proof size",
+			Metric::Reads => "reads",
+			Metric::Writes => "writes",
+		})
+	}
+}
+
+/// The weight formula of an extrinsic: the base value of each metric under `None`, and its
+/// increase per unit of a component under the name of the component.
+type Formula = BTreeMap<(Metric, Option<String>), u64>;
+
+impl BenchmarkCompareCmd {
+	/// Run the benchmark-compare command.
+	pub fn run(&self, cli: &impl SubstrateCli, tokio_handle: Handle) -> sc_cli::Result<()> {
+		if !cfg!(feature = "runtime-benchmarks") {
+			return Err("Runtime benchmarking wasn't enabled when building the node. \
+				You can enable it with `--features runtime-benchmarks`."
+				.into())
+		}
+
+		let mut regressions = 0;
+		for (pallet, path) in &self.weights {
+			let committed = parse_weights(&fs::read_to_string(path)?);
+			let output =
+				std::env::temp_dir().join(format!("{pallet}-weights-{}.rs", std::process::id()));
+			let mut cmd = PalletCmd::try_parse_from([
+				"pallet",
+				"--pallet",
+				pallet,
+				"--extrinsic",
+				"*",
+				"--steps",
+				&self.steps.to_string(),
+				"--repeat",
+				&self.repeat.to_string(),
+				"--execution",
+				"wasm",
+				"--wasm-execution",
+				"compiled",
+				"--output",
+				&output.to_string_lossy(),
+			])
+			.map_err(|e| e.to_string())?;
+			cmd.shared_params = self.shared_params.clone();
+			cmd.run::<Block, ExecutorDispatch>(
+				cmd.create_configuration(cli, tokio_handle.clone())?,
+			)?;
+			let benchmarked = parse_weights(&fs::read_to_string(&output)?);
+			fs::remove_file(&output)?;
+
+			regressions += self.compare(pallet, &committed, &benchmarked);
+		}
+
+		if regressions > 0 {
+			return Err(format!(
+				"{regressions} weight terms changed by more than {}%",
+				self.threshold
+			)
+			.into())
+		}
+		println!("No weight term changed by more than {}%", self.threshold);
+		Ok(())
+	}
+
+	/// Prints the terms of the extrinsics of `pallet` that changed by more than the threshold as
+	/// a diff, returning how many did.
+	fn compare(
+		&self,
+		pallet: &str,
+		committed: &BTreeMap<String, Formula>,
+		benchmarked: &BTreeMap<String, Formula>,
+	) -> usize {
+		let mut regressions = 0;
+		let extrinsics = committed.keys().chain(benchmarked.keys()).collect::<BTreeSet<_>>();
+		for extrinsic in extrinsics {
+			let (old, new) = match (committed.get(extrinsic), benchmarked.get(extrinsic)) {
+				(Some(old), Some(new)) => (old, new),
+				(Some(_), None) => {
+					println!("- {pallet}::{extrinsic}: no longer benchmarked");
+					regressions += 1;
+					continue
+				},
+				(None, _) => {
+					println!("+ {pallet}::{extrinsic}: not in the committed weights");
+					regressions += 1;
+					continue
+				},
+			};
+			let terms = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+			for term @ (metric, component) in terms {
+				let old = old.get(term).copied().unwrap_or_default();
+				let new = new.get(term).copied().unwrap_or_default();
+				let change = match (old, new) {
+					(old, new) if old == new => continue,
+					(0, _) => f64::INFINITY,
+					(old, new) => (new as f64 - old as f64) * 100.0 / old as f64,
+				};
+				if change.abs() <= self.threshold {
+					continue
+				}
+				let name = match component {
+					Some(component) => format!("{metric} per {component}"),
+					None => metric.to_string(),
+				};
+				println!("- {pallet}::{extrinsic} {name}: {old}");
+				println!("+ {pallet}::{extrinsic} {name}: {new} ({change:+.2}%)");
+				regressions += 1;
+			}
+		}
+		regressions
+	}
+}
+
+/// Reads the weight formulas of the first `WeightInfo` implementation of a weight file, by
+/// extrinsic.
+fn parse_weights(source: &str) -> BTreeMap<String, Formula> {
+	let mut formulas = BTreeMap::new();
+	let mut current: Option<(String, Formula)> = None;
+	for line in source.lines().map(str::trim) {
+		if line.starts_with("impl") && !formulas.is_empty() {
+			break
+		}
+		if let Some(signature) = line.strip_prefix("fn ").filter(|_| line.ends_with('{')) {
+			if let Some((name, formula)) = current.take() {
+				formulas.insert(name, formula);
+			}
+			let name = signature.split('(').next().unwrap_or_default();
+			current = Some((name.to_string(), Formula::new()));
+			continue
+		}
+		if line.starts_with("//") {
+			continue
+		}
+		if let Some((_, formula)) = &mut current {
+			parse_term(line, formula);
+		}
+		if line == "}" {
+			if let Some((name, formula)) = current.take() {
+				formulas.insert(name, formula);
+			}
+		}
+	}
+	formulas
+}
+
+/// Adds the terms of one line of a weight formula, such as `Weight::from_parts(9_000_000, 0)`,
+/// `.saturating_add(T::DbWeight::get().reads(1_u64))`, `reads_writes(1_u64, 1_u64)` or
+/// `.saturating_add(Weight::from_parts(1_000, 0).saturating_mul(s.into()))`.
+fn parse_term(line: &str, formula: &mut Formula) {
+	let component = line.split_once(".saturating_mul(").map(|(_, rest)| {
+		rest.trim_start_matches('(').split('.').next().unwrap_or_default().to_string()
+	});
+	let mut add = |metric, value| {
+		*formula.entry((metric, component.clone())).or_default() += value;
+	};
+	if let Some((_, args)) = line.split_once("Weight::from_parts(") {
+		let mut args = args.split(',');
+		add(Metric::RefTime, parse_number(args.next().unwrap_or_default()));
+		add(Metric::ProofSize, parse_number(args.next().unwrap_or_default()));
+	} else if let Some((_, counts)) = line.split_once(".reads_writes(") {
+		let mut counts = counts.split(',');
+		add(Metric::Reads, parse_number(counts.next().unwrap_or_default()));
+		add(Metric::Writes, parse_number(counts.next().unwrap_or_default()));
+	} else if let Some((_, count)) = line.split_once(".reads(") {
+		add(Metric::Reads, parse_number(count));
+	} else if let Some((_, count)) = line.split_once(".writes(") {
+		add(Metric::Writes, parse_number(count));
+	}
+}
+
+/// Parses the integer literal at the start of `s`, such as `(1_000_u64`.
+fn parse_number(s: &str) -> u64 {
+	s.trim_start_matches(|c: char| c == '(' || c.is_whitespace())
+		.chars()
+		.take_while(|c| c.is_ascii_digit() || *c == '_')
+		.filter(char::is_ascii_digit)
+		.collect::<String>()
+		.parse()
+		.unwrap_or_default()
+}
+
+fn parse_weights_arg(arg: &str) -> Result<(String, PathBuf), String> {
+	let (pallet, path) = arg
+		.split_once('=')
+		.ok_or_else(|| format!("Expected <pallet>=<path>, got {arg}"))?;
+	Ok((pallet.to_string(), path.into()))
+}
+
+impl CliConfiguration for BenchmarkCompareCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Weights as written by `benchmark pallet` with the pallet weight template.
+	const WEIGHTS: &str = r#"
+/// Weights for pallet_example using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Example Something (r:1 w:1)
+	/// Proof: Example Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	fn cause_error() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `32`
+		//  Estimated: `1489`
+		// Minimum execution time: 6_000_000 picoseconds.
+		Weight::from_parts(6_000_000, 1489)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Example Items (r:100 w:100)
+	/// Proof: Example Items (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	/// The range of component `n` is `[0, 100]`.
+	fn clear(n: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `69 + n * (39 ±0)`
+		//  Estimated: `1489 + n * (2523 ±0)`
+		// Minimum execution time: 7_000_000 picoseconds.
+		Weight::from_parts(7_512_345, 1489)
+			// Standard Error: 1_234
+			.saturating_add(Weight::from_parts(3_456_789, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2523).saturating_mul(n.into()))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn cause_error() -> Weight {
+		Weight::from_parts(1, 1)
+	}
+}
+"#;
+
+	fn term(metric: Metric, component: Option<&str>) -> (Metric, Option<String>) {
+		(metric, component.map(str::to_string))
+	}
+
+	#[test]
+	fn reads_the_first_implementation() {
+		let formulas = parse_weights(WEIGHTS);
+		assert_eq!(formulas.keys().collect::<Vec<_>>(), ["cause_error", "clear"]);
+		assert_eq!(
+			formulas["cause_error"],
+			Formula::from([
+				(term(Metric::RefTime, None), 6_000_000),
+				(term(Metric::ProofSize, None), 1489),
+				(term(Metric::Reads, None), 1),
+				(term(Metric::Writes, None), 1),
+			])
+		);
+	}
+
+	#[test]
+	fn reads_terms_per_component() {
+		assert_eq!(
+			parse_weights(WEIGHTS)["clear"],
+			Formula::from([
+				(term(Metric::RefTime, None), 7_512_345),
+				(term(Metric::RefTime, Some("n")), 3_456_789),
+				(term(Metric::ProofSize, None), 1489),
+				(term(Metric::ProofSize, Some("n")), 2523),
+				(term(Metric::Reads, None), 1),
+				(term(Metric::Reads, Some("n")), 1),
+				(term(Metric::Writes, Some("n")), 1),
+			])
+		);
+	}
+
+	#[test]
+	fn adds_up_the_terms_of_a_metric() {
+		let mut formula = Formula::new();
+		parse_term(".saturating_add(T::DbWeight::get().reads(2_u64))", &mut formula);
+		parse_term(".saturating_add(RocksDbWeight::get().reads(1_u64))", &mut formula);
+		parse_term(".saturating_add(T::DbWeight::get().reads_writes(1_u64, 1_u64))", &mut formula);
+		assert_eq!(
+			formula,
+			Formula::from([(term(Metric::Reads, None), 4), (term(Metric::Writes, None), 1)])
+		);
+	}
+}
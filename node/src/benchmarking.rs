@@ -1,35 +1,66 @@
 //! Setup code for [`super::command`] which would otherwise bloat that module.
 //!
-//! Should only be used for benchmarking as it may break in other contexts.
+//! Should only be used for benchmarking as it may break in other contexts. The extrinsic builders
+//! also generate the transactions of the `load-test` command, signed against a remote node.
 
 use crate::service::FullClient;
 
+use codec::Encode;
+use node_template_client::tx::{self, ChainContext, Checkpoint, ExtraBuilder};
 use node_template_runtime as runtime;
-use runtime::{AccountId, Balance, BalancesCall, SystemCall};
+use runtime::{pallet_template, AccountId, Balance, BalancesCall, SystemCall};
 use sc_cli::Result;
 use sc_client_api::BlockBackend;
-use sp_core::{Encode, Pair};
+use sc_service::Configuration;
+use sp_core::sr25519;
 use sp_inherents::{InherentData, InherentDataProvider};
 use sp_keyring::Sr25519Keyring;
-use sp_runtime::{OpaqueExtrinsic, SaturatedConversion};
+use sp_runtime::OpaqueExtrinsic;
 
 use std::{sync::Arc, time::Duration};
 
+/// The chain the builders sign extrinsics for, and the block their mortality starts at.
+pub trait SigningContext {
+	/// The chain data the extrinsics commit to.
+	fn chain(&self) -> ChainContext;
+
+	/// The block the extrinsics are anchored to, usually the best block.
+	fn checkpoint(&self) -> Checkpoint;
+}
+
+impl SigningContext for FullClient {
+	fn chain(&self) -> ChainContext {
+		let genesis_hash = self.block_hash(0).ok().flatten().expect("Genesis block exists; qed");
+		ChainContext::native(genesis_hash)
+	}
+
+	fn checkpoint(&self) -> Checkpoint {
+		let info = self.chain_info();
+		Checkpoint { number: info.best_number, hash: info.best_hash }
+	}
+}
+
 /// Generates extrinsics for the `benchmark overhead` command.
 ///
 /// Note: Should only be used for benchmarking.
-pub struct RemarkBuilder {
-	client: Arc<FullClient>,
+pub struct RemarkBuilder<C = FullClient> {
+	client: Arc<C>,
+	sender: sr25519::Pair,
 }
 
-impl RemarkBuilder {
-	/// Creates a new [`Self`] from the given client.
-	pub fn new(client: Arc<FullClient>) -> Self {
-		Self { client }
+impl<C> RemarkBuilder<C> {
+	/// Creates a new [`Self`] from the given client, signing as `//Bob`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, sender: Sr25519Keyring::Bob.pair() }
+	}
+
+	/// Signs the extrinsics as `sender` instead.
+	pub fn with_sender(self, sender: sr25519::Pair) -> Self {
+		Self { sender, ..self }
 	}
 }
 
-impl frame_benchmarking_cli::ExtrinsicBuilder for RemarkBuilder {
+impl<C: SigningContext> frame_benchmarking_cli::ExtrinsicBuilder for RemarkBuilder<C> {
 	fn pallet(&self) -> &str {
 		"system"
 	}
@@ -39,10 +70,9 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for RemarkBuilder {
 	}
 
 	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
-		let acc = Sr25519Keyring::Bob.pair();
 		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
 			self.client.as_ref(),
-			acc,
+			self.sender.clone(),
 			SystemCall::remark { remark: vec![] }.into(),
 			nonce,
 		)
@@ -55,20 +85,26 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for RemarkBuilder {
 /// Generates `Balances::TransferKeepAlive` extrinsics for the benchmarks.
 ///
 /// Note: Should only be used for benchmarking.
-pub struct TransferKeepAliveBuilder {
-	client: Arc<FullClient>,
+pub struct TransferKeepAliveBuilder<C = FullClient> {
+	client: Arc<C>,
+	sender: sr25519::Pair,
 	dest: AccountId,
 	value: Balance,
 }
 
-impl TransferKeepAliveBuilder {
-	/// Creates a new [`Self`] from the given client.
-	pub fn new(client: Arc<FullClient>, dest: AccountId, value: Balance) -> Self {
-		Self { client, dest, value }
+impl<C> TransferKeepAliveBuilder<C> {
+	/// Creates a new [`Self`] from the given client, signing as `//Bob`.
+	pub fn new(client: Arc<C>, dest: AccountId, value: Balance) -> Self {
+		Self { client, sender: Sr25519Keyring::Bob.pair(), dest, value }
+	}
+
+	/// Signs the extrinsics as `sender` instead.
+	pub fn with_sender(self, sender: sr25519::Pair) -> Self {
+		Self { sender, ..self }
 	}
 }
 
-impl frame_benchmarking_cli::ExtrinsicBuilder for TransferKeepAliveBuilder {
+impl<C: SigningContext> frame_benchmarking_cli::ExtrinsicBuilder for TransferKeepAliveBuilder<C> {
 	fn pallet(&self) -> &str {
 		"balances"
 	}
@@ -78,10 +114,9 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for TransferKeepAliveBuilder {
 	}
 
 	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
-		let acc = Sr25519Keyring::Bob.pair();
 		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
 			self.client.as_ref(),
-			acc,
+			self.sender.clone(),
 			BalancesCall::transfer_keep_alive {
 				dest: self.dest.clone().into(),
 				value: self.value.into(),
@@ -95,59 +130,77 @@ impl frame_benchmarking_cli::ExtrinsicBuilder for TransferKeepAliveBuilder {
 	}
 }
 
+/// Generates `TemplateModule::do_something` extrinsics, storing the nonce.
+///
+/// Note: Should only be used for benchmarking.
+pub struct DoSomethingBuilder<C = FullClient> {
+	client: Arc<C>,
+	sender: sr25519::Pair,
+}
+
+impl<C> DoSomethingBuilder<C> {
+	/// Creates a new [`Self`] from the given client, signing as `//Bob`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, sender: Sr25519Keyring::Bob.pair() }
+	}
+
+	/// Signs the extrinsics as `sender` instead.
+	pub fn with_sender(self, sender: sr25519::Pair) -> Self {
+		Self { sender, ..self }
+	}
+}
+
+impl<C: SigningContext> frame_benchmarking_cli::ExtrinsicBuilder for DoSomethingBuilder<C> {
+	fn pallet(&self) -> &str {
+		"template"
+	}
+
+	fn extrinsic(&self) -> &str {
+		"do_something"
+	}
+
+	fn build(&self, nonce: u32) -> std::result::Result<OpaqueExtrinsic, &'static str> {
+		let extrinsic: OpaqueExtrinsic = create_benchmark_extrinsic(
+			self.client.as_ref(),
+			self.sender.clone(),
+			pallet_template::Call::do_something { something: nonce }.into(),
+			nonce,
+		)
+		.into();
+
+		Ok(extrinsic)
+	}
+}
+
+/// Exempts `accounts` from the rate limit of `TemplateModule` in the genesis of the chain of
+/// `config`, so that the extrinsic builders can fill blocks with `do_something` calls.
+///
+/// Note: Should only be used for benchmarking.
+pub fn exempt_from_rate_limit(config: &mut Configuration, accounts: &[AccountId]) -> Result<()> {
+	let mut storage = config.chain_spec.as_storage_builder().build_storage()?;
+	for account in accounts {
+		storage.top.insert(
+			pallet_template::RateLimitExempt::<runtime::Runtime>::hashed_key_for(account),
+			().encode(),
+		);
+	}
+	config.chain_spec.set_storage(storage);
+	Ok(())
+}
+
 /// Create a transaction using the given `call`.
 ///
+/// The transaction is built like the ones of the `tx` command, with `node_template_client`.
+///
 /// Note: Should only be used for benchmarking.
 pub fn create_benchmark_extrinsic(
-	client: &FullClient,
-	sender: sp_core::sr25519::Pair,
+	client: &impl SigningContext,
+	sender: sr25519::Pair,
 	call: runtime::RuntimeCall,
 	nonce: u32,
 ) -> runtime::UncheckedExtrinsic {
-	let genesis_hash = client.block_hash(0).ok().flatten().expect("Genesis block exists; qed");
-	let best_hash = client.chain_info().best_hash;
-	let best_block = client.chain_info().best_number;
-
-	let period = runtime::BlockHashCount::get()
-		.checked_next_power_of_two()
-		.map(|c| c / 2)
-		.unwrap_or(2) as u64;
-	let extra: runtime::SignedExtra = (
-		frame_system::CheckNonZeroSender::<runtime::Runtime>::new(),
-		frame_system::CheckSpecVersion::<runtime::Runtime>::new(),
-		frame_system::CheckTxVersion::<runtime::Runtime>::new(),
-		frame_system::CheckGenesis::<runtime::Runtime>::new(),
-		frame_system::CheckEra::<runtime::Runtime>::from(sp_runtime::generic::Era::mortal(
-			period,
-			best_block.saturated_into(),
-		)),
-		frame_system::CheckNonce::<runtime::Runtime>::from(nonce),
-		frame_system::CheckWeight::<runtime::Runtime>::new(),
-		pallet_transaction_payment::ChargeTransactionPayment::<runtime::Runtime>::from(0),
-	);
-
-	let raw_payload = runtime::SignedPayload::from_raw(
-		call.clone(),
-		extra.clone(),
-		(
-			(),
-			runtime::VERSION.spec_version,
-			runtime::VERSION.transaction_version,
-			genesis_hash,
-			best_hash,
-			(),
-			(),
-			(),
-		),
-	);
-	let signature = raw_payload.using_encoded(|e| sender.sign(e));
-
-	runtime::UncheckedExtrinsic::new_signed(
-		call.clone(),
-		sp_runtime::AccountId32::from(sender.public()).into(),
-		runtime::Signature::Sr25519(signature.clone()),
-		extra.clone(),
-	)
+	let extra = ExtraBuilder::new(nonce).mortal(tx::default_period(), client.checkpoint());
+	tx::sign(call, &sender, &extra, &client.chain())
 }
 
 /// Generates inherent data for the `benchmark overhead` command.
@@ -124,6 +124,27 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+/// The well-known development account that Ethereum tooling, such as MetaMask, can import with the
+/// private key `0x5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133`.
+#[cfg(feature = "evm")]
+const DEV_EVM_ACCOUNT: &str = "f24FF3a9CF04c71Dbc94D0b566f7A27B94566cac";
+
+/// Configure the EVM accounts funded at genesis.
+#[cfg(feature = "evm")]
+fn evm_genesis_accounts(
+) -> std::collections::BTreeMap<sp_core::H160, node_template_runtime::EVMGenesisAccount> {
+	use std::str::FromStr;
+
+	let address = sp_core::H160::from_str(DEV_EVM_ACCOUNT).expect("static values are valid; qed");
+	let account = node_template_runtime::EVMGenesisAccount {
+		nonce: Default::default(),
+		balance: sp_core::U256::from(1u128 << 60),
+		storage: Default::default(),
+		code: Default::default(),
+	};
+	[(address, account)].into_iter().collect()
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
@@ -152,5 +173,10 @@ fn testnet_genesis(
 			key: Some(root_key),
 		},
 		transaction_payment: Default::default(),
+		assets: Default::default(),
+		#[cfg(feature = "evm")]
+		evm: node_template_runtime::EVMConfig { accounts: evm_genesis_accounts() },
+		#[cfg(feature = "evm")]
+		ethereum: Default::default(),
 	}
 }
@@ -7,6 +7,15 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	/// Seal a block as soon as a transaction enters the pool, instead of running Aura and
+	/// GRANDPA. Only meant for development chains.
+	#[arg(long)]
+	pub instant_seal: bool,
+
+	#[cfg(feature = "evm")]
+	#[clap(flatten)]
+	pub eth: crate::eth::EthConfiguration,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -22,14 +31,27 @@ pub enum Subcommand {
 	CheckBlock(sc_cli::CheckBlockCmd),
 
 	/// Export blocks.
-	ExportBlocks(sc_cli::ExportBlocksCmd),
+	ExportBlocks(crate::export_blocks::ExportBlocksCmd),
+
+	/// Export the GRANDPA justifications of a range of finalized blocks.
+	FinalityProof(crate::finality_proof::FinalityProofCmd),
+
+	/// Read a storage item by name and print it as JSON.
+	Query(crate::query::QueryCmd),
 
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Write the state of a block into a development chain spec run by `//Alice`.
+	ForkOff(crate::fork_off::ForkOffCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Create and restore state snapshots of finalized blocks.
+	#[command(subcommand)]
+	Snapshot(crate::snapshot::SnapshotCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
@@ -40,6 +62,15 @@ pub enum Subcommand {
 	#[command(subcommand)]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 
+	/// Report the weight and the length consumed by each dispatch class in a range of blocks.
+	Utilization(crate::utilization::UtilizationCmd),
+
+	/// Rehearse a runtime upgrade on a state snapshot, fully offline.
+	UpgradeCheck(crate::upgrade_check::UpgradeCheckCmd),
+
+	/// Benchmark pallets and compare the results with their committed weight files.
+	BenchmarkCompare(crate::benchmark_compare::BenchmarkCompareCmd),
+
 	/// Try some command against runtime state.
 	#[cfg(feature = "try-runtime")]
 	TryRuntime(try_runtime_cli::TryRuntimeCmd),
@@ -50,4 +81,10 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Sign and submit a transaction to a running node.
+	Tx(crate::tx::TxCmd),
+
+	/// Submit signed transactions to a running node at a target rate, and report how they fared.
+	LoadTest(crate::load_test::LoadTestCmd),
 }
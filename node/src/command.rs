@@ -1,8 +1,12 @@
 use crate::{
-	benchmarking::{inherent_benchmark_data, RemarkBuilder, TransferKeepAliveBuilder},
+	benchmarking::{
+		exempt_from_rate_limit, inherent_benchmark_data, DoSomethingBuilder, RemarkBuilder,
+		TransferKeepAliveBuilder,
+	},
 	chain_spec,
 	cli::{Cli, Subcommand},
 	service,
+	snapshot::SnapshotCmd,
 };
 use frame_benchmarking_cli::{BenchmarkCmd, ExtrinsicFactory, SUBSTRATE_REFERENCE_HARDWARE};
 use node_template_runtime::{Block, EXISTENTIAL_DEPOSIT};
@@ -72,11 +76,34 @@ pub fn run() -> sc_cli::Result<()> {
 		},
 		Some(Subcommand::ExportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
+			if cmd.decoded {
+				return runner.sync_run(|config| {
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run_decoded(client)
+				})
+			}
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
-				Ok((cmd.run(client, config.database), task_manager))
+				Ok((cmd.export.run(client, config.database), task_manager))
 			})
 		},
+		Some(Subcommand::FinalityProof(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
+		Some(Subcommand::Query(cmd)) => match &cmd.url {
+			Some(url) => cmd.run_remote(url),
+			None => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| {
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run(client)
+				})
+			},
+		},
 		Some(Subcommand::ExportState(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -84,6 +111,16 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ForkOff(cmd)) => match &cmd.url {
+			Some(url) => cmd.run_remote(url),
+			None => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| {
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run(client)
+				})
+			},
+		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -92,6 +129,22 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
+		Some(Subcommand::Snapshot(SnapshotCmd::Create(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
+		Some(Subcommand::Snapshot(SnapshotCmd::Restore(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, other, .. } =
+					service::new_partial(&config)?;
+				let (grandpa_block_import, ..) = other;
+				Ok((cmd.run(client, grandpa_block_import), task_manager))
+			})
+		},
 		Some(Subcommand::PurgeChain(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.database))
@@ -157,8 +210,15 @@ pub fn run() -> sc_cli::Result<()> {
 						)
 					},
 					BenchmarkCmd::Extrinsic(cmd) => {
+						// The builders sign as Bob, whose `do_something` calls would otherwise be
+						// rate limited after a few.
+						let mut config = config;
+						exempt_from_rate_limit(
+							&mut config,
+							&[Sr25519Keyring::Bob.to_account_id()],
+						)?;
 						let PartialComponents { client, .. } = service::new_partial(&config)?;
-						// Register the *Remark* and *TKA* builders.
+						// Register the *Remark*, *TKA* and *DoSomething* builders.
 						let ext_factory = ExtrinsicFactory(vec![
 							Box::new(RemarkBuilder::new(client.clone())),
 							Box::new(TransferKeepAliveBuilder::new(
@@ -166,6 +226,7 @@ pub fn run() -> sc_cli::Result<()> {
 								Sr25519Keyring::Alice.to_account_id(),
 								EXISTENTIAL_DEPOSIT,
 							)),
+							Box::new(DoSomethingBuilder::new(client.clone())),
 						]);
 
 						cmd.run(client, inherent_benchmark_data()?, Vec::new(), &ext_factory)
@@ -175,6 +236,27 @@ pub fn run() -> sc_cli::Result<()> {
 				}
 			})
 		},
+		Some(Subcommand::BenchmarkCompare(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(&cli, config.tokio_handle))
+		},
+		Some(Subcommand::Utilization(cmd)) => match &cmd.url {
+			Some(url) => cmd.run_remote(url),
+			None => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| {
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run(client)
+				})
+			},
+		},
+		Some(Subcommand::UpgradeCheck(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(&config, client)
+			})
+		},
 		#[cfg(feature = "try-runtime")]
 		Some(Subcommand::TryRuntime(cmd)) => {
 			use crate::service::ExecutorDispatch;
@@ -206,10 +288,19 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::Tx(cmd)) => cmd.run(),
+		Some(Subcommand::LoadTest(cmd)) => cmd.run(),
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let instant_seal = cli.instant_seal;
+			#[cfg(feature = "evm")]
+			let eth_config = cli.eth.clone();
 			runner.run_node_until_exit(|config| async move {
-				service::new_full(config).map_err(sc_cli::Error::Service)
+				#[cfg(not(feature = "evm"))]
+				let task_manager = service::new_full(config, instant_seal);
+				#[cfg(feature = "evm")]
+				let task_manager = service::new_full(config, instant_seal, eth_config);
+				task_manager.map_err(sc_cli::Error::Service)
 			})
 		},
 	}
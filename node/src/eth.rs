@@ -0,0 +1,149 @@
+//! Ethereum compatibility for the node, compiled in with the `evm` feature.
+//!
+//! Frontier keeps its own database next to the substrate one, mapping Ethereum block and
+//! transaction hashes to substrate blocks. This module opens that database and spawns the tasks
+//! that keep it, and the caches used by the `eth_*` RPC, in sync with the chain.
+
+use std::{
+	collections::BTreeMap,
+	path::PathBuf,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use fc_rpc::{EthTask, OverrideHandle};
+use fc_rpc_core::types::{FeeHistoryCache, FeeHistoryCacheLimit, FilterPool};
+use futures::{future, StreamExt};
+use node_template_runtime::opaque::Block;
+use sc_client_api::BlockchainEvents;
+use sc_network_sync::SyncingService;
+use sc_service::{BasePath, Configuration, TaskManager};
+
+use crate::service::{FullBackend, FullClient};
+
+/// The Frontier database backend.
+pub type FrontierBackend = fc_db::kv::Backend<Block>;
+
+/// Command line options of the Ethereum RPC.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct EthConfiguration {
+	/// Maximum number of logs in a query.
+	#[arg(long, default_value = "10000")]
+	pub max_past_logs: u32,
+
+	/// Maximum fee history cache size.
+	#[arg(long, default_value = "2048")]
+	pub fee_history_limit: u64,
+
+	/// Sign transactions sent through `eth_sendTransaction` with the well-known development key.
+	#[arg(long)]
+	pub enable_dev_signer: bool,
+
+	/// Maximum allowed gas limit will be `block.gas_limit * execute_gas_limit_multiplier` when
+	/// using `eth_call`/`eth_estimateGas`.
+	#[arg(long, default_value = "10")]
+	pub execute_gas_limit_multiplier: u64,
+
+	/// Size in bytes of the LRU cache for block data.
+	#[arg(long, default_value = "50")]
+	pub eth_log_block_cache: usize,
+
+	/// Size in bytes of the LRU cache for transactions statuses data.
+	#[arg(long, default_value = "50")]
+	pub eth_statuses_cache: usize,
+}
+
+/// The parts of the Ethereum machinery shared by the RPC and the background tasks.
+pub struct FrontierPartialComponents {
+	pub filter_pool: Option<FilterPool>,
+	pub fee_history_cache: FeeHistoryCache,
+	pub fee_history_cache_limit: FeeHistoryCacheLimit,
+}
+
+pub fn new_frontier_partial(config: &EthConfiguration) -> FrontierPartialComponents {
+	FrontierPartialComponents {
+		filter_pool: Some(Arc::new(Mutex::new(BTreeMap::new()))),
+		fee_history_cache: Arc::new(Mutex::new(BTreeMap::new())),
+		fee_history_cache_limit: config.fee_history_limit,
+	}
+}
+
+/// The directory holding the Frontier database of the chain being run.
+fn db_config_dir(config: &Configuration) -> PathBuf {
+	config
+		.base_path
+		.as_ref()
+		.map(|base_path| base_path.config_dir(config.chain_spec.id()))
+		.unwrap_or_else(|| {
+			BasePath::from_project("", "", "node-template").config_dir(config.chain_spec.id())
+		})
+}
+
+/// Opens the Frontier database next to the substrate one.
+pub fn open_frontier_backend(
+	client: Arc<FullClient>,
+	config: &Configuration,
+) -> Result<Arc<FrontierBackend>, String> {
+	let path = db_config_dir(config).join("frontier");
+	let source = match config.database {
+		sc_service::DatabaseSource::RocksDb { .. } =>
+			sc_service::DatabaseSource::RocksDb { path: path.join("db"), cache_size: 0 },
+		sc_service::DatabaseSource::ParityDb { .. } =>
+			sc_service::DatabaseSource::ParityDb { path: path.join("paritydb") },
+		sc_service::DatabaseSource::Auto { .. } => sc_service::DatabaseSource::Auto {
+			rocksdb_path: path.join("db"),
+			paritydb_path: path.join("paritydb"),
+			cache_size: 0,
+		},
+		_ => return Err("Supported db sources: `rocksdb` | `paritydb` | `auto`".to_string()),
+	};
+
+	Ok(Arc::new(FrontierBackend::new(client, &fc_db::kv::DatabaseSettings { source })?))
+}
+
+/// Spawns the tasks that index Ethereum blocks and maintain the RPC caches.
+pub fn spawn_frontier_tasks(
+	task_manager: &TaskManager,
+	client: Arc<FullClient>,
+	backend: Arc<FullBackend>,
+	frontier_backend: Arc<FrontierBackend>,
+	overrides: Arc<OverrideHandle<Block>>,
+	sync: Arc<SyncingService<Block>>,
+	FrontierPartialComponents { filter_pool, fee_history_cache, fee_history_cache_limit }:
+		FrontierPartialComponents,
+) {
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-mapping-sync-worker",
+		Some("frontier"),
+		fc_mapping_sync::kv::MappingSyncWorker::new(
+			client.import_notification_stream(),
+			Duration::new(6, 0),
+			client.clone(),
+			backend,
+			overrides.clone(),
+			frontier_backend,
+			3,
+			0,
+			fc_mapping_sync::SyncStrategy::Normal,
+			sync,
+			Default::default(),
+		)
+		.for_each(|()| future::ready(())),
+	);
+
+	// Each filter is allowed to stay in the pool for 100 blocks.
+	const FILTER_RETAIN_THRESHOLD: u64 = 100;
+	if let Some(filter_pool) = filter_pool {
+		task_manager.spawn_essential_handle().spawn(
+			"frontier-filter-pool",
+			Some("frontier"),
+			EthTask::filter_pool_task(client.clone(), filter_pool, FILTER_RETAIN_THRESHOLD),
+		);
+	}
+
+	task_manager.spawn_essential_handle().spawn(
+		"frontier-fee-history",
+		Some("frontier"),
+		EthTask::fee_history_task(client, overrides, fee_history_cache, fee_history_cache_limit),
+	);
+}
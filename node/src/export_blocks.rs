@@ -0,0 +1,144 @@
+//! The `export-blocks` subcommand, extended with a decoded JSON Lines output.
+
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::{BufWriter, Write},
+	sync::Arc,
+};
+
+use node_template_client::{events::events_key, metadata::Metadata};
+use node_template_runtime::BlockNumber;
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider};
+use serde_json::{json, Value};
+use sp_api::{Metadata as _, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::StorageKey, Encode};
+use sp_runtime::traits::Header as _;
+
+use crate::service::FullClient;
+
+/// Export blocks.
+///
+/// With `--decoded`, each block is written as one line of JSON holding its header, its
+/// extrinsics decoded with their signer, nonce, tip, dispatch result and events, and the events
+/// deposited outside of extrinsics. Blocks are decoded with the metadata of the runtime that
+/// executed them.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportBlocksCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub export: sc_cli::ExportBlocksCmd,
+
+	/// Write the blocks decoded, as JSON Lines.
+	#[arg(long, conflicts_with = "binary")]
+	pub decoded: bool,
+}
+
+impl ExportBlocksCmd {
+	/// Run the export-blocks command with `--decoded`.
+	pub fn run_decoded(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let from = self.export.from.as_ref().map(|from| from.parse()).transpose()?.unwrap_or(1);
+		let to: BlockNumber = match &self.export.to {
+			Some(to) => to.parse()?,
+			None => client.info().best_number,
+		};
+
+		let mut output: Box<dyn Write> = match &self.export.output {
+			Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+			None => Box::new(std::io::stdout()),
+		};
+		let mut metadata_by_version = HashMap::new();
+		for number in from..=to {
+			let hash = client.hash(number)?.ok_or_else(|| format!("Block #{number} not found"))?;
+			let block = client
+				.block(hash)?
+				.ok_or_else(|| format!("Body of block #{number} not found"))?
+				.block;
+
+			// The events and the extrinsics of a block are those of the runtime of its parent.
+			let runtime_at = if number == 0 { hash } else { *block.header.parent_hash() };
+			let spec_version = client.runtime_version_at(runtime_at)?.spec_version;
+			if !metadata_by_version.contains_key(&spec_version) {
+				let encoded = client
+					.runtime_api()
+					.metadata(runtime_at)
+					.map_err(sp_blockchain::Error::from)?;
+				metadata_by_version
+					.insert(spec_version, Metadata::decode(&encoded).map_err(application_error)?);
+			}
+			let metadata = &metadata_by_version[&spec_version];
+
+			let events = client.storage(hash, &StorageKey(events_key()))?.map(|events| events.0);
+			let events = metadata
+				.storage("System", "Events", &[])
+				.and_then(|query| query.decode_value(events.as_deref()))
+				.map_err(application_error)?;
+
+			let mut extrinsic_events = vec![Vec::new(); block.extrinsics.len()];
+			let mut block_events = Vec::new();
+			for record in events.as_array().into_iter().flatten() {
+				match record["phase"].get("ApplyExtrinsic").and_then(Value::as_u64) {
+					Some(index) if (index as usize) < extrinsic_events.len() =>
+						extrinsic_events[index as usize].push(record["event"].clone()),
+					_ => block_events
+						.push(json!({ "phase": record["phase"], "event": record["event"] })),
+				}
+			}
+
+			let mut extrinsics = Vec::with_capacity(block.extrinsics.len());
+			for (index, (extrinsic, events)) in
+				block.extrinsics.iter().zip(extrinsic_events).enumerate()
+			{
+				let decoded =
+					metadata.decode_extrinsic(&extrinsic.encode()).map_err(application_error)?;
+				let signature = decoded.signature.as_ref();
+				let error = events.iter().find_map(|event| {
+					event.get("System")?.get("ExtrinsicFailed")?.get("dispatch_error").cloned()
+				});
+				extrinsics.push(json!({
+					"index": index,
+					"signer": signature.map(|signature| signature.signer()),
+					"nonce": signature.and_then(|signature| signature.nonce()),
+					"tip": signature.and_then(|signature| signature.tip()),
+					"call": decoded.call,
+					"success": error.is_none(),
+					"error": error,
+					"events": events,
+				}));
+			}
+
+			let header = &block.header;
+			let line = json!({
+				"number": number,
+				"hash": hash,
+				"parentHash": header.parent_hash(),
+				"stateRoot": header.state_root(),
+				"extrinsicsRoot": header.extrinsics_root(),
+				"specVersion": spec_version,
+				"extrinsics": extrinsics,
+				"events": block_events,
+			});
+			serde_json::to_writer(&mut output, &line).map_err(application_error)?;
+			writeln!(output)?;
+		}
+		output.flush()?;
+
+		Ok(())
+	}
+}
+
+fn application_error(e: impl std::error::Error + Send + Sync + 'static) -> sc_cli::Error {
+	sc_cli::Error::Application(Box::new(e))
+}
+
+impl CliConfiguration for ExportBlocksCmd {
+	fn shared_params(&self) -> &SharedParams {
+		self.export.shared_params()
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		self.export.database_params()
+	}
+}
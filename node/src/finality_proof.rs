@@ -0,0 +1,120 @@
+//! The `finality-proof` subcommand, exporting GRANDPA justifications for offline verification.
+
+use std::{fs::File, io::Write, path::PathBuf, sync::Arc};
+
+use node_template_proof::finality::{enacted_change, FinalityProofs, JustifiedBlock};
+use node_template_runtime::{opaque::Block, BlockNumber};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::BlockBackend;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_grandpa::GRANDPA_ENGINE_ID;
+
+/// Export the GRANDPA justifications of a range of finalized blocks.
+///
+/// Besides the justified blocks of the range, the export holds every earlier block enacting an
+/// authority set change, so that it can be verified starting from the genesis authority set with
+/// `node_template_proof::finality::verify`.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct FinalityProofCmd {
+	/// The first block of the range.
+	#[arg(long, value_name = "BLOCK", default_value_t = 1)]
+	pub from: BlockNumber,
+
+	/// The last block of the range, the last finalized block by default.
+	#[arg(long, value_name = "BLOCK")]
+	pub to: Option<BlockNumber>,
+
+	/// Output file name, or stdout if unspecified.
+	#[arg(long)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl FinalityProofCmd {
+	/// Run the finality-proof command.
+	pub fn run<C>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		C: HeaderBackend<Block> + BlockBackend<Block>,
+	{
+		let info = client.info();
+		let to = self.to.unwrap_or(info.finalized_number);
+		if to > info.finalized_number {
+			return Err(format!(
+				"Block #{to} is not finalized, the last finalized block is #{}",
+				info.finalized_number
+			)
+			.into())
+		}
+		if self.from == 0 || self.from > to {
+			return Err(format!("Invalid block range #{}..=#{to}", self.from).into())
+		}
+
+		let blocks = justified_blocks(&*client, self.from, to)?;
+		let proofs = FinalityProofs { genesis_hash: info.genesis_hash, blocks };
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(std::io::stdout()),
+		};
+		serde_json::to_writer_pretty(&mut output, &proofs)
+			.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+		writeln!(output)?;
+
+		Ok(())
+	}
+}
+
+/// The justified blocks of `from..=to`, preceded by every earlier block enacting an authority set
+/// change, as expected by `node_template_proof::finality::verify`.
+pub fn justified_blocks<C>(
+	client: &C,
+	from: BlockNumber,
+	to: BlockNumber,
+) -> sc_cli::Result<Vec<JustifiedBlock>>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block>,
+{
+	let mut blocks = Vec::new();
+	for number in 1..=to {
+		let hash = client.hash(number)?.ok_or_else(|| format!("Block #{number} not found"))?;
+		let header =
+			client.header(hash)?.ok_or_else(|| format!("Header of #{number} not found"))?;
+		let enacts_change = enacted_change(&header)
+			.map_err(|e| sc_cli::Error::Application(Box::new(e)))?
+			.is_some();
+		if number < from && !enacts_change {
+			continue
+		}
+
+		match client
+			.justifications(hash)?
+			.and_then(|justifications| justifications.into_justification(GRANDPA_ENGINE_ID))
+		{
+			Some(justification) =>
+				blocks.push(JustifiedBlock { header, justification: justification.into() }),
+			None if enacts_change =>
+				return Err(format!(
+					"Block #{number} enacts an authority set change but has no justification"
+				)
+				.into()),
+			None => {},
+		}
+	}
+	Ok(blocks)
+}
+
+impl CliConfiguration for FinalityProofCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
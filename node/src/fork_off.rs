@@ -0,0 +1,370 @@
+//! The `fork-off` subcommand, turning the state of a live chain into a development chain spec.
+//!
+//! The state of a block is copied as is, except for what keeps a single developer from producing
+//! blocks on it: the Aura and GRANDPA authorities become `//Alice`, which also becomes the sudo
+//! key and gets funds to pay fees, and `System` is reset to a genesis block. `LastRuntimeUpgrade`
+//! is kept, so that patching `:code` with a newer runtime runs its migrations in the first block.
+
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Arc};
+
+use codec::{Decode, Encode};
+use node_template_client::Client;
+use node_template_runtime::{opaque::Block, Balance, Hash, Index};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, SharedParams};
+use sc_service::ChainSpec;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_grandpa::{VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY};
+use sp_core::{
+	hashing::{blake2_128, twox_128, twox_64},
+	sr25519,
+	storage::well_known_keys,
+};
+use sp_runtime::generic::BlockId;
+
+use crate::{
+	chain_spec::{authority_keys_from_seed, development_config, get_account_id_from_seed},
+	service::FullClient,
+	snapshot::{into_storage, read_state, ChildTrie},
+};
+
+/// The balance given to `//Alice` on top of the one it has in the forked state.
+const ALICE_ENDOWMENT: Balance = 1 << 60;
+
+/// Write the state of a block into a development chain spec run by `//Alice`.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ForkOffCmd {
+	/// The block to fork off, by number or hash. The last finalized block by default.
+	#[arg(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Read the state from a running node at this WebSocket RPC endpoint instead of the local
+	/// database.
+	#[arg(long)]
+	pub url: Option<String>,
+
+	/// Replace the runtime of the forked state with the runtime of this binary.
+	#[arg(long)]
+	pub patch_code: bool,
+
+	/// Replace the runtime of the forked state with this WASM blob.
+	#[arg(long, value_name = "WASM", conflicts_with = "patch_code")]
+	pub code: Option<PathBuf>,
+
+	/// Output file name of the raw chain spec, or stdout if unspecified.
+	#[arg(long)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ForkOffCmd {
+	/// Run the fork-off command against the local database.
+	pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let hash = match self.block_id()? {
+			Some(BlockId::Hash(hash)) => hash,
+			Some(BlockId::Number(number)) =>
+				client.hash(number)?.ok_or_else(|| format!("Block #{number} not found"))?,
+			None => client.info().finalized_hash,
+		};
+		let (top, children) = read_state(&client, hash)?;
+		self.write_spec(top, children)
+	}
+
+	/// Run the fork-off command against the node at `--url`.
+	pub fn run_remote(&self, url: &str) -> sc_cli::Result<()> {
+		let at = self.block_id()?;
+		let runtime = tokio::runtime::Runtime::new()?;
+		let (top, children) = runtime
+			.block_on(read_remote_state(url, at))
+			.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+		self.write_spec(top, children)
+	}
+
+	fn write_spec(
+		&self,
+		top: Vec<(Vec<u8>, Vec<u8>)>,
+		children: Vec<ChildTrie>,
+	) -> sc_cli::Result<()> {
+		let code = match (&self.code, self.patch_code) {
+			(Some(path), _) => Some(fs::read(path)?),
+			(None, true) => Some(
+				node_template_runtime::WASM_BINARY
+					.ok_or("The runtime of this binary was built without WASM")?
+					.to_vec(),
+			),
+			(None, false) => None,
+		};
+
+		let mut storage = into_storage(top, children);
+		fork_off(&mut storage.top, code)?;
+
+		let mut spec = development_config()?;
+		spec.set_storage(storage);
+		let json = spec.as_json(true)?;
+		match &self.output {
+			Some(path) => fs::write(path, json)?,
+			None => println!("{json}"),
+		}
+		Ok(())
+	}
+
+	fn block_id(&self) -> Result<Option<BlockId<Block>>, String> {
+		self.at.as_ref().map(|at| at.parse::<Block>()).transpose()
+	}
+}
+
+/// Reads the state of the block `at`, the last finalized one by default, from the node at `url`
+/// in the layout of [`read_state`].
+async fn read_remote_state(
+	url: &str,
+	at: Option<BlockId<Block>>,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, Vec<ChildTrie>), node_template_client::Error> {
+	let client = Client::connect(url).await?;
+	let hash = match at {
+		Some(BlockId::Hash(hash)) => hash,
+		Some(BlockId::Number(number)) => client.block_hash(Some(number)).await?,
+		None => client.finalized_hash().await?,
+	};
+
+	let mut top = Vec::new();
+	let mut children = Vec::new();
+	for (key, root) in client.storage_pairs(&[], hash).await? {
+		if !key.starts_with(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			top.push((key, root));
+			continue
+		}
+		let pairs = client.child_storage_pairs(&key, hash).await?;
+		children.push(ChildTrie { prefixed_key: key, root, pairs });
+	}
+	Ok((top, children))
+}
+
+/// Rewrites the top trie of a forked state into the genesis state of a development chain.
+fn fork_off(top: &mut BTreeMap<Vec<u8>, Vec<u8>>, code: Option<Vec<u8>>) -> Result<(), String> {
+	let (aura, grandpa) = authority_keys_from_seed("Alice");
+	let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+
+	// Consensus: `//Alice` authors and finalizes every block from the start.
+	top.insert(value_key("Aura", "Authorities"), vec![aura].encode());
+	top.remove(&value_key("Aura", "CurrentSlot"));
+	top.insert(
+		GRANDPA_AUTHORITIES_KEY.to_vec(),
+		VersionedAuthorityList::from(vec![(grandpa, 1)]).encode(),
+	);
+	for item in ["CurrentSetId", "State", "PendingChange", "NextForced", "Stalled"] {
+		top.remove(&value_key("Grandpa", item));
+	}
+	remove_prefix(top, &value_key("Grandpa", "SetIdSession"));
+
+	// Governance: `//Alice` holds the sudo key and funds to pay for its calls.
+	top.insert(value_key("Sudo", "Key"), alice.encode());
+	let account_key = [value_key("System", "Account"), blake2_128_concat(&alice.encode())].concat();
+	let mut account = match top.get(&account_key) {
+		Some(account) => AccountInfo::decode(&mut &account[..])
+			.map_err(|e| format!("Invalid account of //Alice: {e}"))?,
+		None => AccountInfo { providers: 1, ..Default::default() },
+	};
+	account.data.free = account.data.free.saturating_add(ALICE_ENDOWMENT);
+	top.insert(account_key, account.encode());
+	let issuance_key = value_key("Balances", "TotalIssuance");
+	let issuance = match top.get(&issuance_key) {
+		Some(issuance) => Balance::decode(&mut &issuance[..])
+			.map_err(|e| format!("Invalid total issuance: {e}"))?,
+		None => 0,
+	};
+	top.insert(issuance_key, issuance.saturating_add(ALICE_ENDOWMENT).encode());
+
+	// History: the forked block becomes a genesis block, as built by `frame_system`.
+	let hash69 = Hash::repeat_byte(69);
+	for item in [
+		"Number",
+		"ParentHash",
+		"Digest",
+		"Events",
+		"EventCount",
+		"ExtrinsicCount",
+		"BlockWeight",
+		"AllExtrinsicsLen",
+		"ExecutionPhase",
+	] {
+		top.remove(&value_key("System", item));
+	}
+	remove_prefix(top, &value_key("System", "BlockHash"));
+	remove_prefix(top, &value_key("System", "EventTopics"));
+	remove_prefix(top, &value_key("System", "ExtrinsicData"));
+	top.insert(value_key("System", "ParentHash"), hash69.encode());
+	top.insert(
+		[value_key("System", "BlockHash"), twox_64_concat(&0u32.encode())].concat(),
+		hash69.encode(),
+	);
+	top.insert(well_known_keys::EXTRINSIC_INDEX.to_vec(), 0u32.encode());
+
+	if let Some(code) = code {
+		top.insert(well_known_keys::CODE.to_vec(), code);
+	}
+	Ok(())
+}
+
+/// The account data of `frame_system`, with the balances of `pallet_balances`.
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// The key of a storage value, or the prefix of the entries of a storage map.
+fn value_key(pallet: &str, item: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+fn blake2_128_concat(encoded: &[u8]) -> Vec<u8> {
+	[&blake2_128(encoded)[..], encoded].concat()
+}
+
+fn twox_64_concat(encoded: &[u8]) -> Vec<u8> {
+	[&twox_64(encoded)[..], encoded].concat()
+}
+
+fn remove_prefix(top: &mut BTreeMap<Vec<u8>, Vec<u8>>, prefix: &[u8]) {
+	top.retain(|key, _| !key.starts_with(prefix));
+}
+
+impl CliConfiguration for ForkOffCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A forked state run by `//Bob`, in which `//Alice` holds `free` and others the rest.
+	fn forked_state(free: Balance) -> BTreeMap<Vec<u8>, Vec<u8>> {
+		let (aura, grandpa) = authority_keys_from_seed("Bob");
+		let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+		let bob = get_account_id_from_seed::<sr25519::Public>("Bob");
+		let account = AccountInfo {
+			nonce: 7,
+			providers: 1,
+			data: pallet_balances::AccountData { free, ..Default::default() },
+			..Default::default()
+		};
+		[
+			(value_key("Aura", "Authorities"), vec![aura].encode()),
+			(value_key("Aura", "CurrentSlot"), 1_000u64.encode()),
+			(
+				GRANDPA_AUTHORITIES_KEY.to_vec(),
+				VersionedAuthorityList::from(vec![(grandpa, 1)]).encode(),
+			),
+			(value_key("Grandpa", "CurrentSetId"), 3u64.encode()),
+			(value_key("Grandpa", "State"), vec![0]),
+			(
+				[value_key("Grandpa", "SetIdSession"), twox_64_concat(&3u64.encode())].concat(),
+				vec![],
+			),
+			(value_key("Sudo", "Key"), bob.encode()),
+			(
+				[value_key("System", "Account"), blake2_128_concat(&alice.encode())].concat(),
+				account.encode(),
+			),
+			(value_key("Balances", "TotalIssuance"), (free + 1_000).encode()),
+			(value_key("System", "Number"), 42u32.encode()),
+			(value_key("System", "ParentHash"), Hash::repeat_byte(1).encode()),
+			(
+				[value_key("System", "BlockHash"), twox_64_concat(&41u32.encode())].concat(),
+				Hash::repeat_byte(1).encode(),
+			),
+			(value_key("System", "EventCount"), 2u32.encode()),
+			(well_known_keys::CODE.to_vec(), b"old code".to_vec()),
+		]
+		.into_iter()
+		.collect()
+	}
+
+	fn get<T: Decode>(top: &BTreeMap<Vec<u8>, Vec<u8>>, key: &[u8]) -> Option<T> {
+		top.get(key).map(|value| T::decode(&mut &value[..]).unwrap())
+	}
+
+	#[test]
+	fn hands_the_chain_over_to_alice() {
+		let (aura, grandpa) = authority_keys_from_seed("Alice");
+		let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+		let mut top = forked_state(500);
+
+		fork_off(&mut top, None).unwrap();
+
+		assert_eq!(top.get(&value_key("Aura", "Authorities")), Some(&vec![aura].encode()));
+		assert_eq!(top.get(&value_key("Aura", "CurrentSlot")), None);
+		assert_eq!(
+			top.get(GRANDPA_AUTHORITIES_KEY),
+			Some(&VersionedAuthorityList::from(vec![(grandpa, 1)]).encode())
+		);
+		assert!(!top.keys().any(|key| key.starts_with(&twox_128(b"Grandpa"))));
+		assert_eq!(top.get(&value_key("Sudo", "Key")), Some(&alice.encode()));
+		assert_eq!(top.get(well_known_keys::CODE), Some(&b"old code".to_vec()));
+	}
+
+	#[test]
+	fn resets_system_to_a_genesis_block() {
+		let mut top = forked_state(500);
+
+		fork_off(&mut top, None).unwrap();
+
+		for item in ["Number", "EventCount"] {
+			assert_eq!(top.get(&value_key("System", item)), None);
+		}
+		assert_eq!(get(&top, &value_key("System", "ParentHash")), Some(Hash::repeat_byte(69)));
+		let block_hashes: Vec<_> = top
+			.iter()
+			.filter(|(key, _)| key.starts_with(&value_key("System", "BlockHash")))
+			.collect();
+		assert_eq!(
+			block_hashes,
+			[(
+				&[value_key("System", "BlockHash"), twox_64_concat(&0u32.encode())].concat(),
+				&Hash::repeat_byte(69).encode()
+			)]
+		);
+		assert_eq!(get(&top, well_known_keys::EXTRINSIC_INDEX), Some(0u32));
+	}
+
+	#[test]
+	fn endows_alice_in_step_with_the_total_issuance() {
+		let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+		let account_key =
+			[value_key("System", "Account"), blake2_128_concat(&alice.encode())].concat();
+		let issuance_key = value_key("Balances", "TotalIssuance");
+
+		// An existing account keeps its nonce and balance.
+		let mut top = forked_state(500);
+		fork_off(&mut top, None).unwrap();
+		let account: AccountInfo = get(&top, &account_key).unwrap();
+		assert_eq!((account.nonce, account.data.free), (7, 500 + ALICE_ENDOWMENT));
+		assert_eq!(get(&top, &issuance_key), Some(1_500 + ALICE_ENDOWMENT));
+
+		// A missing one is created.
+		let mut top = forked_state(500);
+		top.remove(&account_key);
+		top.remove(&issuance_key);
+		fork_off(&mut top, None).unwrap();
+		let account: AccountInfo = get(&top, &account_key).unwrap();
+		assert_eq!((account.providers, account.data.free), (1, ALICE_ENDOWMENT));
+		assert_eq!(get(&top, &issuance_key), Some(ALICE_ENDOWMENT));
+	}
+
+	#[test]
+	fn patches_the_code_only_when_given() {
+		let mut top = forked_state(500);
+
+		fork_off(&mut top, Some(b"new code".to_vec())).unwrap();
+
+		assert_eq!(top.get(well_known_keys::CODE), Some(&b"new code".to_vec()));
+	}
+}
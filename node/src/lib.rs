@@ -1,3 +1,5 @@
 pub mod chain_spec;
+#[cfg(feature = "evm")]
+pub mod eth;
 pub mod rpc;
 pub mod service;
@@ -0,0 +1,339 @@
+//! The `load-test` subcommand, submitting signed transactions to a running node at a target rate.
+//!
+//! Transactions are built by the extrinsic builders of the benchmark commands, signed against the
+//! chain and the best block of the node, by accounts derived from `--seed` and funded by
+//! `--funder` before the test. For `do_something`, the funder, which must then hold the sudo key,
+//! also exempts the accounts from the rate limit of `TemplateModule`. Each transaction is followed
+//! until it is included in a block, which gives its inclusion latency; the reasons the
+//! transaction pool rejects transactions for are counted.
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use codec::{Decode, Encode};
+use frame_benchmarking_cli::ExtrinsicBuilder;
+use node_template_client::{
+	tx::{ChainContext, Checkpoint},
+	Client, TxInBlock, TxParams, WaitFor,
+};
+use node_template_runtime::{
+	pallet_template::RateLimitExempt, AccountId, Balance, Runtime, UncheckedExtrinsic,
+	EXISTENTIAL_DEPOSIT, UNIT,
+};
+use sc_cli::Result;
+use sp_core::{sr25519, Pair};
+use sp_runtime::{traits::Header as _, OpaqueExtrinsic};
+use tokio::task::JoinSet;
+
+use crate::benchmarking::{
+	DoSomethingBuilder, RemarkBuilder, SigningContext, TransferKeepAliveBuilder,
+};
+
+/// How long a transaction may take to be included before it is counted as lost.
+const INCLUSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Submit signed transactions to a running node at a target rate, and report how they fared.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct LoadTestCmd {
+	/// The WebSocket RPC endpoint of the node.
+	#[arg(long, default_value = "ws://127.0.0.1:9944")]
+	pub url: String,
+
+	/// The call of the transactions.
+	#[arg(long, value_enum, default_value_t = LoadCall::Remark)]
+	pub call: LoadCall,
+
+	/// The number of transactions to submit per second.
+	#[arg(long, default_value_t = 100)]
+	pub tps: u32,
+
+	/// How long to submit transactions for, in seconds.
+	#[arg(long, default_value_t = 60)]
+	pub duration: u32,
+
+	/// The number of accounts signing the transactions in turn.
+	#[arg(long, default_value_t = 100)]
+	pub accounts: u32,
+
+	/// The secret URI the accounts are derived from, as `<seed>//<index>`.
+	#[arg(long, default_value = "//LoadTest")]
+	pub seed: String,
+
+	/// The secret URI of the account funding the derived accounts before the test, and exempting
+	/// them from the rate limit of `TemplateModule` through sudo for `do-something`.
+	#[arg(long, default_value = "//Alice")]
+	pub funder: String,
+
+	/// The balance given to each derived account, in the smallest unit.
+	#[arg(long, default_value_t = 1_000 * UNIT)]
+	pub endowment: Balance,
+}
+
+/// The calls a load test can submit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LoadCall {
+	/// `System::remark` with an empty remark.
+	Remark,
+	/// `Balances::transfer_keep_alive` of the existential deposit back to the funder.
+	TransferKeepAlive,
+	/// `TemplateModule::do_something`. When an NFT collection restricts the call, the derived
+	/// accounts must hold one of its items, or their calls fail to dispatch.
+	DoSomething,
+}
+
+/// The signing context of a remote node, anchoring transactions to its latest known best block.
+struct RemoteContext {
+	chain: ChainContext,
+	checkpoint: Mutex<Checkpoint>,
+}
+
+impl SigningContext for RemoteContext {
+	fn chain(&self) -> ChainContext {
+		self.chain
+	}
+
+	fn checkpoint(&self) -> Checkpoint {
+		*self.checkpoint.lock().expect("Not poisoned; qed")
+	}
+}
+
+/// The outcome of a submitted transaction.
+enum Outcome {
+	Included { latency: Duration, at: Instant, included: TxInBlock },
+	Rejected(String),
+	TimedOut,
+}
+
+impl LoadTestCmd {
+	/// Run the load-test command.
+	pub fn run(&self) -> Result<()> {
+		if self.tps == 0 || self.accounts == 0 {
+			return Err("`--tps` and `--accounts` must not be zero".into())
+		}
+		let runtime = tokio::runtime::Runtime::new()?;
+		runtime.block_on(self.load_test())
+	}
+
+	async fn load_test(&self) -> Result<()> {
+		let client = Arc::new(Client::connect(&self.url).await.map_err(application_error)?);
+		let best = client.best_header().await.map_err(application_error)?;
+		let context = Arc::new(RemoteContext {
+			chain: *client.context(),
+			checkpoint: Mutex::new(Checkpoint { number: best.number, hash: best.hash() }),
+		});
+		let follower = tokio::spawn(follow_best(client.clone(), context.clone()));
+
+		let funder = pair(&self.funder)?;
+		let accounts = (0..self.accounts)
+			.map(|index| pair(&format!("{}//{index}", self.seed)))
+			.collect::<Result<Vec<_>>>()?;
+		self.fund(&client, &context, &funder, &accounts).await?;
+		if self.call == LoadCall::DoSomething {
+			exempt_from_rate_limit(&client, &funder, &accounts).await?;
+		}
+
+		let mut builders = Vec::with_capacity(accounts.len());
+		let mut nonces = Vec::with_capacity(accounts.len());
+		for account in accounts {
+			nonces.push(
+				client.next_nonce(&account.public().into()).await.map_err(application_error)?,
+			);
+			let builder: Box<dyn ExtrinsicBuilder> = match self.call {
+				LoadCall::Remark =>
+					Box::new(RemarkBuilder::new(context.clone()).with_sender(account)),
+				LoadCall::TransferKeepAlive => Box::new(
+					TransferKeepAliveBuilder::new(
+						context.clone(),
+						funder.public().into(),
+						EXISTENTIAL_DEPOSIT,
+					)
+					.with_sender(account),
+				),
+				LoadCall::DoSomething =>
+					Box::new(DoSomethingBuilder::new(context.clone()).with_sender(account)),
+			};
+			builders.push(builder);
+		}
+
+		let total = self.tps as u64 * self.duration as u64;
+		println!(
+			"Submitting {total} {:?} transactions from {} accounts at {} per second",
+			self.call, self.accounts, self.tps
+		);
+		let mut interval = tokio::time::interval(Duration::from_secs(1) / self.tps);
+		let mut tasks = JoinSet::new();
+		let start = Instant::now();
+		for index in 0..total {
+			interval.tick().await;
+			let account = (index % self.accounts as u64) as usize;
+			let xt = unchecked(builders[account].build(nonces[account])?)?;
+			nonces[account] += 1;
+			tasks.spawn(submit(client.clone(), xt));
+		}
+		let submitted_in = start.elapsed();
+
+		let mut latencies = Vec::new();
+		let mut last_included = start;
+		let mut blocks = HashMap::<_, u32>::new();
+		let mut failed = 0;
+		let mut rejections = BTreeMap::<_, u32>::new();
+		let mut timed_out = 0;
+		while let Some(outcome) = tasks.join_next().await {
+			match outcome.map_err(|e| e.to_string())? {
+				Outcome::Included { latency, at, included } => {
+					latencies.push(latency);
+					last_included = last_included.max(at);
+					*blocks.entry(included.block_hash).or_default() += 1;
+					if included.dispatch_result().is_err() {
+						failed += 1;
+					}
+				},
+				Outcome::Rejected(reason) => *rejections.entry(reason).or_default() += 1,
+				Outcome::TimedOut => timed_out += 1,
+			}
+		}
+		follower.abort();
+
+		println!(
+			"Submitted {total} transactions in {:.1}s ({:.1} per second)",
+			submitted_in.as_secs_f64(),
+			total as f64 / submitted_in.as_secs_f64(),
+		);
+		println!(
+			"Included {} transactions in {} blocks, at most {} in a block, {failed} failing to \
+			dispatch",
+			latencies.len(),
+			blocks.len(),
+			blocks.values().max().copied().unwrap_or_default(),
+		);
+		if !latencies.is_empty() {
+			println!(
+				"Achieved throughput: {:.1} transactions per second",
+				latencies.len() as f64 / (last_included - start).as_secs_f64(),
+			);
+			latencies.sort();
+			let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100].as_millis();
+			println!(
+				"Inclusion latency: p50 {}ms, p90 {}ms, p99 {}ms, max {}ms",
+				percentile(50),
+				percentile(90),
+				percentile(99),
+				percentile(100),
+			);
+		}
+		println!("Rejected by the transaction pool: {}", rejections.values().sum::<u32>());
+		for (reason, count) in rejections {
+			println!("  {reason}: {count}");
+		}
+		println!("Not included after {}s: {timed_out}", INCLUSION_TIMEOUT.as_secs());
+
+		Ok(())
+	}
+
+	/// Transfers the endowment from the funder to every account, waiting for the transfers to be
+	/// included.
+	async fn fund(
+		&self,
+		client: &Arc<Client>,
+		context: &Arc<RemoteContext>,
+		funder: &sr25519::Pair,
+		accounts: &[sr25519::Pair],
+	) -> Result<()> {
+		println!("Funding {} accounts from {}", accounts.len(), self.funder);
+		let mut nonce =
+			client.next_nonce(&funder.public().into()).await.map_err(application_error)?;
+		let mut tasks = JoinSet::new();
+		for account in accounts {
+			let builder = TransferKeepAliveBuilder::new(
+				context.clone(),
+				account.public().into(),
+				self.endowment,
+			)
+			.with_sender(funder.clone());
+			tasks.spawn(submit(client.clone(), unchecked(builder.build(nonce)?)?));
+			nonce += 1;
+		}
+		while let Some(outcome) = tasks.join_next().await {
+			match outcome.map_err(|e| e.to_string())? {
+				Outcome::Included { included, .. } => included
+					.dispatch_result()
+					.map_err(|e| format!("Funding an account failed: {e:?}"))?,
+				Outcome::Rejected(reason) =>
+					return Err(format!("Funding an account failed: {reason}").into()),
+				Outcome::TimedOut => return Err("Funding an account timed out".into()),
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Exempts `accounts` from the rate limit of `TemplateModule`, through a sudo call of `funder`
+/// writing them into `RateLimitExempt`, as `benchmark extrinsic` does in the genesis state.
+async fn exempt_from_rate_limit(
+	client: &Arc<Client>,
+	funder: &sr25519::Pair,
+	accounts: &[sr25519::Pair],
+) -> Result<()> {
+	println!("Exempting {} accounts from the rate limit of TemplateModule", accounts.len());
+	let items = accounts
+		.iter()
+		.map(|account| {
+			let account = AccountId::from(account.public());
+			(RateLimitExempt::<Runtime>::hashed_key_for(account), ().encode())
+		})
+		.collect();
+	let call = pallet_sudo::Call::<Runtime>::sudo {
+		call: Box::new(frame_system::Call::<Runtime>::set_storage { items }.into()),
+	};
+	let xt = client
+		.sign(call, funder, TxParams::default())
+		.await
+		.map_err(application_error)?;
+	match submit(client.clone(), xt).await {
+		Outcome::Included { included, .. } => included.dispatch_result().map_err(|e| {
+			format!("Exempting the accounts failed, is the funder the sudo key? {e:?}").into()
+		}),
+		Outcome::Rejected(reason) => Err(format!("Exempting the accounts failed: {reason}").into()),
+		Outcome::TimedOut => Err("Exempting the accounts timed out".into()),
+	}
+}
+
+/// Submits `xt` and follows it until it is included in a block.
+async fn submit(client: Arc<Client>, xt: UncheckedExtrinsic) -> Outcome {
+	let start = Instant::now();
+	match tokio::time::timeout(INCLUSION_TIMEOUT, client.submit(&xt, WaitFor::InBlock)).await {
+		Ok(Ok(included)) =>
+			Outcome::Included { latency: start.elapsed(), at: Instant::now(), included },
+		Ok(Err(node_template_client::Error::Rejected(reason))) => Outcome::Rejected(reason),
+		Ok(Err(e)) => Outcome::Rejected(e.to_string()),
+		Err(_) => Outcome::TimedOut,
+	}
+}
+
+/// Keeps the checkpoint of `context` at the best block of the node, so that mortal transactions
+/// stay valid however long the test runs.
+async fn follow_best(client: Arc<Client>, context: Arc<RemoteContext>) {
+	loop {
+		tokio::time::sleep(Duration::from_secs(1)).await;
+		if let Ok(best) = client.best_header().await {
+			*context.checkpoint.lock().expect("Not poisoned; qed") =
+				Checkpoint { number: best.number, hash: best.hash() };
+		}
+	}
+}
+
+fn unchecked(xt: OpaqueExtrinsic) -> Result<UncheckedExtrinsic> {
+	UncheckedExtrinsic::decode(&mut &xt.encode()[..])
+		.map_err(|e| format!("Invalid extrinsic: {e}").into())
+}
+
+fn pair(suri: &str) -> Result<sr25519::Pair> {
+	sr25519::Pair::from_string(suri, None).map_err(|e| format!("Invalid secret URI: {e:?}").into())
+}
+
+fn application_error(e: node_template_client::Error) -> sc_cli::Error {
+	sc_cli::Error::Application(Box::new(e))
+}
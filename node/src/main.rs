@@ -1,13 +1,24 @@
 //! Substrate Node Template CLI library.
 #![warn(missing_docs)]
 
-mod chain_spec;
-#[macro_use]
-mod service;
+mod benchmark_compare;
 mod benchmarking;
 mod cli;
 mod command;
-mod rpc;
+mod export_blocks;
+mod finality_proof;
+mod fork_off;
+mod load_test;
+mod query;
+mod snapshot;
+mod tx;
+mod upgrade_check;
+mod utilization;
+
+// The modules shared with the library target, which `node-template-test-utils` builds on.
+#[cfg(feature = "evm")]
+use node_template::eth;
+use node_template::{chain_spec, rpc, service};
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
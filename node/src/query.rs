@@ -0,0 +1,147 @@
+//! The `query` subcommand, reading storage items by name and decoding them with the metadata.
+
+use std::sync::Arc;
+
+use node_template_client::{
+	metadata::{Metadata, StorageQuery},
+	Client,
+};
+use node_template_runtime::opaque::Block;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::StorageProvider;
+use serde_json::{json, Value};
+use sp_api::{Metadata as _, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::generic::BlockId;
+
+use crate::service::FullClient;
+
+/// Read a storage item and print it as JSON.
+///
+/// The item is looked up by name in the metadata of the queried block, e.g.
+/// `query TemplateModule Something` or `query System Account <SS58 address>`. When fewer keys
+/// than the map has are given, every entry under them is printed together with its keys.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct QueryCmd {
+	/// The name of the pallet in the runtime, e.g. `TemplateModule`.
+	pub pallet: String,
+
+	/// The name of the storage item, e.g. `Something`.
+	pub item: String,
+
+	/// The keys of a map: SS58 addresses, numbers, strings, or SCALE encoded keys in hex.
+	pub keys: Vec<String>,
+
+	/// The block to read the state of, by number or hash. The best block by default.
+	#[arg(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Query a running node at this WebSocket RPC endpoint instead of the local database.
+	#[arg(long)]
+	pub url: Option<String>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl QueryCmd {
+	/// Run the query command against the local database.
+	pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let hash = match self.block_id()? {
+			Some(BlockId::Hash(hash)) => hash,
+			Some(BlockId::Number(number)) =>
+				client.hash(number)?.ok_or_else(|| format!("Block #{number} not found"))?,
+			None => client.info().best_hash,
+		};
+		let metadata = client.runtime_api().metadata(hash).map_err(sp_blockchain::Error::from)?;
+		let metadata = Metadata::decode(&metadata).map_err(application_error)?;
+		let query = metadata
+			.storage(&self.pallet, &self.item, &self.keys)
+			.map_err(application_error)?;
+
+		let output = if query.partial {
+			let mut entries = Vec::new();
+			for key in client.storage_keys(hash, Some(&StorageKey(query.key.clone())), None)? {
+				let value = client.storage(hash, &key)?.map(|value| value.0);
+				entries.push(entry(&query, &key.0, value.as_deref()).map_err(application_error)?);
+			}
+			entries.into()
+		} else {
+			let value = client.storage(hash, &StorageKey(query.key.clone()))?.map(|value| value.0);
+			query.decode_value(value.as_deref()).map_err(application_error)?
+		};
+		print(&output)
+	}
+
+	/// Run the query command against the node at `--url`.
+	pub fn run_remote(&self, url: &str) -> sc_cli::Result<()> {
+		let at = self.block_id()?;
+		let runtime = tokio::runtime::Runtime::new()?;
+		let output = runtime.block_on(self.query_remote(url, at)).map_err(application_error)?;
+		print(&output)
+	}
+
+	async fn query_remote(
+		&self,
+		url: &str,
+		at: Option<BlockId<Block>>,
+	) -> Result<Value, node_template_client::Error> {
+		let client = Client::connect(url).await?;
+		let hash = match at {
+			Some(BlockId::Hash(hash)) => hash,
+			Some(BlockId::Number(number)) => client.block_hash(Some(number)).await?,
+			None => client.block_hash(None).await?,
+		};
+		let metadata = client.metadata(hash).await?;
+		let query = metadata.storage(&self.pallet, &self.item, &self.keys)?;
+
+		if !query.partial {
+			let value = client.storage(&query.key, hash).await?;
+			return Ok(query.decode_value(value.as_deref())?)
+		}
+		let mut entries = Vec::new();
+		for (key, value) in client.storage_pairs(&query.key, hash).await? {
+			entries.push(entry(&query, &key, Some(&value))?);
+		}
+		Ok(entries.into())
+	}
+
+	fn block_id(&self) -> Result<Option<BlockId<Block>>, String> {
+		self.at.as_ref().map(|at| at.parse::<Block>()).transpose()
+	}
+}
+
+/// An entry of a map listed by a query with missing keys.
+fn entry(
+	query: &StorageQuery,
+	key: &[u8],
+	value: Option<&[u8]>,
+) -> Result<Value, node_template_client::metadata::Error> {
+	Ok(json!({ "keys": query.decode_keys(key)?, "value": query.decode_value(value)? }))
+}
+
+fn print(output: &Value) -> sc_cli::Result<()> {
+	let output = serde_json::to_string_pretty(output).map_err(application_error)?;
+	println!("{output}");
+	Ok(())
+}
+
+fn application_error(e: impl std::error::Error + Send + Sync + 'static) -> sc_cli::Error {
+	sc_cli::Error::Application(Box::new(e))
+}
+
+impl CliConfiguration for QueryCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,84 @@
+//! RPC interface reporting transaction fees in the asset chosen through `ChargeAssetTxPayment`.
+//!
+//! `payment_queryInfo` keeps reporting the fee in the native balance. This module adds
+//! `payment_queryAssetInfo`, which also converts that fee into the asset the extrinsic pays with.
+
+use std::sync::Arc;
+
+use codec::Decode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_runtime::{
+	apis::{AssetDispatchInfo, AssetTxPaymentApi as AssetTxPaymentRuntimeApi},
+	opaque::Block,
+	AssetId, Balance,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i32 = 1;
+/// Error code returned when the extrinsic cannot be decoded.
+const DECODE_ERROR: i32 = 2;
+
+/// Asset fee RPC methods.
+#[rpc(server)]
+pub trait AssetTxPaymentApi<BlockHash> {
+	/// Returns the dispatch info of a SCALE-encoded extrinsic, together with its fee converted
+	/// into the asset it pays with.
+	#[method(name = "payment_queryAssetInfo")]
+	fn query_asset_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<BlockHash>,
+	) -> RpcResult<AssetDispatchInfo<Balance, AssetId>>;
+}
+
+/// Implementation of [`AssetTxPaymentApiServer`] on top of a client.
+pub struct AssetTxPayment<C> {
+	client: Arc<C>,
+}
+
+impl<C> AssetTxPayment<C> {
+	/// Creates a new instance of the asset fee RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> AssetTxPaymentApiServer<<Block as BlockT>::Hash> for AssetTxPayment<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: AssetTxPaymentRuntimeApi<Block, Balance, AssetId>,
+{
+	fn query_asset_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<AssetDispatchInfo<Balance, AssetId>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let encoded_len = encoded_xt.len() as u32;
+
+		let uxt: <Block as BlockT>::Extrinsic = Decode::decode(&mut &*encoded_xt).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				DECODE_ERROR,
+				"Unable to query asset fee info.",
+				Some(e.to_string()),
+			))
+		})?;
+
+		self.client.runtime_api().query_asset_info(at, uxt, encoded_len).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				RUNTIME_ERROR,
+				"Unable to query asset fee info.",
+				Some(e.to_string()),
+			))
+			.into()
+		})
+	}
+}
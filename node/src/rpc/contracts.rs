@@ -0,0 +1,274 @@
+//! RPC interface for dry-running contracts through the runtime's `ContractsApi`.
+//!
+//! The results are returned SCALE-encoded, exactly as produced by the runtime API, so that
+//! clients can decode them with the types from `pallet-contracts-primitives`.
+
+use std::sync::Arc;
+
+use codec::Encode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Weight};
+use pallet_contracts::ContractsApi as ContractsRuntimeApi;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+/// Error code returned when the runtime API call itself fails.
+const RUNTIME_ERROR: i32 = 1;
+
+/// A request to dry-run a call to a contract.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CallRequest {
+	/// The account the call is made from.
+	pub origin: AccountId,
+	/// The contract being called.
+	pub dest: AccountId,
+	/// The balance transferred to the contract.
+	pub value: Balance,
+	/// The maximum weight the call may consume, defaults to the block limit.
+	pub gas_limit: Option<Weight>,
+	/// The maximum storage deposit the call may charge, defaults to no limit.
+	pub storage_deposit_limit: Option<Balance>,
+	/// The SCALE-encoded input passed to the contract.
+	pub input_data: Bytes,
+}
+
+/// The code of a contract to instantiate.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Code {
+	/// Wasm code uploaded with the instantiation.
+	Upload(Bytes),
+	/// The hash of code already on chain.
+	Existing(Hash),
+}
+
+impl From<Code> for pallet_contracts_primitives::Code<Hash> {
+	fn from(code: Code) -> Self {
+		match code {
+			Code::Upload(code) => Self::Upload(code.to_vec()),
+			Code::Existing(hash) => Self::Existing(hash),
+		}
+	}
+}
+
+/// A request to dry-run the instantiation of a contract.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct InstantiateRequest {
+	/// The account instantiating the contract.
+	pub origin: AccountId,
+	/// The balance transferred to the new contract.
+	pub value: Balance,
+	/// The maximum weight the instantiation may consume, defaults to the block limit.
+	pub gas_limit: Option<Weight>,
+	/// The maximum storage deposit the instantiation may charge, defaults to no limit.
+	pub storage_deposit_limit: Option<Balance>,
+	/// The code of the contract.
+	pub code: Code,
+	/// The SCALE-encoded input passed to the constructor.
+	pub data: Bytes,
+	/// The salt the address of the contract is derived with.
+	pub salt: Bytes,
+}
+
+/// Whether uploaded code may behave non-deterministically.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Determinism {
+	/// The code is deterministic, and may be called from transactions.
+	Enforced,
+	/// The code may use non-deterministic features, and may only be dry-run.
+	Relaxed,
+}
+
+impl From<Determinism> for pallet_contracts::Determinism {
+	fn from(determinism: Determinism) -> Self {
+		match determinism {
+			Determinism::Enforced => Self::Enforced,
+			Determinism::Relaxed => Self::Relaxed,
+		}
+	}
+}
+
+/// A request to dry-run the upload of contract code.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CodeUploadRequest {
+	/// The account uploading the code.
+	pub origin: AccountId,
+	/// The Wasm code of the contract.
+	pub code: Bytes,
+	/// The maximum storage deposit the upload may charge, defaults to no limit.
+	pub storage_deposit_limit: Option<Balance>,
+	/// Whether the code may behave non-deterministically.
+	pub determinism: Determinism,
+}
+
+/// Contracts RPC methods.
+#[rpc(server)]
+pub trait ContractsApi<BlockHash> {
+	/// Dry-runs a call to a contract and returns the SCALE-encoded `ContractExecResult`.
+	///
+	/// Nothing is persisted, but the result includes the storage deposit and weight the call
+	/// would consume, as well as any debug messages emitted by the contract.
+	#[method(name = "contracts_call")]
+	fn call(&self, call_request: CallRequest, at: Option<BlockHash>) -> RpcResult<Bytes>;
+
+	/// Dry-runs the instantiation of a contract and returns the SCALE-encoded
+	/// `ContractInstantiateResult`.
+	///
+	/// Nothing is persisted, but the result includes the address of the contract, the storage
+	/// deposit and the weight the instantiation would consume.
+	#[method(name = "contracts_instantiate")]
+	fn instantiate(
+		&self,
+		instantiate_request: InstantiateRequest,
+		at: Option<BlockHash>,
+	) -> RpcResult<Bytes>;
+
+	/// Dry-runs the upload of contract code and returns the SCALE-encoded `CodeUploadResult`.
+	///
+	/// Nothing is persisted, but the result includes the hash of the code and the storage
+	/// deposit the upload would charge.
+	#[method(name = "contracts_uploadCode")]
+	fn upload_code(
+		&self,
+		upload_request: CodeUploadRequest,
+		at: Option<BlockHash>,
+	) -> RpcResult<Bytes>;
+
+	/// Returns the value stored under `key` in the storage of the contract at `address`.
+	///
+	/// The result is the SCALE-encoded `GetStorageResult`.
+	#[method(name = "contracts_getStorage")]
+	fn get_storage(
+		&self,
+		address: AccountId,
+		key: Bytes,
+		at: Option<BlockHash>,
+	) -> RpcResult<Bytes>;
+}
+
+/// Implementation of [`ContractsApiServer`] on top of a client.
+pub struct Contracts<C> {
+	client: Arc<C>,
+}
+
+impl<C> Contracts<C> {
+	/// Creates a new instance of the contracts RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> ContractsApiServer<<Block as BlockT>::Hash> for Contracts<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
+{
+	fn call(
+		&self,
+		call_request: CallRequest,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Bytes> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let CallRequest { origin, dest, value, gas_limit, storage_deposit_limit, input_data } =
+			call_request;
+
+		let result = self
+			.client
+			.runtime_api()
+			.call(at, origin, dest, value, gas_limit, storage_deposit_limit, input_data.to_vec())
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(result.encode().into())
+	}
+
+	fn instantiate(
+		&self,
+		instantiate_request: InstantiateRequest,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Bytes> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let InstantiateRequest {
+			origin,
+			value,
+			gas_limit,
+			storage_deposit_limit,
+			code,
+			data,
+			salt,
+		} = instantiate_request;
+
+		let result = self
+			.client
+			.runtime_api()
+			.instantiate(
+				at,
+				origin,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				code.into(),
+				data.to_vec(),
+				salt.to_vec(),
+			)
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(result.encode().into())
+	}
+
+	fn upload_code(
+		&self,
+		upload_request: CodeUploadRequest,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Bytes> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let CodeUploadRequest { origin, code, storage_deposit_limit, determinism } = upload_request;
+
+		let result = self
+			.client
+			.runtime_api()
+			.upload_code(at, origin, code.to_vec(), storage_deposit_limit, determinism.into())
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(result.encode().into())
+	}
+
+	fn get_storage(
+		&self,
+		address: AccountId,
+		key: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Bytes> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		let result = self
+			.client
+			.runtime_api()
+			.get_storage(at, address, key.to_vec())
+			.map_err(runtime_error_into_rpc_err)?;
+
+		Ok(result.encode().into())
+	}
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(
+		RUNTIME_ERROR,
+		"Runtime error",
+		Some(format!("{:?}", err)),
+	))
+	.into()
+}
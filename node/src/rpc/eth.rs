@@ -0,0 +1,129 @@
+//! The `eth_*`, `net_*` and `web3_*` RPC namespaces, compiled in with the `evm` feature.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use fc_rpc::{
+	Eth, EthApiServer, EthBlockDataCacheTask, EthDevSigner, EthFilter, EthFilterApiServer,
+	EthSigner, Net, NetApiServer, OverrideHandle, Web3, Web3ApiServer,
+};
+use fc_rpc_core::types::{FeeHistoryCache, FilterPool};
+use jsonrpsee::RpcModule;
+use node_template_runtime::{opaque::Block, TransactionConverter};
+use sc_network::NetworkService;
+use sc_network_sync::SyncingService;
+use sc_transaction_pool::{ChainApi, Pool};
+use sc_transaction_pool_api::TransactionPool;
+use sp_core::H256;
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{
+	eth::{EthConfiguration, FrontierBackend},
+	service::FullClient,
+};
+
+/// Dependencies of the Ethereum RPC.
+pub struct EthDeps<P, A: ChainApi> {
+	/// The client instance to use.
+	pub client: Arc<FullClient>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Graph pool instance.
+	pub graph: Arc<Pool<A>>,
+	/// Network service.
+	pub network: Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
+	/// Chain syncing service.
+	pub sync: Arc<SyncingService<Block>>,
+	/// Whether the node is an authority.
+	pub is_authority: bool,
+	/// The Frontier database.
+	pub frontier_backend: Arc<FrontierBackend>,
+	/// Ethereum data access overrides.
+	pub overrides: Arc<OverrideHandle<Block>>,
+	/// Cache of Ethereum block data.
+	pub block_data_cache: Arc<EthBlockDataCacheTask<Block>>,
+	/// Pool of installed `eth_newFilter` filters.
+	pub filter_pool: Option<FilterPool>,
+	/// Cache for `eth_feeHistory`.
+	pub fee_history_cache: FeeHistoryCache,
+	/// Command line options of the Ethereum RPC.
+	pub eth_config: EthConfiguration,
+}
+
+/// Merges the Ethereum RPC into `io`.
+pub fn create_eth<P, A>(
+	io: &mut RpcModule<()>,
+	deps: EthDeps<P, A>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+	P: TransactionPool<Block = Block> + 'static,
+	A: ChainApi<Block = Block> + 'static,
+{
+	let EthDeps {
+		client,
+		pool,
+		graph,
+		network,
+		sync,
+		is_authority,
+		frontier_backend,
+		overrides,
+		block_data_cache,
+		filter_pool,
+		fee_history_cache,
+		eth_config,
+	} = deps;
+
+	let mut signers = Vec::new();
+	if eth_config.enable_dev_signer {
+		signers.push(Box::new(EthDevSigner::new()) as Box<dyn EthSigner>);
+	}
+
+	io.merge(
+		Eth::new(
+			client.clone(),
+			pool,
+			graph.clone(),
+			Some(TransactionConverter),
+			sync.clone(),
+			signers,
+			overrides.clone(),
+			frontier_backend.clone(),
+			is_authority,
+			block_data_cache.clone(),
+			fee_history_cache,
+			eth_config.fee_history_limit,
+			eth_config.execute_gas_limit_multiplier,
+			None::<BTreeMap<H256, H256>>,
+		)
+		.into_rpc(),
+	)?;
+
+	if let Some(filter_pool) = filter_pool {
+		io.merge(
+			EthFilter::new(
+				client.clone(),
+				frontier_backend,
+				graph,
+				filter_pool,
+				500_usize, // max stored filters
+				eth_config.max_past_logs,
+				block_data_cache,
+			)
+			.into_rpc(),
+		)?;
+	}
+
+	io.merge(
+		Net::new(
+			client.clone(),
+			network,
+			// Whether to format the `peer_count` response as Hex (default) or not.
+			true,
+		)
+		.into_rpc(),
+	)?;
+
+	io.merge(Web3::new(client).into_rpc())?;
+
+	Ok(())
+}
@@ -0,0 +1,147 @@
+//! RPC interface suggesting the tip a transaction needs to be included in the next block.
+//!
+//! The estimate is computed by `node_template_client::payment` from the utilization of the blocks
+//! up to the requested one and the priorities of the ready transactions in the pool.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Decode, Encode};
+use frame_support::dispatch::{DispatchInfo, Pays};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_client::{
+	payment::{estimate_tip, priority_boost, TipEstimate},
+	tx::ExtraBuilder,
+};
+use node_template_runtime::{
+	opaque::Block, AccountId, Address, Balance, BlockNumber, RuntimeCall, Signature, SignedExtra,
+};
+use pallet_transaction_payment_rpc_runtime_api::TransactionPaymentCallApi;
+use sc_client_api::{Backend, BlockBackend, StorageProvider};
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{sr25519, Bytes};
+use sp_runtime::{generic, traits::Block as BlockT};
+
+/// The number of recent blocks the capacity of a block is estimated from.
+const RECENT_BLOCKS: BlockNumber = 20;
+/// The target probability of inclusion when not given.
+const DEFAULT_PROBABILITY: f64 = 0.9;
+
+/// Error code returned when the runtime API call or reading a block fails.
+const RUNTIME_ERROR: i32 = 1;
+/// Error code returned when the call cannot be decoded.
+const DECODE_ERROR: i32 = 2;
+/// Error code returned when the probability is not in `(0, 1]`.
+const PROBABILITY_ERROR: i32 = 3;
+
+/// Tip estimation RPC methods.
+#[rpc(server)]
+pub trait PaymentEstimateApi<BlockHash> {
+	/// Returns the smallest tip giving a SCALE-encoded call, once signed, a chance of at least
+	/// `probability` to be included in the block after `at`, 0.9 when not given.
+	///
+	/// The weight and fee of the call and the utilization of the recent blocks are read on the
+	/// chain ending with `at`, the best block when not given. The transactions competing with the
+	/// call are the ones ready in the pool now, which builds on the best block.
+	#[method(name = "payment_estimate")]
+	fn estimate(
+		&self,
+		encoded_call: Bytes,
+		probability: Option<f64>,
+		at: Option<BlockHash>,
+	) -> RpcResult<TipEstimate>;
+}
+
+/// Implementation of [`PaymentEstimateApiServer`] on top of a client and a transaction pool.
+pub struct PaymentEstimate<C, P, B> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	_backend: PhantomData<B>,
+}
+
+impl<C, P, B> PaymentEstimate<C, P, B> {
+	/// Creates a new instance of the tip estimation RPC handler.
+	pub fn new(client: Arc<C>, pool: Arc<P>) -> Self {
+		Self { client, pool, _backend: PhantomData }
+	}
+}
+
+/// The length of `call` once signed, with a signature and signed extensions of typical length.
+fn signed_len(call: RuntimeCall) -> u32 {
+	generic::UncheckedExtrinsic::<Address, RuntimeCall, Signature, SignedExtra>::new_signed(
+		call,
+		Address::Id(AccountId::new([0; 32])),
+		Signature::Sr25519(sr25519::Signature::from_raw([0; 64])),
+		ExtraBuilder::new(0).build(),
+	)
+	.encoded_size() as u32
+}
+
+fn runtime_error(e: impl ToString) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(
+		RUNTIME_ERROR,
+		"Unable to estimate the tip.",
+		Some(e.to_string()),
+	))
+	.into()
+}
+
+impl<C, P, B> PaymentEstimateApiServer<<Block as BlockT>::Hash> for PaymentEstimate<C, P, B>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C: StorageProvider<Block, B> + Send + Sync + 'static,
+	C::Api: TransactionPaymentCallApi<Block, Balance, RuntimeCall>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	P: TransactionPool + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	fn estimate(
+		&self,
+		encoded_call: Bytes,
+		probability: Option<f64>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<TipEstimate> {
+		let probability = probability.unwrap_or(DEFAULT_PROBABILITY);
+		if !(probability > 0.0 && probability <= 1.0) {
+			return Err(CallError::Custom(ErrorObject::owned(
+				PROBABILITY_ERROR,
+				"Invalid probability.",
+				Some(format!("{probability} is not in (0, 1]")),
+			))
+			.into())
+		}
+
+		let call = RuntimeCall::decode(&mut &*encoded_call).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				DECODE_ERROR,
+				"Unable to estimate the tip.",
+				Some(e.to_string()),
+			))
+		})?;
+		let len = signed_len(call.clone());
+		let boost = priority_boost(&call);
+
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let dispatch_info = self
+			.client
+			.runtime_api()
+			.query_call_info(at, call, len)
+			.map_err(runtime_error)?;
+
+		let recent = super::utilization::ancestry(&*self.client, at, RECENT_BLOCKS, 0)
+			.map_err(runtime_error)?;
+		let pool: Vec<_> = self.pool.ready().map(|tx| *tx.priority()).collect();
+
+		let info = DispatchInfo {
+			weight: dispatch_info.weight,
+			class: dispatch_info.class,
+			pays_fee: Pays::Yes,
+		};
+		Ok(estimate_tip(&info, len, dispatch_info.partial_fee, boost, &recent, &pool, probability))
+	}
+}
@@ -0,0 +1,91 @@
+//! RPC interface serving `pallet_template` storage with proofs a light client can check.
+//!
+//! The proof can be verified with the `node-template-proof` crate, against the hash of a
+//! finalized block the client trusts.
+
+use std::{iter, sync::Arc};
+
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_proof::{read_something, something_key, SomethingProof};
+use node_template_runtime::opaque::Block;
+use sc_client_api::ProofProvider;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+/// Error code returned when reading the chain or building the proof fails.
+const CLIENT_ERROR: i32 = 1;
+/// Error code returned when the requested block is unknown or not finalized.
+const NOT_FINALIZED_ERROR: i32 = 2;
+
+/// Template pallet RPC methods.
+#[rpc(server)]
+pub trait TemplateApi<BlockHash> {
+	/// Returns `Something` at the finalized block `at`, or at the last finalized block, with the
+	/// block's header and a proof of the value against its state root.
+	#[method(name = "template_somethingWithProof")]
+	fn something_with_proof(&self, at: Option<BlockHash>) -> RpcResult<SomethingProof>;
+}
+
+/// Implementation of [`TemplateApiServer`] on top of a client.
+pub struct Template<C> {
+	client: Arc<C>,
+}
+
+impl<C> Template<C> {
+	/// Creates a new instance of the template RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+fn client_error(e: impl ToString) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(
+		CLIENT_ERROR,
+		"Unable to prove the template storage.",
+		Some(e.to_string()),
+	))
+	.into()
+}
+
+impl<C> TemplateApiServer<<Block as BlockT>::Hash> for Template<C>
+where
+	C: HeaderBackend<Block> + ProofProvider<Block> + Send + Sync + 'static,
+{
+	fn something_with_proof(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<SomethingProof> {
+		let info = self.client.info();
+		let hash = at.unwrap_or(info.finalized_hash);
+
+		// Only finalized blocks can be trusted by a light client following finality.
+		let header = self.client.header(hash).map_err(client_error)?.filter(|header| {
+			header.number <= info.finalized_number &&
+				self.client.hash(header.number).ok().flatten() == Some(hash)
+		});
+		let Some(header) = header else {
+			return Err(CallError::Custom(ErrorObject::owned(
+				NOT_FINALIZED_ERROR,
+				"Block is unknown or not finalized.",
+				Some(format!("{hash:?}")),
+			))
+			.into())
+		};
+
+		let proof: Vec<Bytes> = self
+			.client
+			.read_proof(hash, &mut iter::once(something_key().as_slice()))
+			.map_err(client_error)?
+			.into_iter_nodes()
+			.map(Bytes)
+			.collect();
+		let value = read_something(&header.state_root, &proof).map_err(client_error)?;
+
+		Ok(SomethingProof { header, value, proof })
+	}
+}
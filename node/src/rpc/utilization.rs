@@ -0,0 +1,202 @@
+//! RPC interface reporting how full blocks are, per dispatch class.
+//!
+//! The utilization is computed by `node_template_client::utilization` from `System::BlockWeight`
+//! and the dispatch info in the events of each block, decoded with the metadata of the runtime
+//! that built it.
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use codec::Encode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use node_template_client::{
+	events::events_key,
+	metadata::Metadata,
+	utilization::{block_weight_key, BlockUtilization},
+};
+use node_template_runtime::{opaque::Block, BlockNumber, Hash};
+use sc_client_api::{Backend, BlockBackend, StorageProvider};
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+
+/// The most blocks a single request may cover.
+const MAX_BLOCKS: BlockNumber = 1000;
+/// The number of heaviest calls reported per block when not given.
+const DEFAULT_TOP: u32 = 5;
+
+/// Error code returned when reading a block or its state fails.
+const CLIENT_ERROR: i32 = 1;
+/// Error code returned when the requested range is invalid or too large.
+const RANGE_ERROR: i32 = 2;
+
+/// The metadata of the runtimes met so far, by spec version.
+type MetadataCache = HashMap<u32, Metadata>;
+
+/// Block utilization RPC methods.
+#[rpc(server)]
+pub trait UtilizationApi {
+	/// Returns the utilization of the blocks `from..=to` of the best chain, up to the best block
+	/// when `to` is not given, with the `top` heaviest calls of each block.
+	#[method(name = "utilization_blocks")]
+	fn blocks(
+		&self,
+		from: BlockNumber,
+		to: Option<BlockNumber>,
+		top: Option<u32>,
+	) -> RpcResult<Vec<BlockUtilization>>;
+}
+
+/// Implementation of [`UtilizationApiServer`] on top of a client.
+pub struct Utilization<C, B> {
+	client: Arc<C>,
+	_backend: PhantomData<B>,
+}
+
+impl<C, B> Utilization<C, B> {
+	/// Creates a new instance of the utilization RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _backend: PhantomData }
+	}
+}
+
+/// The utilization of the blocks `from..=to` of the best chain of `client`.
+pub fn blocks<C, B>(
+	client: &C,
+	from: BlockNumber,
+	to: BlockNumber,
+	top: u32,
+) -> Result<Vec<BlockUtilization>, String>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, B>,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block>,
+{
+	let mut cache = MetadataCache::new();
+	(from..=to)
+		.map(|number| {
+			let hash = client
+				.hash(number)
+				.map_err(|e| e.to_string())?
+				.ok_or_else(|| format!("Block #{number} not found"))?;
+			block(client, hash, top, &mut cache)
+		})
+		.collect()
+}
+
+/// The utilization of the `count` blocks ending with `at` on its own chain of `client`, the
+/// genesis block left out, in ascending order of block number.
+pub fn ancestry<C, B>(
+	client: &C,
+	at: Hash,
+	count: BlockNumber,
+	top: u32,
+) -> Result<Vec<BlockUtilization>, String>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, B>,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block>,
+{
+	let mut cache = MetadataCache::new();
+	let mut blocks = Vec::new();
+	let mut hash = at;
+	while blocks.len() < count as usize {
+		let header = client
+			.header(hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Block {hash:?} not found"))?;
+		if header.number == 0 {
+			break
+		}
+		blocks.push(block(client, hash, top, &mut cache)?);
+		hash = header.parent_hash;
+	}
+	blocks.reverse();
+	Ok(blocks)
+}
+
+/// The utilization of the block `hash` of `client`, with its `top` heaviest calls, decoded with
+/// the metadata of its runtime, looked up in `cache` first.
+fn block<C, B>(
+	client: &C,
+	hash: Hash,
+	top: u32,
+	cache: &mut MetadataCache,
+) -> Result<BlockUtilization, String>
+where
+	C: BlockBackend<Block> + StorageProvider<Block, B> + ProvideRuntimeApi<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	B: Backend<Block>,
+{
+	let block = client
+		.block(hash)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Body of block {hash:?} not found"))?
+		.block;
+	let number = block.header.number;
+
+	// A block is built by the runtime of its parent.
+	let runtime_at = if number == 0 { hash } else { block.header.parent_hash };
+	let api = client.runtime_api();
+	let spec_version = api.version(runtime_at).map_err(|e| e.to_string())?.spec_version;
+	if !cache.contains_key(&spec_version) {
+		let encoded = api.metadata(runtime_at).map_err(|e| e.to_string())?;
+		cache.insert(spec_version, Metadata::decode(&encoded).map_err(|e| e.to_string())?);
+	}
+	let extrinsics: Vec<_> = block.extrinsics.iter().map(Encode::encode).collect();
+	let block_weight = client
+		.storage(hash, &StorageKey(block_weight_key()))
+		.map_err(|e| e.to_string())?;
+	let events = client.storage(hash, &StorageKey(events_key())).map_err(|e| e.to_string())?;
+
+	BlockUtilization::compute(
+		&cache[&spec_version],
+		number,
+		hash,
+		&extrinsics,
+		block_weight.as_ref().map(|value| &value.0[..]),
+		events.as_ref().map(|value| &value.0[..]),
+		top as usize,
+	)
+	.map_err(|e| format!("Invalid limits of block #{number}: {e}"))
+}
+
+impl<C, B> UtilizationApiServer for Utilization<C, B>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, B>,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: Core<Block> + MetadataApi<Block>,
+	C: Send + Sync + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	fn blocks(
+		&self,
+		from: BlockNumber,
+		to: Option<BlockNumber>,
+		top: Option<u32>,
+	) -> RpcResult<Vec<BlockUtilization>> {
+		let to = to.unwrap_or_else(|| self.client.info().best_number);
+		if from > to || to - from >= MAX_BLOCKS {
+			return Err(CallError::Custom(ErrorObject::owned(
+				RANGE_ERROR,
+				"Invalid block range.",
+				Some(format!("#{from} to #{to}, at most {MAX_BLOCKS} blocks")),
+			))
+			.into())
+		}
+
+		blocks(&*self.client, from, to, top.unwrap_or(DEFAULT_TOP)).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				CLIENT_ERROR,
+				"Unable to compute the block utilization.",
+				Some(e),
+			))
+			.into()
+		})
+	}
+}
@@ -1,15 +1,27 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_client_api::BlockBackend;
+use codec::Decode;
+use futures::{channel::mpsc, stream::BoxStream, StreamExt};
+use node_template_runtime::{self, opaque::Block, Hash, RuntimeApi, SLOT_DURATION};
+use sc_client_api::{BlockBackend, StorageProvider};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
 use sc_consensus_grandpa::SharedVoterState;
+use sc_consensus_manual_seal::{
+	consensus::aura::AuraConsensusDataProvider, EngineCommand, ManualSealParams,
+};
 pub use sc_executor::NativeElseWasmExecutor;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager, WarpSyncParams};
+use sc_service::{
+	error::Error as ServiceError, Configuration, RpcHandlers, TaskManager, WarpSyncParams,
+};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+use sc_transaction_pool_api::TransactionPool;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+use sp_core::{hashing::twox_128, storage::StorageKey};
 use std::{sync::Arc, time::Duration};
 
+#[cfg(feature = "evm")]
+use crate::eth::EthConfiguration;
+
 // Our native executor instance.
 pub struct ExecutorDispatch;
 
@@ -30,9 +42,10 @@ impl sc_executor::NativeExecutionDispatch for ExecutorDispatch {
 	}
 }
 
-pub(crate) type FullClient =
+pub type FullClient =
 	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
-type FullBackend = sc_service::TFullBackend<Block>;
+pub type FullBackend = sc_service::TFullBackend<Block>;
+pub type FullPool = sc_transaction_pool::FullPool<Block, FullClient>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
 pub fn new_partial(
@@ -43,7 +56,7 @@ pub fn new_partial(
 		FullBackend,
 		FullSelectChain,
 		sc_consensus::DefaultImportQueue<Block, FullClient>,
-		sc_transaction_pool::FullPool<Block, FullClient>,
+		FullPool,
 		(
 			sc_consensus_grandpa::GrandpaBlockImport<
 				FullBackend,
@@ -137,8 +150,76 @@ pub fn new_partial(
 	})
 }
 
+/// The timestamp of a block sealed instantly or manually on top of `parent`: now, or one slot
+/// after the timestamp of `parent` when blocks are sealed faster than once per slot.
+///
+/// Aura requires every block to be in a later slot than its parent. Reading the timestamp of
+/// `parent` from its state keeps this true across restarts, whichever thread seals the block.
+fn next_timestamp(client: &FullClient, parent: Hash) -> sp_blockchain::Result<u64> {
+	let key = StorageKey([twox_128(b"Timestamp"), twox_128(b"Now")].concat());
+	let parent_timestamp = match client.storage(parent, &key)? {
+		Some(encoded) => u64::decode(&mut &encoded.0[..])
+			.map_err(|e| sp_blockchain::Error::Backend(format!("Invalid Timestamp::Now: {e}")))?,
+		None => 0,
+	};
+	let now = *sp_timestamp::InherentDataProvider::from_system_time().timestamp();
+	Ok(now.max(parent_timestamp + SLOT_DURATION))
+}
+
+/// How a full node authors blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// Aura and GRANDPA, when the node is an authority.
+	Aura,
+	/// A block sealed and finalized as soon as a transaction enters the pool.
+	Instant,
+	/// A block sealed on every command sent to [`FullComponents::manual_seal`].
+	Manual,
+}
+
+/// The components of a running full node.
+pub struct FullComponents {
+	/// The task manager running the node, which stops it when dropped.
+	pub task_manager: TaskManager,
+	/// The client of the node.
+	pub client: Arc<FullClient>,
+	/// The transaction pool of the node.
+	pub transaction_pool: Arc<FullPool>,
+	/// The RPC methods of the node, callable without going through its RPC server.
+	pub rpc_handlers: RpcHandlers,
+	/// The sink of the block authoring task with [`Sealing::Manual`].
+	pub manual_seal: Option<mpsc::Sender<EngineCommand<Hash>>>,
+}
+
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+///
+/// With `instant_seal`, the node neither runs Aura nor GRANDPA: it seals and finalizes a block as
+/// soon as a transaction enters the pool. This is only meant for development chains.
+pub fn new_full(
+	config: Configuration,
+	instant_seal: bool,
+	#[cfg(feature = "evm")] eth_config: EthConfiguration,
+) -> Result<TaskManager, ServiceError> {
+	let sealing = if instant_seal { Sealing::Instant } else { Sealing::Aura };
+	new_full_components(
+		config,
+		sealing,
+		#[cfg(feature = "evm")]
+		eth_config,
+	)
+	.map(|components| components.task_manager)
+}
+
+/// Builds a new service for a full client authoring blocks with `sealing`, returning its
+/// components.
+///
+/// With [`Sealing::Instant`] or [`Sealing::Manual`], the node neither runs Aura nor GRANDPA. This
+/// is only meant for development chains.
+pub fn new_full_components(
+	mut config: Configuration,
+	sealing: Sealing,
+	#[cfg(feature = "evm")] eth_config: EthConfiguration,
+) -> Result<FullComponents, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -189,28 +270,75 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let force_authoring = config.force_authoring;
 	let backoff_authoring_blocks: Option<()> = None;
 	let name = config.network.node_name.clone();
-	let enable_grandpa = !config.disable_grandpa;
+	let enable_grandpa = !config.disable_grandpa && sealing == Sealing::Aura;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	#[cfg(feature = "evm")]
+	let (frontier_backend, overrides, frontier_partial) = {
+		let frontier_backend = crate::eth::open_frontier_backend(client.clone(), &config)?;
+		let overrides = fc_storage::overrides_handle(client.clone());
+		(frontier_backend, overrides, crate::eth::new_frontier_partial(&eth_config))
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 
+		#[cfg(feature = "evm")]
+		let eth_deps = {
+			let client = client.clone();
+			let pool = pool.clone();
+			let block_data_cache = Arc::new(fc_rpc::EthBlockDataCacheTask::new(
+				task_manager.spawn_handle(),
+				overrides.clone(),
+				eth_config.eth_log_block_cache,
+				eth_config.eth_statuses_cache,
+				prometheus_registry.clone(),
+			));
+			let network = network.clone();
+			let sync = sync_service.clone();
+			let is_authority = role.is_authority();
+			let frontier_backend = frontier_backend.clone();
+			let overrides = overrides.clone();
+			let filter_pool = frontier_partial.filter_pool.clone();
+			let fee_history_cache = frontier_partial.fee_history_cache.clone();
+			let eth_config = eth_config.clone();
+
+			move || crate::rpc::eth::EthDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				graph: pool.pool().clone(),
+				network: network.clone(),
+				sync: sync.clone(),
+				is_authority,
+				frontier_backend: frontier_backend.clone(),
+				overrides: overrides.clone(),
+				block_data_cache: block_data_cache.clone(),
+				filter_pool: filter_pool.clone(),
+				fee_history_cache: fee_history_cache.clone(),
+				eth_config: eth_config.clone(),
+			}
+		};
+
 		Box::new(move |deny_unsafe, _| {
 			let deps =
 				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
-			crate::rpc::create_full(deps).map_err(Into::into)
+			#[allow(unused_mut)]
+			let mut io = crate::rpc::create_full(deps)?;
+			#[cfg(feature = "evm")]
+			crate::rpc::eth::create_eth(&mut io, eth_deps())?;
+			Ok(io)
 		})
 	};
 
-	let _rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+	let rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		network: network.clone(),
 		client: client.clone(),
 		keystore: keystore_container.keystore(),
 		task_manager: &mut task_manager,
 		transaction_pool: transaction_pool.clone(),
 		rpc_builder: rpc_extensions_builder,
-		backend,
+		backend: backend.clone(),
 		system_rpc_tx,
 		tx_handler_controller,
 		sync_service: sync_service.clone(),
@@ -218,11 +346,79 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		telemetry: telemetry.as_mut(),
 	})?;
 
-	if role.is_authority() {
+	#[cfg(feature = "evm")]
+	crate::eth::spawn_frontier_tasks(
+		&task_manager,
+		client.clone(),
+		backend,
+		frontier_backend,
+		overrides,
+		sync_service.clone(),
+		frontier_partial,
+	);
+
+	let mut manual_seal = None;
+	if sealing != Sealing::Aura {
+		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			prometheus_registry.as_ref(),
+			telemetry.as_ref().map(|x| x.handle()),
+		);
+
+		let commands_stream: BoxStream<'static, EngineCommand<Hash>> = match sealing {
+			// Seal and finalize a new block for every transaction that enters the pool.
+			Sealing::Instant => transaction_pool
+				.import_notification_stream()
+				.map(|_| EngineCommand::SealNewBlock {
+					create_empty: false,
+					finalize: true,
+					parent_hash: None,
+					sender: None,
+				})
+				.boxed(),
+			_ => {
+				let (sink, commands_stream) = mpsc::channel(1024);
+				manual_seal = Some(sink);
+				commands_stream.boxed()
+			},
+		};
+
+		let authoring = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+			block_import,
+			env: proposer_factory,
+			client: client.clone(),
+			pool: transaction_pool.clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: Some(Box::new(AuraConsensusDataProvider::new(client.clone()))),
+			create_inherent_data_providers: {
+				let client = client.clone();
+				move |parent, ()| {
+					let timestamp = next_timestamp(&client, parent);
+					async move {
+						let timestamp = timestamp?.into();
+						Ok::<_, Box<dyn std::error::Error + Send + Sync>>(
+							sp_timestamp::InherentDataProvider::new(timestamp),
+						)
+					}
+				}
+			},
+		});
+
+		// the manual seal authoring task is considered essential, i.e. if it
+		// fails we take down the service with it.
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"manual-seal",
+			Some("block-authoring"),
+			authoring,
+		);
+	} else if role.is_authority() {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
 			client.clone(),
-			transaction_pool,
+			transaction_pool.clone(),
 			prometheus_registry.as_ref(),
 			telemetry.as_ref().map(|x| x.handle()),
 		);
@@ -232,7 +428,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _>(
 			StartAuraParams {
 				slot_duration,
-				client,
+				client: client.clone(),
 				select_chain,
 				block_import,
 				proposer_factory,
@@ -310,5 +506,5 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	}
 
 	network_starter.start_network();
-	Ok(task_manager)
+	Ok(FullComponents { task_manager, client, transaction_pool, rpc_handlers, manual_seal })
 }
@@ -0,0 +1,489 @@
+//! The `snapshot` subcommands, bootstrapping a node from the state of a finalized block.
+//!
+//! A snapshot holds a GRANDPA justified block, the full state of that block, and the justified
+//! blocks enacting every earlier authority set change. Restoring it checks the whole chain of
+//! justifications against the genesis authorities of the chain spec, the body of the block
+//! against its header, and the state against the state root of the header, before importing the
+//! block into an empty database the way warp sync does.
+//!
+//! Both subcommands hold the whole state in memory, as the state import of warp sync does, so
+//! they are limited to states that fit in the memory of the node.
+
+use std::{
+	fs::File,
+	io::Write,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use codec::{Decode, Encode, IoReader};
+use node_template_proof::finality::{self, FinalityProofs, JustifiedBlock};
+use node_template_runtime::{
+	opaque::{Block, Header},
+	BlockNumber, Hash,
+};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider};
+use sc_consensus::{
+	BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult, ImportedState, StateAction,
+	StorageChanges,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_consensus_grandpa::{AuthorityList, GrandpaApi, GRANDPA_ENGINE_ID};
+use sp_core::storage::{
+	well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX, ChildInfo, Storage, StorageChild,
+};
+use sp_runtime::{
+	traits::{BlakeTwo256, Block as BlockT, Hash as HashT, Header as HeaderT},
+	Justifications, StateVersion,
+};
+use sp_state_machine::{KeyValueStates, KeyValueStorageLevel};
+
+use crate::{finality_proof::justified_blocks, service::FullClient};
+
+/// The zstd compression level of snapshots.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Create and restore state snapshots of finalized blocks.
+#[derive(Debug, clap::Subcommand)]
+pub enum SnapshotCmd {
+	/// Write the state of a finalized block, with its proof of finality, into a snapshot file.
+	Create(SnapshotCreateCmd),
+
+	/// Initialise an empty database from a snapshot file.
+	Restore(SnapshotRestoreCmd),
+}
+
+/// Write the state of a finalized block, with its proof of finality, into a snapshot file.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct SnapshotCreateCmd {
+	/// The snapshot file to write.
+	pub output: PathBuf,
+
+	/// The block to snapshot, which must carry a GRANDPA justification. The last finalized block
+	/// with a justification by default.
+	#[arg(long, value_name = "BLOCK")]
+	pub at: Option<BlockNumber>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// Initialise an empty database from a snapshot file.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct SnapshotRestoreCmd {
+	/// The snapshot file to read.
+	pub input: PathBuf,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// The content of a snapshot file, SCALE encoded and compressed with zstd.
+#[derive(Encode, Decode)]
+pub struct Snapshot {
+	/// The hash of the genesis block of the chain.
+	pub genesis_hash: Hash,
+	/// The justified blocks enacting the authority set changes before `block`.
+	pub authority_changes: Vec<(Header, Vec<u8>)>,
+	/// The block of the state.
+	pub block: Block,
+	/// The GRANDPA justification of `block`.
+	pub justification: Vec<u8>,
+	/// The key-value pairs of the top trie, without the roots of the child tries.
+	pub top: Vec<(Vec<u8>, Vec<u8>)>,
+	/// The child tries.
+	pub children: Vec<ChildTrie>,
+}
+
+impl Snapshot {
+	/// Reads a snapshot file, without verifying it.
+	pub fn read(path: &Path) -> sc_cli::Result<Self> {
+		let decoder = zstd::stream::read::Decoder::new(File::open(path)?)?;
+		Ok(Self::decode(&mut IoReader(decoder)).map_err(|e| format!("Invalid snapshot: {e}"))?)
+	}
+
+	/// Writes the snapshot into a file.
+	pub fn write(&self, path: &Path) -> sc_cli::Result<()> {
+		let compressed = zstd::stream::encode_all(&self.encode()[..], COMPRESSION_LEVEL)?;
+		File::create(path)?.write_all(&compressed)?;
+		Ok(())
+	}
+
+	/// Checks that the snapshot belongs to the chain whose genesis block is `genesis_hash`, that
+	/// its block is final from the `genesis_authorities` of that chain on, and that the body of
+	/// the block matches its header. The state is checked against the header when imported.
+	pub fn verify(
+		self,
+		genesis_hash: Hash,
+		genesis_authorities: AuthorityList,
+	) -> sc_cli::Result<VerifiedSnapshot> {
+		if self.genesis_hash != genesis_hash {
+			return Err("The snapshot belongs to another chain than the chain spec".into())
+		}
+
+		let (header, body) = self.block.deconstruct();
+		let extrinsics_root = BlakeTwo256::ordered_trie_root(
+			body.iter().map(Encode::encode).collect(),
+			StateVersion::V0,
+		);
+		if extrinsics_root != *header.extrinsics_root() {
+			return Err("The body of the snapshot block does not match its header".into())
+		}
+
+		let blocks = self
+			.authority_changes
+			.into_iter()
+			.map(|(header, justification)| JustifiedBlock {
+				header,
+				justification: justification.into(),
+			})
+			.chain(std::iter::once(JustifiedBlock {
+				header: header.clone(),
+				justification: self.justification.clone().into(),
+			}))
+			.collect();
+		finality::verify(
+			&FinalityProofs { genesis_hash, blocks },
+			genesis_hash,
+			genesis_authorities,
+		)
+		.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+
+		let mut state = vec![KeyValueStorageLevel {
+			state_root: Vec::new(),
+			parent_storage_keys: Vec::new(),
+			key_values: self.top,
+		}];
+		state.extend(self.children.into_iter().map(|child| KeyValueStorageLevel {
+			state_root: child.root,
+			parent_storage_keys: vec![child.prefixed_key],
+			key_values: child.pairs,
+		}));
+
+		Ok(VerifiedSnapshot {
+			header,
+			body,
+			justification: self.justification,
+			state: KeyValueStates(state),
+		})
+	}
+}
+
+/// A [`Snapshot`] checked against the chain it is restored into, in the layout of a state import.
+pub struct VerifiedSnapshot {
+	/// The header of the block of the state.
+	pub header: Header,
+	/// The body of the block of the state.
+	pub body: Vec<<Block as BlockT>::Extrinsic>,
+	/// The GRANDPA justification of the block.
+	pub justification: Vec<u8>,
+	/// The state, the top trie first.
+	pub state: KeyValueStates,
+}
+
+/// A default child trie of a state.
+#[derive(Encode, Decode)]
+pub struct ChildTrie {
+	/// The key of the child trie in the top trie, with its `:child_storage:default:` prefix.
+	pub prefixed_key: Vec<u8>,
+	/// The root of the child trie.
+	pub root: Vec<u8>,
+	/// The key-value pairs of the child trie.
+	pub pairs: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl SnapshotCreateCmd {
+	/// Run the snapshot create command.
+	pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let info = client.info();
+		let number = match self.at {
+			Some(number) if number > info.finalized_number =>
+				return Err(format!(
+					"Block #{number} is not finalized, the last finalized block is #{}",
+					info.finalized_number
+				)
+				.into()),
+			Some(number) => number,
+			None => last_justified(&*client, info.finalized_number)?,
+		};
+
+		let mut blocks = justified_blocks(&*client, number, number)?;
+		let justified = blocks.pop().filter(|block| block.header.number == number);
+		let justification = justified
+			.ok_or_else(|| format!("Block #{number} has no GRANDPA justification"))?
+			.justification
+			.0;
+		let authority_changes =
+			blocks.into_iter().map(|block| (block.header, block.justification.0)).collect();
+
+		let hash = client.hash(number)?.ok_or_else(|| format!("Block #{number} not found"))?;
+		let block =
+			client.block(hash)?.ok_or_else(|| format!("Body of #{number} not found"))?.block;
+
+		let (top, children) = read_state(&client, hash)?;
+
+		let snapshot = Snapshot {
+			genesis_hash: info.genesis_hash,
+			authority_changes,
+			block,
+			justification,
+			top,
+			children,
+		};
+		snapshot.write(&self.output)?;
+		println!(
+			"Wrote the state of block #{number} ({hash:?}), {} keys, to {}",
+			snapshot.top.len() + snapshot.children.iter().map(|c| c.pairs.len()).sum::<usize>(),
+			self.output.display(),
+		);
+
+		Ok(())
+	}
+}
+
+/// The state of the block `hash`: the key-value pairs of the top trie, without the roots of the
+/// child tries, and the child tries.
+pub fn read_state(
+	client: &FullClient,
+	hash: Hash,
+) -> sc_cli::Result<(Vec<(Vec<u8>, Vec<u8>)>, Vec<ChildTrie>)> {
+	let mut top = Vec::new();
+	let mut children = Vec::new();
+	for (key, root) in client.storage_pairs(hash, None, None)? {
+		if !key.0.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			top.push((key.0, root.0));
+			continue
+		}
+		let child_info = ChildInfo::new_default(&key.0[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..]);
+		let mut pairs = Vec::new();
+		for child in client.child_storage_keys(hash, child_info.clone(), None, None)? {
+			let value = client
+				.child_storage(hash, &child_info, &child)?
+				.ok_or("Child storage changed while being read")?;
+			pairs.push((child.0, value.0));
+		}
+		children.push(ChildTrie { prefixed_key: key.0, root: root.0, pairs });
+	}
+
+	Ok((top, children))
+}
+
+/// Assembles a state in the layout of [`read_state`] into a [`Storage`].
+pub fn into_storage(top: Vec<(Vec<u8>, Vec<u8>)>, children: Vec<ChildTrie>) -> Storage {
+	Storage {
+		top: top.into_iter().collect(),
+		children_default: children
+			.into_iter()
+			.map(|child| {
+				let key = child.prefixed_key[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..].to_vec();
+				let child_info = ChildInfo::new_default(&key);
+				(key, StorageChild { data: child.pairs.into_iter().collect(), child_info })
+			})
+			.collect(),
+	}
+}
+
+/// The number of the last block up to `finalized` that has a GRANDPA justification.
+///
+/// `sc_consensus_grandpa` only stores the justifications of the blocks enacting authority set
+/// changes and of every `justification_period` blocks.
+fn last_justified(client: &FullClient, finalized: BlockNumber) -> sc_cli::Result<BlockNumber> {
+	for number in (1..=finalized).rev() {
+		let hash = client.hash(number)?.ok_or_else(|| format!("Block #{number} not found"))?;
+		if let Some(justifications) = client.justifications(hash)? {
+			if justifications.get(GRANDPA_ENGINE_ID).is_some() {
+				return Ok(number)
+			}
+		}
+	}
+	Err("No finalized block has a GRANDPA justification yet".into())
+}
+
+impl SnapshotRestoreCmd {
+	/// Run the snapshot restore command, importing through the GRANDPA `block_import` so that
+	/// the authority set is initialised from the restored state.
+	pub async fn run<I>(&self, client: Arc<FullClient>, mut block_import: I) -> sc_cli::Result<()>
+	where
+		I: BlockImport<Block, Error = sp_consensus::Error>,
+	{
+		let info = client.info();
+		if info.best_number != 0 {
+			return Err(
+				"The database is not empty, remove it with `purge-chain` before restoring".into()
+			)
+		}
+
+		let genesis_authorities = client
+			.runtime_api()
+			.grandpa_authorities(info.genesis_hash)
+			.map_err(sp_blockchain::Error::from)?;
+		let VerifiedSnapshot { header, body, justification, state } =
+			Snapshot::read(&self.input)?.verify(info.genesis_hash, genesis_authorities)?;
+		let hash = header.hash();
+		let number = *header.number();
+
+		// The state root is checked by the client while importing.
+		let mut params = BlockImportParams::new(BlockOrigin::File, header);
+		params.body = Some(body);
+		params.justifications = Some(Justifications::from((GRANDPA_ENGINE_ID, justification)));
+		params.state_action =
+			StateAction::ApplyChanges(StorageChanges::Import(ImportedState { block: hash, state }));
+		params.finalized = true;
+		params.fork_choice = Some(ForkChoiceStrategy::Custom(true));
+		params.import_existing = true;
+
+		let result = block_import
+			.import_block(params)
+			.await
+			.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+		match result {
+			ImportResult::Imported(_) => {
+				println!("Restored the state of block #{number} ({hash:?})");
+				Ok(())
+			},
+			result => Err(format!("The snapshot block was not imported: {result:?}").into()),
+		}
+	}
+}
+
+impl CliConfiguration for SnapshotCreateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+impl CliConfiguration for SnapshotRestoreCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use finality_grandpa::{Commit, Message, Precommit, SignedPrecommit};
+	use sp_consensus_grandpa::{
+		AuthorityId, AuthoritySignature, ConsensusLog, ScheduledChange, SetId,
+	};
+	use sp_keyring::Ed25519Keyring::{self, *};
+	use sp_runtime::{Digest, DigestItem};
+
+	const GENESIS: Hash = Hash::repeat_byte(0x69);
+
+	fn authorities(keys: &[Ed25519Keyring]) -> AuthorityList {
+		keys.iter().map(|key| (key.public().into(), 1)).collect()
+	}
+
+	fn header(number: BlockNumber, parent_hash: Hash, change: Option<AuthorityList>) -> Header {
+		let logs = change
+			.map(|next_authorities| {
+				let log = ConsensusLog::<BlockNumber>::ScheduledChange(ScheduledChange {
+					next_authorities,
+					delay: 0,
+				});
+				DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode())
+			})
+			.into_iter()
+			.collect();
+		let extrinsics_root = BlakeTwo256::ordered_trie_root(Vec::new(), StateVersion::V0);
+		Header::new(number, extrinsics_root, Hash::repeat_byte(1), parent_hash, Digest { logs })
+	}
+
+	/// A justification of `header` by `signers`, in the layout `sc_consensus_grandpa` stores.
+	fn justify(header: &Header, set_id: SetId, signers: &[Ed25519Keyring]) -> Vec<u8> {
+		let round = 1u64;
+		let precommit = Precommit { target_hash: header.hash(), target_number: header.number };
+		let payload = sp_consensus_grandpa::localized_payload(
+			round,
+			set_id,
+			&Message::<Hash, BlockNumber>::Precommit(precommit.clone()),
+		);
+		let precommits: Vec<SignedPrecommit<_, _, AuthoritySignature, AuthorityId>> = signers
+			.iter()
+			.map(|key| SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: key.sign(&payload).into(),
+				id: key.public().into(),
+			})
+			.collect();
+		let commit =
+			Commit { target_hash: header.hash(), target_number: header.number, precommits };
+		(round, commit, Vec::<Header>::new()).encode()
+	}
+
+	/// A snapshot of block #2, whose parent hands finality over from Alice to Bob, justified by
+	/// `signers`.
+	fn snapshot(signers: &[Ed25519Keyring]) -> Snapshot {
+		let change = header(1, GENESIS, Some(authorities(&[Bob])));
+		let change_justification = justify(&change, 0, &[Alice]);
+		let header = header(2, change.hash(), None);
+		Snapshot {
+			genesis_hash: GENESIS,
+			justification: justify(&header, 1, signers),
+			authority_changes: vec![(change, change_justification)],
+			block: Block::new(header, Vec::new()),
+			top: vec![(b"key".to_vec(), b"value".to_vec())],
+			children: vec![ChildTrie {
+				prefixed_key: ChildInfo::new_default(b"child").prefixed_storage_key().into_inner(),
+				root: vec![2; 32],
+				pairs: vec![(b"child key".to_vec(), b"child value".to_vec())],
+			}],
+		}
+	}
+
+	#[test]
+	fn restores_the_created_block_and_state() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("snapshot");
+		let created = snapshot(&[Bob]);
+		created.write(&path).unwrap();
+		let restored = Snapshot::read(&path).unwrap();
+		assert_eq!(restored.encode(), created.encode());
+
+		let verified = restored.verify(GENESIS, authorities(&[Alice])).unwrap();
+		assert_eq!(verified.header, created.block.header);
+		assert_eq!(verified.justification, created.justification);
+		let [top, child] = &verified.state.0[..] else { panic!("one top and one child trie") };
+		assert_eq!(top.key_values, created.top);
+		assert!(top.parent_storage_keys.is_empty());
+		assert_eq!(child.key_values, created.children[0].pairs);
+		assert_eq!(child.state_root, created.children[0].root);
+		assert_eq!(child.parent_storage_keys, vec![created.children[0].prefixed_key.clone()]);
+	}
+
+	#[test]
+	fn rejects_snapshots_not_final_on_the_chain() {
+		// Alice handed finality over to Bob.
+		assert!(snapshot(&[Alice]).verify(GENESIS, authorities(&[Alice])).is_err());
+		assert!(snapshot(&[]).verify(GENESIS, authorities(&[Alice])).is_err());
+		// The hand-over is not signed by the genesis authorities of the chain.
+		assert!(snapshot(&[Bob]).verify(GENESIS, authorities(&[Charlie])).is_err());
+		// Nor is the snapshot of the chain.
+		assert!(snapshot(&[Bob]).verify(Hash::repeat_byte(0x42), authorities(&[Alice])).is_err());
+
+		let mut unrelated = snapshot(&[Bob]);
+		unrelated.authority_changes.clear();
+		assert!(unrelated.verify(GENESIS, authorities(&[Alice])).is_err());
+	}
+}
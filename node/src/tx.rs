@@ -0,0 +1,263 @@
+//! The `tx` subcommand, signing and submitting transactions to a running node.
+//!
+//! Transactions are built with `node_template_client`, the same way as the extrinsics of the
+//! benchmark commands, and submitted over the RPC endpoint given with `--url`.
+
+use std::path::PathBuf;
+
+use codec::Decode;
+use node_template_client::{Client, TxParams, WaitFor};
+use node_template_runtime::{
+	pallet_sponsorship, pallet_template, AccountId, Balance, BalancesCall, RuntimeCall, SystemCall,
+	Weight,
+};
+use sc_cli::CryptoScheme;
+use sp_core::{crypto::Ss58Codec, ecdsa, ed25519, sr25519, Bytes, Pair};
+
+/// Sign and submit a transaction to a running node.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TxCmd {
+	#[allow(missing_docs)]
+	#[command(subcommand)]
+	pub call: TxCall,
+
+	/// The WebSocket RPC endpoint of the node.
+	#[arg(long, global = true, default_value = "ws://127.0.0.1:9944")]
+	pub url: String,
+
+	/// The secret URI of the signer, e.g. `//Alice` or a mnemonic phrase.
+	#[arg(long, global = true)]
+	pub suri: Option<String>,
+
+	/// The cryptography scheme of the signer.
+	#[arg(long, global = true, value_enum, ignore_case = true, default_value_t = CryptoScheme::Sr25519)]
+	pub scheme: CryptoScheme,
+
+	/// The tip for the block author.
+	#[arg(long, global = true, default_value_t = 0)]
+	pub tip: Balance,
+
+	/// The SS58 address of the sponsor paying the fee instead of the signer, without tip.
+	#[arg(long, global = true, value_parser = parse_account, conflicts_with = "tip")]
+	pub sponsor: Option<AccountId>,
+
+	/// Wait for the including block to be finalized, not only imported.
+	#[arg(long, global = true)]
+	pub finalized: bool,
+}
+
+/// The calls that can be submitted.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum TxCall {
+	/// Calls of the template pallet.
+	#[command(subcommand)]
+	Template(TemplateCall),
+
+	/// Calls of the balances pallet.
+	#[command(subcommand)]
+	Balances(BalancesTxCall),
+
+	/// Calls of the sponsorship pallet.
+	#[command(subcommand)]
+	Sponsorship(SponsorshipCall),
+
+	/// Calls dispatched with the root origin through the sudo pallet.
+	#[command(subcommand)]
+	Sudo(SudoCall),
+}
+
+/// Calls of the template pallet.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum TemplateCall {
+	/// Store a value in `Something`.
+	DoSomething {
+		/// The value to store.
+		value: u32,
+	},
+
+	/// Increment `Something`, failing when it is not set.
+	CauseError,
+
+	/// Store a random value in `Something`.
+	StoreRandom,
+}
+
+/// Calls of the balances pallet.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum BalancesTxCall {
+	/// Transfer funds to another account.
+	Transfer {
+		/// The SS58 address of the recipient.
+		#[arg(value_parser = parse_account)]
+		dest: AccountId,
+
+		/// The amount to transfer, in the smallest unit.
+		value: Balance,
+
+		/// Fail instead of reaping the sender's account.
+		#[arg(long)]
+		keep_alive: bool,
+	},
+}
+
+/// Calls of the sponsorship pallet.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SponsorshipCall {
+	/// Pay the fees of a user, up to a budget.
+	Sponsor {
+		/// The SS58 address of the user.
+		#[arg(value_parser = parse_account)]
+		user: AccountId,
+
+		/// The total fees to pay for the user, in the smallest unit.
+		budget: Balance,
+	},
+
+	/// Stop paying the fees of a user.
+	Revoke {
+		/// The SS58 address of the user.
+		#[arg(value_parser = parse_account)]
+		user: AccountId,
+	},
+}
+
+/// Calls dispatched with the root origin through the sudo pallet.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SudoCall {
+	/// Dispatch a hex-encoded `RuntimeCall`.
+	Call {
+		/// The SCALE-encoded call, in hex.
+		call: Bytes,
+	},
+
+	/// Set the free balance of an account.
+	SetBalance {
+		/// The SS58 address of the account.
+		#[arg(value_parser = parse_account)]
+		who: AccountId,
+
+		/// The new free balance, in the smallest unit.
+		free: Balance,
+	},
+
+	/// Upgrade the runtime to the given WASM blob.
+	SetCode {
+		/// Path of the runtime WASM blob.
+		wasm: PathBuf,
+	},
+
+	/// Hand the sudo key to another account.
+	SetKey {
+		/// The SS58 address of the new sudo key.
+		#[arg(value_parser = parse_account)]
+		new: AccountId,
+	},
+}
+
+fn parse_account(address: &str) -> Result<AccountId, String> {
+	AccountId::from_ss58check(address).map_err(|e| format!("{e:?}"))
+}
+
+impl TxCmd {
+	/// Run the tx command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let call = self.call.to_runtime_call()?;
+		let suri = self.suri.as_deref().ok_or("The signer must be given with `--suri`")?;
+
+		let runtime = tokio::runtime::Runtime::new()?;
+		runtime.block_on(async {
+			match self.scheme {
+				CryptoScheme::Sr25519 => self.submit(call, &pair::<sr25519::Pair>(suri)?).await,
+				CryptoScheme::Ed25519 => self.submit(call, &pair::<ed25519::Pair>(suri)?).await,
+				CryptoScheme::Ecdsa => self.submit(call, &pair::<ecdsa::Pair>(suri)?).await,
+			}
+		})
+	}
+
+	async fn submit<P>(&self, call: RuntimeCall, signer: &P) -> sc_cli::Result<()>
+	where
+		P: Pair,
+		node_template_runtime::Signature: From<P::Signature>,
+		sp_runtime::MultiSigner: From<P::Public>,
+	{
+		let client = Client::connect(&self.url).await.map_err(application_error)?;
+		let params =
+			TxParams { tip: self.tip, sponsor: self.sponsor.clone(), ..Default::default() };
+		let xt = client.sign(call, signer, params).await.map_err(application_error)?;
+		let wait = if self.finalized { WaitFor::Finalized } else { WaitFor::InBlock };
+		let included = client.submit(&xt, wait).await.map_err(application_error)?;
+
+		println!(
+			"Included in block {:?} as extrinsic #{}",
+			included.block_hash, included.extrinsic_index
+		);
+		for event in &included.events {
+			println!("  {event:?}");
+		}
+
+		included
+			.dispatch_result()
+			.map_err(|e| format!("The transaction failed to dispatch: {e:?}").into())
+	}
+}
+
+impl TxCall {
+	fn to_runtime_call(&self) -> sc_cli::Result<RuntimeCall> {
+		Ok(match self {
+			TxCall::Template(TemplateCall::DoSomething { value }) =>
+				pallet_template::Call::do_something { something: *value }.into(),
+			TxCall::Template(TemplateCall::CauseError) =>
+				pallet_template::Call::cause_error {}.into(),
+			TxCall::Template(TemplateCall::StoreRandom) =>
+				pallet_template::Call::store_random {}.into(),
+			TxCall::Balances(BalancesTxCall::Transfer { dest, value, keep_alive: true }) =>
+				BalancesCall::transfer_keep_alive { dest: dest.clone().into(), value: *value }
+					.into(),
+			TxCall::Balances(BalancesTxCall::Transfer { dest, value, keep_alive: false }) =>
+				BalancesCall::transfer { dest: dest.clone().into(), value: *value }.into(),
+			TxCall::Sponsorship(SponsorshipCall::Sponsor { user, budget }) =>
+				pallet_sponsorship::Call::sponsor { user: user.clone(), budget: *budget }.into(),
+			TxCall::Sponsorship(SponsorshipCall::Revoke { user }) =>
+				pallet_sponsorship::Call::revoke { user: user.clone() }.into(),
+			TxCall::Sudo(call) => sudo(call.to_runtime_call()?),
+		})
+	}
+}
+
+impl SudoCall {
+	fn to_runtime_call(&self) -> sc_cli::Result<RuntimeCall> {
+		Ok(match self {
+			SudoCall::Call { call } =>
+				RuntimeCall::decode(&mut &call[..]).map_err(|e| format!("Invalid call: {e}"))?,
+			SudoCall::SetBalance { who, free } =>
+				BalancesCall::force_set_balance { who: who.clone().into(), new_free: *free }.into(),
+			SudoCall::SetCode { wasm } =>
+				SystemCall::set_code { code: std::fs::read(wasm)? }.into(),
+			SudoCall::SetKey { new } =>
+				pallet_sudo::Call::set_key { new: new.clone().into() }.into(),
+		})
+	}
+}
+
+/// Wraps `call` into `Sudo::sudo`, or `Sudo::sudo_unchecked_weight` for runtime upgrades which
+/// may exceed the block weight when checked. Calls of the sudo pallet are submitted as they are.
+fn sudo(call: RuntimeCall) -> RuntimeCall {
+	match call {
+		RuntimeCall::Sudo(_) => call,
+		RuntimeCall::System(SystemCall::set_code { .. }) =>
+			pallet_sudo::Call::sudo_unchecked_weight {
+				call: Box::new(call),
+				weight: Weight::zero(),
+			}
+			.into(),
+		call => pallet_sudo::Call::sudo { call: Box::new(call) }.into(),
+	}
+}
+
+fn pair<P: Pair>(suri: &str) -> sc_cli::Result<P> {
+	P::from_string(suri, None).map_err(|e| format!("Invalid secret URI: {e:?}").into())
+}
+
+fn application_error(e: node_template_client::Error) -> sc_cli::Error {
+	sc_cli::Error::Application(Box::new(e))
+}
@@ -0,0 +1,313 @@
+//! The `upgrade-check` subcommand, rehearsing a runtime upgrade offline on a state snapshot.
+//!
+//! The state of a snapshot written by `snapshot create` gets its `:code` replaced with the new
+//! runtime, which must be built with the `try-runtime` feature. `on_runtime_upgrade` then runs
+//! with every pre and post upgrade check and `try_state` hook, and the blocks following the
+//! snapshot block in the local database are executed on top of the upgraded state, the first of
+//! them running the migrations for real. Nothing is read from the network.
+
+use std::{path::PathBuf, sync::Arc};
+
+use codec::{Decode, Encode};
+use node_template_runtime::{opaque::Header, Hash, Weight};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::BlockBackend;
+use sc_executor::{
+	sp_wasm_interface::ExtendedHostFunctions, NativeExecutionDispatch, WasmExecutor,
+};
+use sc_service::Configuration;
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::well_known_keys::CODE, traits::ReadRuntimeVersionExt};
+use sp_externalities::Extensions;
+use sp_runtime::{
+	traits::{BlakeTwo256, Header as _},
+	StateVersion,
+};
+use sp_state_machine::{
+	backend::BackendRuntimeCode, ExecutionStrategy, InMemoryBackend, OverlayedChanges, StateMachine,
+};
+
+use crate::{
+	service::{ExecutorDispatch, FullClient},
+	snapshot::{into_storage, Snapshot},
+};
+
+/// The host functions of the runtime, as provided by the node.
+type HostFunctions = ExtendedHostFunctions<
+	sp_io::SubstrateHostFunctions,
+	<ExecutorDispatch as NativeExecutionDispatch>::ExtendHostFunctions,
+>;
+
+/// Rehearse a runtime upgrade on a state snapshot, fully offline.
+///
+/// Runs `on_runtime_upgrade` of the new runtime with all its pre and post upgrade checks, then
+/// executes the blocks following the snapshot block, read from the local database, with the new
+/// runtime. Fails if a check fails, a block cannot be executed, or the weight of the upgrade or of
+/// a block exceeds `BlockWeights::max_block`.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct UpgradeCheckCmd {
+	/// The snapshot file holding the state to upgrade, written by `snapshot create`.
+	#[arg(long)]
+	pub snapshot: PathBuf,
+
+	/// The WASM blob of the new runtime, built with the `try-runtime` feature.
+	#[arg(long, value_name = "WASM")]
+	pub runtime: PathBuf,
+
+	/// The number of blocks following the snapshot block to execute with the new runtime.
+	#[arg(long, default_value_t = 10)]
+	pub blocks: u32,
+
+	/// The `try_state` hooks to run after each block: `all`, `none`, `rr-<n>` for `n` pallets in
+	/// turn, or a comma-separated list of pallet names such as `TemplateModule,Balances`.
+	#[arg(long, default_value = "all", value_parser = parse_try_state)]
+	pub try_state: TryStateSelect,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// The `frame_try_runtime::UpgradeCheckSelect` argument of `TryRuntime_on_runtime_upgrade`, which
+/// is only compiled with the `try-runtime` feature.
+#[derive(Encode)]
+#[allow(dead_code)]
+enum UpgradeCheckSelect {
+	None,
+	All,
+}
+
+/// The `frame_try_runtime::TryStateSelect` argument of `TryRuntime_execute_block`, which is only
+/// compiled with the `try-runtime` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Encode)]
+pub enum TryStateSelect {
+	/// Run no hook.
+	None,
+	/// Run the hooks of all pallets.
+	All,
+	/// Run the hooks of this many pallets, in turn from one block to the next.
+	RoundRobin(u32),
+	/// Run the hooks of the pallets with these names.
+	Only(Vec<Vec<u8>>),
+}
+
+/// Parses a `--try-state` value, as the `try-runtime` CLI does.
+fn parse_try_state(s: &str) -> Result<TryStateSelect, String> {
+	match s {
+		"all" | "All" => Ok(TryStateSelect::All),
+		"none" | "None" => Ok(TryStateSelect::None),
+		_ =>
+			if let Some(count) = s.strip_prefix("rr-") {
+				count
+					.parse()
+					.map(TryStateSelect::RoundRobin)
+					.map_err(|_| format!("Invalid number of pallets in {s}"))
+			} else {
+				Ok(TryStateSelect::Only(
+					s.split(',').map(|name| name.as_bytes().to_vec()).collect(),
+				))
+			},
+	}
+}
+
+impl UpgradeCheckCmd {
+	/// Run the upgrade-check command.
+	pub fn run(&self, config: &Configuration, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let snapshot = Snapshot::read(&self.snapshot)?;
+		let number = *snapshot.block.header.number();
+		let mut parent = snapshot.block.header.hash();
+		let mut storage = into_storage(snapshot.top, snapshot.children);
+		storage.top.insert(CODE.to_vec(), std::fs::read(&self.runtime)?);
+		let backend: InMemoryBackend<BlakeTwo256> = (storage, StateVersion::V1).into();
+		let executor = sc_service::new_wasm_executor::<HostFunctions>(config);
+
+		let mut exceeded = false;
+		let result = call(
+			&backend,
+			&executor,
+			&mut OverlayedChanges::default(),
+			"TryRuntime_on_runtime_upgrade",
+			&UpgradeCheckSelect::All.encode(),
+		)?;
+		let (weight, max_block) = <(Weight, Weight)>::decode(&mut &result[..])
+			.map_err(|e| format!("Invalid result of on_runtime_upgrade: {e}"))?;
+		println!("on_runtime_upgrade at #{number}: all checks passed");
+		exceeded |= report("on_runtime_upgrade", weight, max_block);
+
+		// The checks above leave no trace, so the first block runs the migrations again, for real.
+		let mut overlay = OverlayedChanges::default();
+		for number in number + 1..=number + self.blocks {
+			let hash = match client.hash(number)? {
+				Some(hash) => hash,
+				None => {
+					println!("Block #{number} is not in the local database, stopping");
+					break
+				},
+			};
+			let block =
+				client.block(hash)?.ok_or_else(|| format!("Body of #{number} not found"))?.block;
+			check_parent(&block.header, parent)?;
+			parent = hash;
+			let weight = execute_block(&backend, &executor, &mut overlay, block, &self.try_state)?;
+			exceeded |= report(&format!("Block #{number}"), weight, max_block);
+		}
+
+		if exceeded {
+			return Err("The upgrade exceeds the maximum weight of a block".into())
+		}
+		Ok(())
+	}
+}
+
+/// Checks that `header` is the child of the block `parent`, as the local database may be of
+/// another chain or fork than the snapshot.
+fn check_parent(header: &Header, parent: Hash) -> sc_cli::Result<()> {
+	if header.parent_hash != parent {
+		return Err(format!(
+			"Block #{} of the local database is not built on {parent:?}, stopping",
+			header.number
+		)
+		.into())
+	}
+	Ok(())
+}
+
+/// Executes `block` with the runtime in the `:code` of `backend`, on top of `overlay`, returning
+/// the weight it used.
+///
+/// Neither the state root nor the signatures are checked, as by the `try-runtime` CLI: the former
+/// changes as soon as anything migrates, and the latter fail once the new runtime bumps
+/// `spec_version`, which every signed payload commits to.
+fn execute_block<B: Encode>(
+	backend: &InMemoryBackend<BlakeTwo256>,
+	executor: &WasmExecutor<HostFunctions>,
+	overlay: &mut OverlayedChanges,
+	block: B,
+	try_state: &TryStateSelect,
+) -> sc_cli::Result<Weight> {
+	let result = call(
+		backend,
+		executor,
+		overlay,
+		"TryRuntime_execute_block",
+		&(block, false, false, try_state).encode(),
+	)?;
+	Weight::decode(&mut &result[..])
+		.map_err(|e| format!("Invalid result of execute_block: {e}").into())
+}
+
+/// Calls `method` of the runtime in the `:code` of `backend`, on top of `overlay`.
+fn call(
+	backend: &InMemoryBackend<BlakeTwo256>,
+	executor: &WasmExecutor<HostFunctions>,
+	overlay: &mut OverlayedChanges,
+	method: &str,
+	data: &[u8],
+) -> sc_cli::Result<Vec<u8>> {
+	let mut extensions = Extensions::default();
+	extensions.register(ReadRuntimeVersionExt::new(executor.clone()));
+	let runtime_code = BackendRuntimeCode::new(backend).runtime_code()?;
+	let result = StateMachine::new(
+		backend,
+		overlay,
+		executor,
+		method,
+		data,
+		extensions,
+		&runtime_code,
+		sp_core::testing::TaskExecutor::new(),
+	)
+	.execute(ExecutionStrategy::AlwaysWasm)
+	.map_err(|e| format!("{method} failed: {e}"))?;
+	Ok(result)
+}
+
+/// Prints the weight used by `what` against the maximum weight of a block, returning whether it
+/// exceeds it.
+fn report(what: &str, weight: Weight, max_block: Weight) -> bool {
+	let percent = |used: u64, max: u64| used as f64 * 100.0 / max.max(1) as f64;
+	println!(
+		"{what}: ref time {} ({:.2}% of max block), proof size {} ({:.2}% of max block)",
+		weight.ref_time(),
+		percent(weight.ref_time(), max_block.ref_time()),
+		weight.proof_size(),
+		percent(weight.proof_size(), max_block.proof_size()),
+	);
+	weight.any_gt(max_block)
+}
+
+impl CliConfiguration for UpgradeCheckCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(all(test, feature = "try-runtime"))]
+mod tests {
+	use super::*;
+	use node_template_client::tx::{sign, ChainContext, ExtraBuilder};
+	use node_template_runtime::{
+		Block, Hash, Header, SystemCall, TimestampCall, UncheckedExtrinsic,
+	};
+	use sp_keyring::Sr25519Keyring::Alice;
+	use sp_runtime::{traits::Hash as _, BuildStorage};
+
+	#[test]
+	fn replays_transactions_signed_for_the_previous_runtime() {
+		let storage = crate::chain_spec::development_config().unwrap().build_storage().unwrap();
+		let backend: InMemoryBackend<BlakeTwo256> = (storage, StateVersion::V1).into();
+		let executor = WasmExecutor::<HostFunctions>::builder().build();
+
+		// The runtime of the chain spec takes the part of the new one, so signing for the
+		// previous `spec_version` is what a block authored before the upgrade looks like.
+		let parent_hash = Hash::repeat_byte(1);
+		let context = ChainContext {
+			spec_version: node_template_runtime::VERSION.spec_version - 1,
+			..ChainContext::native(parent_hash)
+		};
+		let extrinsics = vec![
+			UncheckedExtrinsic::new_unsigned(TimestampCall::set { now: 0 }.into()),
+			sign(
+				SystemCall::remark { remark: vec![1] },
+				&Alice.pair(),
+				&ExtraBuilder::new(0),
+				&context,
+			),
+		];
+		let extrinsics_root = BlakeTwo256::ordered_trie_root(
+			extrinsics.iter().map(Encode::encode).collect(),
+			StateVersion::V0,
+		);
+		let header =
+			Header::new(1, extrinsics_root, Default::default(), parent_hash, Default::default());
+		let block = Block { header, extrinsics };
+
+		let checked = (&block, false, true, TryStateSelect::All).encode();
+		let mut overlay = OverlayedChanges::default();
+		assert!(
+			call(&backend, &executor, &mut overlay, "TryRuntime_execute_block", &checked).is_err()
+		);
+		let mut overlay = OverlayedChanges::default();
+		assert!(
+			execute_block(&backend, &executor, &mut overlay, &block, &TryStateSelect::All).is_ok()
+		);
+	}
+
+	#[test]
+	fn replays_only_children_of_the_snapshot_block() {
+		let header = |number, parent_hash| {
+			Header::new(number, Hash::zero(), Hash::zero(), parent_hash, Default::default())
+		};
+		let snapshot = header(10, Hash::repeat_byte(1));
+		assert!(check_parent(&header(11, snapshot.hash()), snapshot.hash()).is_ok());
+		assert!(check_parent(&header(11, Hash::repeat_byte(2)), snapshot.hash()).is_err());
+	}
+}
@@ -0,0 +1,186 @@
+//! The `utilization` subcommand, reporting how full the blocks of a range are.
+
+use std::sync::Arc;
+
+use frame_support::dispatch::DispatchClass;
+use node_template_client::{
+	utilization::{BlockUtilization, ClassUtilization},
+	Client,
+};
+use node_template_runtime::{BlockNumber, Weight};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sp_blockchain::HeaderBackend;
+
+use crate::service::FullClient;
+
+/// Report the weight and the length consumed by each dispatch class in a range of blocks.
+///
+/// The weight of a class is read from `System::BlockWeight` and compared with its maximum in
+/// `BlockWeights`, the length of its extrinsics with its maximum in `BlockLength`. The heaviest
+/// calls of the range are listed with their actual weight, read from their dispatch info. Each
+/// block is decoded with the metadata of its runtime, and the blocks whose state does not decode
+/// are listed rather than failing the report.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct UtilizationCmd {
+	/// The first block of the range.
+	#[arg(long, default_value_t = 1)]
+	pub from: BlockNumber,
+
+	/// The last block of the range. The best block by default.
+	#[arg(long)]
+	pub to: Option<BlockNumber>,
+
+	/// The number of heaviest calls to list.
+	#[arg(long, default_value_t = 10)]
+	pub top: u32,
+
+	/// Print the utilization of every block as JSON instead of a report.
+	#[arg(long)]
+	pub json: bool,
+
+	/// Read the blocks from a running node at this WebSocket RPC endpoint, through its
+	/// `utilization_blocks` RPC, instead of the local database.
+	#[arg(long)]
+	pub url: Option<String>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl UtilizationCmd {
+	/// Run the utilization command against the local database.
+	pub fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+		let to = self.to.unwrap_or_else(|| client.info().best_number);
+		let blocks = crate::rpc::utilization::blocks(&*client, self.from, to, self.top)?;
+		self.print(&blocks)
+	}
+
+	/// Run the utilization command against the node at `--url`.
+	pub fn run_remote(&self, url: &str) -> sc_cli::Result<()> {
+		let runtime = tokio::runtime::Runtime::new()?;
+		let blocks = runtime
+			.block_on(async {
+				let client = Client::connect(url).await?;
+				client.utilization(self.from, self.to, Some(self.top)).await
+			})
+			.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+		self.print(&blocks)
+	}
+
+	fn print(&self, blocks: &[BlockUtilization]) -> sc_cli::Result<()> {
+		if self.json {
+			let json = serde_json::to_string_pretty(blocks)
+				.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+			println!("{json}");
+			return Ok(())
+		}
+		let (Some(first), Some(last)) = (blocks.first(), blocks.last()) else {
+			return Err("The range holds no block".into())
+		};
+
+		println!("Blocks #{} to #{}", first.number, last.number);
+		let undecodable: Vec<_> = blocks
+			.iter()
+			.filter(|block| block.undecodable)
+			.map(|block| format!("#{}", block.number))
+			.collect();
+		if !undecodable.is_empty() {
+			println!("Undecodable state, extrinsics not counted: {}", undecodable.join(", "));
+		}
+		for class in [DispatchClass::Normal, DispatchClass::Operational, DispatchClass::Mandatory] {
+			let extrinsics: u32 = blocks.iter().map(|block| block.class(class).extrinsics).sum();
+			println!("{class:?}: {extrinsics} extrinsics");
+			println!(
+				"  ref time:   {}",
+				summary(blocks, |block| {
+					let utilization = block.class(class);
+					percent(
+						utilization.weight.ref_time(),
+						max_weight(block, utilization).ref_time(),
+					)
+				})
+			);
+			println!(
+				"  proof size: {}",
+				summary(blocks, |block| {
+					let utilization = block.class(class);
+					percent(
+						utilization.weight.proof_size(),
+						max_weight(block, utilization).proof_size(),
+					)
+				})
+			);
+			println!(
+				"  length:     {}",
+				summary(blocks, |block| {
+					let utilization = block.class(class);
+					percent(utilization.length.into(), utilization.max_length.into())
+				})
+			);
+		}
+
+		let mut calls: Vec<_> = blocks
+			.iter()
+			.flat_map(|block| block.top_calls.iter().map(move |call| (block, call)))
+			.collect();
+		calls.sort_by_key(|(block, call)| {
+			(std::cmp::Reverse(call.weight.ref_time()), block.number, call.extrinsic_index)
+		});
+		println!("Heaviest calls:");
+		for (block, call) in calls.into_iter().take(self.top as usize) {
+			let name = match call.pallet.is_empty() {
+				true => "<undecodable>".to_string(),
+				false => format!("{}::{}", call.pallet, call.call),
+			};
+			println!(
+				"  #{}-{} {name} ({:?}): ref time {} ({:.2}% of max block), proof size {}, length {}",
+				block.number,
+				call.extrinsic_index,
+				call.class,
+				call.weight.ref_time(),
+				percent(call.weight.ref_time(), block.max_block.ref_time()),
+				call.weight.proof_size(),
+				call.length,
+			);
+		}
+		Ok(())
+	}
+}
+
+/// The maximum weight of a class, the one of the whole block when the class is unlimited.
+fn max_weight(block: &BlockUtilization, utilization: &ClassUtilization) -> Weight {
+	utilization.max_weight.unwrap_or(block.max_block)
+}
+
+/// The average and the peak of a percentage over `blocks`.
+fn summary(blocks: &[BlockUtilization], percentage: impl Fn(&BlockUtilization) -> f64) -> String {
+	let mut total = 0.0;
+	let mut peak = (0.0, blocks[0].number);
+	for block in blocks {
+		let value = percentage(block);
+		total += value;
+		if value > peak.0 {
+			peak = (value, block.number);
+		}
+	}
+	format!("average {:.2}%, peak {:.2}% at #{}", total / blocks.len() as f64, peak.0, peak.1)
+}
+
+fn percent(used: u64, max: u64) -> f64 {
+	used as f64 * 100.0 / max.max(1) as f64
+}
+
+impl CliConfiguration for UtilizationCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,128 @@
+//! Tests of `node-template-client` against a development node spawned from this package's binary.
+
+use std::{
+	net::TcpListener,
+	process::{Child, Command, Stdio},
+	time::Duration,
+};
+
+use codec::Encode;
+use node_template_client::{Client, TxParams, WaitFor};
+use node_template_runtime::{pallet_template, BalancesCall, Runtime, RuntimeEvent, UNIT};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::DispatchError;
+
+/// A `--dev --instant-seal` node with a temporary database, killed on drop.
+struct DevNode {
+	process: Child,
+	rpc_port: u16,
+}
+
+impl DevNode {
+	fn spawn() -> Self {
+		let rpc_port = free_port();
+		let process = Command::new(env!("CARGO_BIN_EXE_node-template"))
+			.args(["--dev", "--instant-seal", "--tmp", "--no-telemetry", "--no-prometheus"])
+			.args(["--rpc-port", &rpc_port.to_string(), "--port", &free_port().to_string()])
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+			.expect("node-template is built with the tests; qed");
+		Self { process, rpc_port }
+	}
+
+	/// Connects to the node, waiting for its RPC server to start.
+	async fn client(&self) -> Client {
+		let url = format!("ws://127.0.0.1:{}", self.rpc_port);
+		for _ in 0..120 {
+			if let Ok(client) = Client::connect(&url).await {
+				return client
+			}
+			tokio::time::sleep(Duration::from_millis(500)).await;
+		}
+		panic!("the node did not start its RPC server at {url}");
+	}
+}
+
+impl Drop for DevNode {
+	fn drop(&mut self) {
+		let _ = self.process.kill();
+		let _ = self.process.wait();
+	}
+}
+
+fn free_port() -> u16 {
+	TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+#[tokio::test]
+async fn transfer_is_finalized_with_its_events() {
+	let node = DevNode::spawn();
+	let client = node.client().await;
+	let bob = Sr25519Keyring::Bob.to_account_id();
+
+	let call = BalancesCall::transfer_keep_alive { dest: bob.clone().into(), value: UNIT };
+	let xt = client
+		.sign(call, &Sr25519Keyring::Alice.pair(), TxParams::default())
+		.await
+		.unwrap();
+	let included = client.submit(&xt, WaitFor::Finalized).await.unwrap();
+
+	assert_eq!(included.dispatch_result(), Ok(()));
+	assert!(included.events.iter().any(|event| matches!(
+		event,
+		RuntimeEvent::Balances(pallet_balances::Event::Transfer { to, amount, .. })
+			if to == &bob && *amount == UNIT
+	)));
+}
+
+#[tokio::test]
+async fn template_calls_report_events_and_errors() {
+	let node = DevNode::spawn();
+	let client = node.client().await;
+	let alice = Sr25519Keyring::Alice;
+
+	// `cause_error` fails while `Something` is not set.
+	let xt = client
+		.sign(pallet_template::Call::cause_error {}, &alice.pair(), TxParams::default())
+		.await
+		.unwrap();
+	let included = client.submit(&xt, WaitFor::InBlock).await.unwrap();
+	// The message of module errors is not encoded, compare the encoded errors.
+	assert_eq!(
+		included.dispatch_result().map_err(|e| e.encode()),
+		Err(DispatchError::from(pallet_template::Error::<Runtime>::NoneValue).encode())
+	);
+
+	let xt = client
+		.sign(
+			pallet_template::Call::do_something { something: 42 },
+			&alice.pair(),
+			TxParams { tip: 10, mortality: None, ..Default::default() },
+		)
+		.await
+		.unwrap();
+	let included = client.submit(&xt, WaitFor::InBlock).await.unwrap();
+	assert_eq!(included.dispatch_result(), Ok(()));
+	assert!(included.events.contains(&RuntimeEvent::TemplateModule(
+		pallet_template::Event::SomethingStored { something: 42, who: alice.to_account_id() }
+	)));
+}
+
+#[tokio::test]
+async fn blocks_sealed_faster_than_once_per_slot_are_imported() {
+	let node = DevNode::spawn();
+	let client = node.client().await;
+	let bob = Sr25519Keyring::Bob.to_account_id();
+
+	// Each transaction is sealed in a block of its own, well within a slot of the previous one.
+	for _ in 0..3 {
+		let call = BalancesCall::transfer_keep_alive { dest: bob.clone().into(), value: UNIT };
+		let xt = client
+			.sign(call, &Sr25519Keyring::Alice.pair(), TxParams::default())
+			.await
+			.unwrap();
+		let included = client.submit(&xt, WaitFor::Finalized).await.unwrap();
+		assert_eq!(included.dispatch_result(), Ok(()));
+	}
+}
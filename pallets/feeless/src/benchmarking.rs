@@ -0,0 +1,82 @@
+//! Benchmarking setup for pallet-feeless
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Feeless;
+use frame_benchmarking::v2::*;
+use frame_support::traits::EnsureOrigin;
+
+#[benchmarks(where T::RuntimeCall: From<frame_system::Call<T>>)]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn whitelist_account() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let who: T::AccountId = account("who", 0, 0);
+		#[extrinsic_call]
+		whitelist_account(origin as T::RuntimeOrigin, who.clone());
+
+		assert!(Accounts::<T>::contains_key(who));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_account() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let who: T::AccountId = account("who", 0, 0);
+		Accounts::<T>::insert(&who, ());
+		#[extrinsic_call]
+		remove_account(origin as T::RuntimeOrigin, who.clone());
+
+		assert!(!Accounts::<T>::contains_key(who));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn whitelist_call() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		#[extrinsic_call]
+		whitelist_call(origin as T::RuntimeOrigin, (1, 0), Condition::Once);
+
+		assert_eq!(Calls::<T>::get((1, 0)), Some(Condition::Once));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_call() -> Result<(), BenchmarkError> {
+		let origin =
+			T::ManagerOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		Calls::<T>::insert((1, 0), Condition::Always);
+		#[extrinsic_call]
+		remove_call(origin as T::RuntimeOrigin, (1, 0));
+
+		assert!(!Calls::<T>::contains_key((1, 0)));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn use_exemption() {
+		// The worst case: a call feeless once, so that using it is recorded too.
+		let who: T::AccountId = account("who", 0, 0);
+		frame_system::Pallet::<T>::inc_providers(&who);
+		let call: T::RuntimeCall =
+			frame_system::Call::<T>::remark { remark: Default::default() }.into();
+		let index = Feeless::<T>::call_index(&call);
+		Calls::<T>::insert(index, Condition::Once);
+
+		#[block]
+		{
+			let exemption = Feeless::<T>::exemption(&who, &call).expect("the call is feeless once");
+			Feeless::<T>::use_exemption(exemption);
+		}
+
+		assert!(Used::<T>::contains_key(&who, index));
+	}
+
+	impl_benchmark_test_suite!(Feeless, crate::mock::new_test_ext(), crate::mock::Test);
+}
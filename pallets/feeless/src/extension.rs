@@ -0,0 +1,139 @@
+//! A signed extension skipping the extension that charges fees for feeless transactions.
+//!
+//! [`SkipFeeless`] wraps the fee extension of the runtime, e.g. `ChargeAssetTxPayment`. It
+//! encodes, signs and appears in the metadata as the extension it wraps, so clients build
+//! transactions as before. For a transaction [`Pallet::exemption`] exempts, it counts the
+//! transaction against the rate limits instead of running the wrapped extension, which then
+//! neither charges the fee nor the tip, and adds the weight of doing so to the block.
+//!
+//! A feeless transaction is valid in the pool until its period ends, and provides a tag of its
+//! sender and period: the pool holds one feeless transaction of each account at a time, rather
+//! than as many as the sender can sign while the rate limits are only counted on dispatch.
+
+use crate::{Config, Pallet, WeightInfo};
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use scale_info::{StaticTypeInfo, TypeInfo};
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, PostDispatchInfoOf, SignedExtension, SignedExtensionMetadata,
+		UniqueSaturatedInto,
+	},
+	transaction_validity::{TransactionValidity, TransactionValidityError, ValidTransaction},
+	DispatchResult,
+};
+use sp_std::vec::Vec;
+
+/// Runs the fee extension `S` unless the transaction is feeless.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct SkipFeeless<T, S>(pub S, PhantomData<T>);
+
+impl<T, S> From<S> for SkipFeeless<T, S> {
+	fn from(extension: S) -> Self {
+		Self(extension, PhantomData)
+	}
+}
+
+impl<T, S: StaticTypeInfo> TypeInfo for SkipFeeless<T, S> {
+	type Identity = S;
+
+	fn type_info() -> scale_info::Type {
+		S::type_info()
+	}
+}
+
+impl<T, S: core::fmt::Debug> core::fmt::Debug for SkipFeeless<T, S> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "SkipFeeless<{:?}>", self.0)
+	}
+}
+
+impl<T, S> SignedExtension for SkipFeeless<T, S>
+where
+	T: Config + Send + Sync,
+	S: SignedExtension<AccountId = T::AccountId, Call = T::RuntimeCall> + StaticTypeInfo,
+{
+	const IDENTIFIER: &'static str = S::IDENTIFIER;
+	type AccountId = T::AccountId;
+	type Call = T::RuntimeCall;
+	type AdditionalSigned = S::AdditionalSigned;
+	/// The pre-dispatch data of `S`, `None` when it was skipped.
+	type Pre = Option<S::Pre>;
+
+	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+		self.0.additional_signed()
+	}
+
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		S::metadata()
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		match Pallet::<T>::exemption(who, call) {
+			Some(exemption) => ValidTransaction::with_tag_prefix("SkipFeeless")
+				.and_provides((who, exemption.period()))
+				.longevity(exemption.left().unique_saturated_into())
+				.build(),
+			None => self.0.validate(who, call, info, len),
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		match Pallet::<T>::exemption(who, call) {
+			Some(exemption) => {
+				// The weight of the transaction does not include counting it against the rate
+				// limits.
+				frame_system::Pallet::<T>::register_extra_weight_unchecked(
+					T::WeightInfo::use_exemption(),
+					info.class,
+				);
+				Pallet::<T>::use_exemption(exemption);
+				Ok(None)
+			},
+			None => Ok(Some(self.0.pre_dispatch(who, call, info, len)?)),
+		}
+	}
+
+	fn validate_unsigned(
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		S::validate_unsigned(call, info, len)
+	}
+
+	fn pre_dispatch_unsigned(
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		S::pre_dispatch_unsigned(call, info, len)
+	}
+
+	fn post_dispatch(
+		pre: Option<Self::Pre>,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			// Skipped for a feeless transaction.
+			Some(None) => Ok(()),
+			Some(Some(pre)) => S::post_dispatch(Some(pre), info, post_info, len, result),
+			None => S::post_dispatch(None, info, post_info, len, result),
+		}
+	}
+}
@@ -0,0 +1,353 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Exempts whitelisted accounts and calls from transaction fees.
+//!
+//! [`ManagerOrigin`](Config::ManagerOrigin), sudo or governance, whitelists accounts, all of
+//! whose transactions are feeless, and calls, identified by the index of their pallet and their
+//! own index. A call is feeless for every account, or only the first time each account makes it,
+//! e.g. for the first `do_something` of a new account. Whitelisted calls are only feeless for
+//! accounts that exist, i.e. have a provider such as the existential deposit, so that throwaway
+//! keys can neither send them for free nor make the chain store their usage for free.
+//!
+//! The [`SkipFeeless`] signed extension wraps the extension charging fees and skips it for exempt
+//! transactions. Feeless transactions are rate limited: an account may send at most
+//! [`MaxPerAccount`](Config::MaxPerAccount) of them, and all accounts together at most
+//! [`MaxPerPeriod`](Config::MaxPerPeriod), in every period of [`Period`](Config::Period) blocks.
+//! Past these limits transactions pay their fees as usual. The transaction pool holds at most one
+//! feeless transaction of each account at a time.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod extension;
+pub mod weights;
+pub use extension::SkipFeeless;
+pub use weights::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::One;
+
+	/// A call, as the index of its pallet in the runtime and its index in the pallet: the first
+	/// two bytes of the encoded call.
+	pub type CallIndex = (u8, u8);
+
+	/// Which transactions making a whitelisted call are feeless.
+	#[derive(Clone, Copy, Debug, Encode, Decode, Eq, PartialEq, TypeInfo, MaxEncodedLen)]
+	pub enum Condition {
+		/// All of them.
+		Always,
+		/// The first one of each account.
+		Once,
+	}
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		/// Type representing the weight of this pallet
+		type WeightInfo: WeightInfo;
+		/// The origin allowed to whitelist accounts and calls.
+		type ManagerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+		/// The length in blocks of the periods feeless transactions are limited over.
+		#[pallet::constant]
+		type Period: Get<BlockNumberFor<Self>>;
+		/// The most feeless transactions an account may send in a period.
+		#[pallet::constant]
+		type MaxPerAccount: Get<u32>;
+		/// The most feeless transactions all accounts together may send in a period.
+		#[pallet::constant]
+		type MaxPerPeriod: Get<u32>;
+	}
+
+	/// The accounts whose transactions are feeless.
+	#[pallet::storage]
+	pub type Accounts<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
+	/// The calls that are feeless, and for which transactions.
+	#[pallet::storage]
+	pub type Calls<T: Config> = StorageMap<_, Twox64Concat, CallIndex, Condition>;
+
+	/// The calls whitelisted [`Condition::Once`] each account already made without fee.
+	#[pallet::storage]
+	pub type Used<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, CallIndex, ()>;
+
+	/// The first block of the last period each account sent feeless transactions in, with the
+	/// number it sent in that period. Removed by [`Hooks::on_idle`] once the period is over.
+	#[pallet::storage]
+	pub type AccountUsage<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (BlockNumberFor<T>, u32)>;
+
+	/// Where [`Hooks::on_idle`] is in removing the `AccountUsage` of past periods: the first block
+	/// of the period the last pass started in, and the account it stopped after if the pass is not
+	/// over.
+	#[pallet::storage]
+	pub type AccountUsageCursor<T: Config> =
+		StorageValue<_, (BlockNumberFor<T>, Option<T::AccountId>)>;
+
+	/// The first block of the last period feeless transactions were sent in, with the number sent
+	/// in that period.
+	#[pallet::storage]
+	pub type PeriodUsage<T: Config> = StorageValue<_, (BlockNumberFor<T>, u32)>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The transactions of an account are now feeless.
+		AccountWhitelisted { who: T::AccountId },
+		/// The transactions of an account pay fees again.
+		AccountRemoved { who: T::AccountId },
+		/// A call is now feeless under a condition.
+		CallWhitelisted { call: CallIndex, condition: Condition },
+		/// A call pays fees again.
+		CallRemoved { call: CallIndex },
+		/// The fee of a transaction was waived.
+		FeeWaived { who: T::AccountId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account or the call is not whitelisted.
+		NotWhitelisted,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			Self::prune_account_usage(now, remaining_weight)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Makes the transactions of `who` feeless. Must be dispatched by `ManagerOrigin`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::whitelist_account())]
+		pub fn whitelist_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Accounts::<T>::insert(&who, ());
+			Self::deposit_event(Event::AccountWhitelisted { who });
+			Ok(())
+		}
+
+		/// Makes the transactions of `who` pay fees again. Must be dispatched by `ManagerOrigin`.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::remove_account())]
+		pub fn remove_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(Accounts::<T>::contains_key(&who), Error::<T>::NotWhitelisted);
+			Accounts::<T>::remove(&who);
+			Self::deposit_event(Event::AccountRemoved { who });
+			Ok(())
+		}
+
+		/// Makes the transactions making `call` feeless under `condition`, replacing the previous
+		/// condition. Must be dispatched by `ManagerOrigin`.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::whitelist_call())]
+		pub fn whitelist_call(
+			origin: OriginFor<T>,
+			call: CallIndex,
+			condition: Condition,
+		) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			Calls::<T>::insert(call, condition);
+			Self::deposit_event(Event::CallWhitelisted { call, condition });
+			Ok(())
+		}
+
+		/// Makes the transactions making `call` pay fees again. Must be dispatched by
+		/// `ManagerOrigin`.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::remove_call())]
+		pub fn remove_call(origin: OriginFor<T>, call: CallIndex) -> DispatchResult {
+			T::ManagerOrigin::ensure_origin(origin)?;
+			ensure!(Calls::<T>::contains_key(call), Error::<T>::NotWhitelisted);
+			Calls::<T>::remove(call);
+			Self::deposit_event(Event::CallRemoved { call });
+			Ok(())
+		}
+	}
+
+	/// A transaction exempt from fees, within the rate limits.
+	pub struct Exemption<T: Config> {
+		who: T::AccountId,
+		/// The call consumed by the transaction, if feeless once.
+		once: Option<CallIndex>,
+		period: BlockNumberFor<T>,
+		/// The number of blocks left in `period`, the current one included.
+		left: BlockNumberFor<T>,
+		account_count: u32,
+		period_count: u32,
+	}
+
+	impl<T: Config> Exemption<T> {
+		/// The first block of the period the transaction is counted in.
+		pub fn period(&self) -> BlockNumberFor<T> {
+			self.period
+		}
+
+		/// The number of blocks left in the period the transaction is counted in, the current one
+		/// included.
+		pub fn left(&self) -> BlockNumberFor<T> {
+			self.left
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The index of `call`.
+		pub fn call_index(call: &T::RuntimeCall) -> CallIndex {
+			call.using_encoded(|encoded| match encoded {
+				[pallet, call, ..] => (*pallet, *call),
+				_ => (0, 0),
+			})
+		}
+
+		/// The exemption of a transaction of `who` making `call`, if it is feeless and within the
+		/// rate limits of the current period.
+		///
+		/// Whitelisted calls are only feeless for accounts with a provider: an account that does
+		/// not exist would otherwise be created by the transaction without paying for it.
+		pub fn exemption(who: &T::AccountId, call: &T::RuntimeCall) -> Option<Exemption<T>> {
+			let index = Self::call_index(call);
+			let once = match Calls::<T>::get(index) {
+				_ if Accounts::<T>::contains_key(who) => None,
+				_ if frame_system::Pallet::<T>::providers(who) == 0 => return None,
+				Some(Condition::Always) => None,
+				Some(Condition::Once) if !Used::<T>::contains_key(who, index) => Some(index),
+				_ => return None,
+			};
+
+			let (period, left) = Self::period();
+			let count = |usage: Option<(BlockNumberFor<T>, u32)>| match usage {
+				Some((start, count)) if start == period => count,
+				_ => 0,
+			};
+			let account_count = count(AccountUsage::<T>::get(who));
+			let period_count = count(PeriodUsage::<T>::get());
+			if account_count >= T::MaxPerAccount::get() || period_count >= T::MaxPerPeriod::get() {
+				return None
+			}
+			Some(Exemption { who: who.clone(), once, period, left, account_count, period_count })
+		}
+
+		/// Whether transactions making `call` are feeless whichever existing account sends them,
+		/// the rate limit of all accounts permitting.
+		pub fn is_feeless_call(call: &T::RuntimeCall) -> bool {
+			let period_count = match PeriodUsage::<T>::get() {
+				Some((start, count)) if start == Self::period().0 => count,
+				_ => 0,
+			};
+			Calls::<T>::get(Self::call_index(call)) == Some(Condition::Always) &&
+				period_count < T::MaxPerPeriod::get()
+		}
+
+		/// Counts the feeless transaction of `exemption` against the rate limits.
+		pub(crate) fn use_exemption(exemption: Exemption<T>) {
+			let Exemption { who, once, period, account_count, period_count, .. } = exemption;
+			if let Some(call) = once {
+				Used::<T>::insert(&who, call, ());
+			}
+			AccountUsage::<T>::insert(&who, (period, account_count + 1));
+			PeriodUsage::<T>::put((period, period_count + 1));
+			Self::deposit_event(Event::FeeWaived { who });
+		}
+
+		/// The first block of the current period, and the number of blocks left in it, the
+		/// current one included.
+		fn period() -> (BlockNumberFor<T>, BlockNumberFor<T>) {
+			let now = frame_system::Pallet::<T>::block_number();
+			let length = T::Period::get().max(One::one());
+			let start = now - now % length;
+			(start, start + length - now)
+		}
+
+		/// Removes the `AccountUsage` of periods that are over, within `limit`, returning the
+		/// weight used.
+		///
+		/// A pass goes over all the usages once per period, resuming in the next block where it
+		/// ran out of weight. Usages of a period that ends while a pass is under way may be left
+		/// until the pass of the next period.
+		pub(crate) fn prune_account_usage(now: BlockNumberFor<T>, limit: Weight) -> Weight {
+			let db = T::DbWeight::get();
+			// Reading and writing the cursor.
+			let mut used = db.reads_writes(1, 1);
+			// Reading a usage, and removing it if its period is over.
+			let per_usage = db.reads_writes(1, 1);
+			if used.saturating_add(per_usage).any_gt(limit) {
+				return Weight::zero()
+			}
+
+			let start = now - now % T::Period::get().max(One::one());
+			let resume = match AccountUsageCursor::<T>::get() {
+				Some((pass_start, None)) if pass_start == start => return db.reads(1),
+				Some((_, Some(last))) => Some(last),
+				_ => None,
+			};
+			let mut usages = match resume {
+				Some(last) => AccountUsage::<T>::iter_from(AccountUsage::<T>::hashed_key_for(last)),
+				None => AccountUsage::<T>::iter(),
+			};
+			let mut last = None;
+			let cursor = loop {
+				if used.saturating_add(per_usage).any_gt(limit) {
+					break last
+				}
+				match usages.next() {
+					Some((who, (period, _))) => {
+						used.saturating_accrue(per_usage);
+						// Removing the usage just read leaves the iteration unaffected.
+						if period < start {
+							AccountUsage::<T>::remove(&who);
+						}
+						last = Some(who);
+					},
+					None => break None,
+				}
+			};
+			AccountUsageCursor::<T>::put((start, cursor));
+			used
+		}
+
+		/// Checks the invariants of the pallet's storage.
+		///
+		/// No account, nor all accounts together, may have sent more feeless transactions than
+		/// allowed in the current period.
+		#[cfg(any(feature = "try-runtime", test))]
+		pub fn do_try_state() -> Result<(), &'static str> {
+			let (period, _) = Self::period();
+			ensure!(
+				AccountUsage::<T>::iter_values()
+					.all(|(start, count)| { start != period || count <= T::MaxPerAccount::get() }),
+				"An account sent more feeless transactions than allowed in the current period"
+			);
+			ensure!(
+				PeriodUsage::<T>::get().map_or(true, |(start, count)| {
+					start != period || count <= T::MaxPerPeriod::get()
+				}),
+				"More feeless transactions were sent than allowed in the current period"
+			);
+			Ok(())
+		}
+	}
+}
@@ -0,0 +1,139 @@
+use crate as pallet_feeless;
+use codec::{Decode, Encode};
+use frame_support::{
+	parameter_types,
+	traits::{ConstU16, ConstU32, ConstU64},
+	weights::constants::RocksDbWeight,
+};
+use frame_system::EnsureRoot;
+use scale_info::TypeInfo;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, DispatchInfoOf, IdentityLookup, SignedExtension},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Feeless: pallet_feeless,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = RocksDbWeight;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_feeless::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type ManagerOrigin = EnsureRoot<u64>;
+	type Period = ConstU64<10>;
+	type MaxPerAccount = ConstU32<2>;
+	type MaxPerPeriod = ConstU32<3>;
+}
+
+parameter_types! {
+	/// The fees [`ChargeFee`] charged, per account.
+	pub static Charged: Vec<u64> = vec![];
+}
+
+/// Stands in for the fee extension of the runtime: records the accounts it charges, and rejects
+/// account 0 and accounts that do not exist as unable to pay.
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq, TypeInfo)]
+pub struct ChargeFee;
+
+impl SignedExtension for ChargeFee {
+	const IDENTIFIER: &'static str = "ChargeFee";
+	type AccountId = u64;
+	type Call = RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = u64;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &u64,
+		_call: &RuntimeCall,
+		_info: &DispatchInfoOf<RuntimeCall>,
+		_len: usize,
+	) -> TransactionValidity {
+		match who {
+			0 => Err(InvalidTransaction::Payment.into()),
+			_ if !System::account_exists(who) => Err(InvalidTransaction::Payment.into()),
+			_ => Ok(ValidTransaction { priority: 10, ..Default::default() }),
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &u64,
+		call: &RuntimeCall,
+		info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> Result<u64, TransactionValidityError> {
+		self.validate(who, call, info, len)?;
+		Charged::mutate(|charged| charged.push(*who));
+		Ok(*who)
+	}
+}
+
+// Build genesis storage according to the mock runtime. Accounts 0 to 3 exist, as if they held the
+// existential deposit.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities =
+		frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		for who in 0..=3 {
+			System::inc_providers(&who);
+		}
+	});
+	Charged::set(vec![]);
+	ext
+}
+
+/// Runs `test` on a fresh mock runtime, then checks the invariants of the pallet.
+pub fn build_and_execute(test: impl FnOnce()) {
+	new_test_ext().execute_with(|| {
+		test();
+		Feeless::do_try_state().unwrap();
+	});
+}
@@ -0,0 +1,266 @@
+use crate::{mock::*, Condition, Error, Event, SkipFeeless};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	DispatchError,
+};
+
+fn remark() -> RuntimeCall {
+	frame_system::Call::remark { remark: vec![1] }.into()
+}
+
+fn remark_with_event() -> RuntimeCall {
+	frame_system::Call::remark_with_event { remark: vec![1] }.into()
+}
+
+/// Runs the extension for a transaction of `who` making `call`, returning whether it was feeless.
+fn apply(who: u64, call: &RuntimeCall) -> Result<bool, TransactionValidityError> {
+	let extension = SkipFeeless::<Test, _>::from(ChargeFee);
+	let info = Default::default();
+	let valid = extension.validate(&who, call, &info, 0)?;
+	let pre = extension.pre_dispatch(&who, call, &info, 0)?;
+	let post_info = Default::default();
+	SkipFeeless::<Test, ChargeFee>::post_dispatch(
+		Some(pre.clone()),
+		&info,
+		&post_info,
+		0,
+		&Ok(()),
+	)?;
+	assert_eq!(valid.priority == 0, pre.is_none(), "validate and pre_dispatch agree");
+	Ok(pre.is_none())
+}
+
+#[test]
+fn whitelisting_requires_manager_origin() {
+	build_and_execute(|| {
+		assert_noop!(
+			Feeless::whitelist_account(RuntimeOrigin::signed(1), 1),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Feeless::whitelist_call(RuntimeOrigin::signed(1), (0, 0), Condition::Always),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Feeless::remove_account(RuntimeOrigin::root(), 1),
+			Error::<Test>::NotWhitelisted
+		);
+		assert_noop!(
+			Feeless::remove_call(RuntimeOrigin::root(), (0, 0)),
+			Error::<Test>::NotWhitelisted
+		);
+
+		assert_ok!(Feeless::whitelist_account(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::AccountWhitelisted { who: 1 }.into());
+		assert_ok!(Feeless::remove_account(RuntimeOrigin::root(), 1));
+		System::assert_last_event(Event::AccountRemoved { who: 1 }.into());
+	});
+}
+
+#[test]
+fn whitelisted_accounts_skip_fees() {
+	build_and_execute(|| {
+		assert_ok!(Feeless::whitelist_account(RuntimeOrigin::root(), 1));
+
+		assert_eq!(apply(1, &remark()), Ok(true));
+		System::assert_last_event(Event::FeeWaived { who: 1 }.into());
+		assert_eq!(apply(2, &remark()), Ok(false));
+		assert_eq!(Charged::get(), vec![2]);
+
+		// Feeless transactions are valid without the means to pay.
+		assert_ok!(Feeless::whitelist_account(RuntimeOrigin::root(), 0));
+		assert_eq!(apply(0, &remark()), Ok(true));
+	});
+}
+
+#[test]
+fn whitelisted_calls_skip_fees_always_or_once() {
+	build_and_execute(|| {
+		let always = Feeless::call_index(&remark());
+		let once = Feeless::call_index(&remark_with_event());
+		assert_ok!(Feeless::whitelist_call(RuntimeOrigin::root(), always, Condition::Always));
+		assert_ok!(Feeless::whitelist_call(RuntimeOrigin::root(), once, Condition::Once));
+		assert!(Feeless::is_feeless_call(&remark()));
+		assert!(!Feeless::is_feeless_call(&remark_with_event()));
+
+		assert_eq!(apply(1, &remark_with_event()), Ok(true));
+		assert_eq!(apply(1, &remark_with_event()), Ok(false));
+		assert_eq!(apply(2, &remark_with_event()), Ok(true));
+		System::set_block_number(20);
+		assert_eq!(apply(1, &remark()), Ok(true));
+		assert_eq!(apply(0, &remark_with_event()), Ok(true));
+		assert_eq!(
+			apply(0, &remark_with_event()),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Payment))
+		);
+		assert_eq!(Charged::get(), vec![1]);
+
+		assert_ok!(Feeless::remove_call(RuntimeOrigin::root(), always));
+		assert_eq!(apply(1, &remark()), Ok(false));
+	});
+}
+
+#[test]
+fn feeless_transactions_are_rate_limited() {
+	build_and_execute(|| {
+		assert_ok!(Feeless::whitelist_call(
+			RuntimeOrigin::root(),
+			Feeless::call_index(&remark()),
+			Condition::Always
+		));
+
+		// Two feeless transactions per account and three in total per period of ten blocks, then
+		// fees are charged.
+		assert_eq!(apply(1, &remark()), Ok(true));
+		assert_eq!(apply(1, &remark()), Ok(true));
+		assert_eq!(apply(1, &remark()), Ok(false));
+		assert_eq!(apply(2, &remark()), Ok(true));
+		assert!(!Feeless::is_feeless_call(&remark()));
+		assert_eq!(apply(3, &remark()), Ok(false));
+		assert_eq!(apply(0, &remark()), Err(InvalidTransaction::Payment.into()));
+		assert_eq!(Charged::get(), vec![1, 3]);
+
+		// The limits start over with the next period.
+		System::set_block_number(10);
+		assert!(Feeless::is_feeless_call(&remark()));
+		assert_eq!(apply(1, &remark()), Ok(true));
+		assert_eq!(apply(3, &remark()), Ok(true));
+	});
+}
+
+#[test]
+fn throwaway_accounts_cannot_use_up_the_rate_limit() {
+	build_and_execute(|| {
+		assert_ok!(Feeless::whitelist_call(
+			RuntimeOrigin::root(),
+			Feeless::call_index(&remark()),
+			Condition::Always
+		));
+		assert_ok!(Feeless::whitelist_call(
+			RuntimeOrigin::root(),
+			Feeless::call_index(&remark_with_event()),
+			Condition::Once
+		));
+
+		// Keys without a provider are not exempt, so they pay or are rejected, and nothing of
+		// theirs is stored.
+		for who in 10..20 {
+			assert!(!System::account_exists(&who));
+			assert_eq!(apply(who, &remark()), Err(InvalidTransaction::Payment.into()));
+			assert_eq!(apply(who, &remark_with_event()), Err(InvalidTransaction::Payment.into()));
+		}
+		assert_eq!(crate::AccountUsage::<Test>::iter().count(), 0);
+		assert_eq!(crate::Used::<Test>::iter().count(), 0);
+		assert_eq!(crate::PeriodUsage::<Test>::get(), None);
+
+		// Which leaves the limit of the period to the accounts that exist.
+		assert_eq!(apply(1, &remark()), Ok(true));
+		assert_eq!(apply(2, &remark_with_event()), Ok(true));
+		assert_eq!(apply(3, &remark()), Ok(true));
+		assert_eq!(crate::PeriodUsage::<Test>::get(), Some((0, 3)));
+
+		// Whitelisted accounts need no provider.
+		System::set_block_number(10);
+		assert_ok!(Feeless::whitelist_account(RuntimeOrigin::root(), 10));
+		assert_eq!(apply(10, &remark()), Ok(true));
+	});
+}
+
+#[test]
+fn feeless_transactions_take_one_place_in_the_pool_until_the_period_ends() {
+	use codec::Encode;
+
+	build_and_execute(|| {
+		assert_ok!(Feeless::whitelist_call(
+			RuntimeOrigin::root(),
+			Feeless::call_index(&remark()),
+			Condition::Always
+		));
+		let extension = SkipFeeless::<Test, _>::from(ChargeFee);
+		let info = Default::default();
+
+		// Each account provides one tag per period, so a second feeless transaction of the same
+		// account only replaces the first in the pool.
+		System::set_block_number(13);
+		let valid = extension.validate(&1, &remark(), &info, 0).unwrap();
+		assert_eq!(valid.provides, vec![("SkipFeeless", (1u64, 10u64)).encode()]);
+		assert_eq!(valid.longevity, 7);
+		let other = extension.validate(&2, &remark(), &info, 0).unwrap();
+		assert_ne!(other.provides, valid.provides);
+
+		// Transactions paying their fees are left to the wrapped extension.
+		assert_ok!(Feeless::remove_call(RuntimeOrigin::root(), Feeless::call_index(&remark())));
+		let paid = extension.validate(&1, &remark(), &info, 0).unwrap();
+		assert!(paid.provides.is_empty());
+		assert_eq!(paid.priority, 10);
+	});
+}
+
+#[test]
+fn account_usage_of_past_periods_is_pruned_when_idle() {
+	use frame_support::{
+		traits::{Get, Hooks},
+		weights::Weight,
+	};
+
+	build_and_execute(|| {
+		let db = <Test as frame_system::Config>::DbWeight::get();
+		System::set_block_number(13);
+		for who in 1..=4 {
+			crate::AccountUsage::<Test>::insert(who, (0, 1));
+		}
+
+		// The cursor and two usages fit: the pass resumes in the next block.
+		let limit = db.reads_writes(3, 3);
+		assert_eq!(Feeless::on_idle(13, limit), limit);
+		assert_eq!(crate::AccountUsage::<Test>::iter().count(), 2);
+		assert_eq!(Feeless::on_idle(14, Weight::MAX), db.reads_writes(3, 3));
+		assert_eq!(crate::AccountUsage::<Test>::iter().count(), 0);
+
+		// Nothing is left to prune until the next period.
+		crate::AccountUsage::<Test>::insert(1, (10, 1));
+		assert_eq!(Feeless::on_idle(15, Weight::MAX), db.reads(1));
+
+		// Which only prunes the usages of periods that are over.
+		System::set_block_number(20);
+		crate::AccountUsage::<Test>::insert(2, (20, 1));
+		Feeless::on_idle(20, Weight::MAX);
+		assert_eq!(crate::AccountUsage::<Test>::iter_keys().collect::<Vec<_>>(), vec![2]);
+	});
+}
+
+#[test]
+fn feeless_transactions_are_weighted() {
+	use crate::WeightInfo;
+	use frame_support::dispatch::DispatchClass;
+
+	build_and_execute(|| {
+		let block_weight = || *System::block_weight().get(DispatchClass::Normal);
+		assert_ok!(Feeless::whitelist_call(
+			RuntimeOrigin::root(),
+			Feeless::call_index(&remark()),
+			Condition::Once
+		));
+
+		// Counting a feeless transaction against the rate limits is added to the block.
+		assert_eq!(apply(1, &remark()), Ok(true));
+		assert_eq!(block_weight(), <() as WeightInfo>::use_exemption());
+
+		// Transactions paying their fees are weighted by the wrapped extension.
+		assert_eq!(apply(1, &remark()), Ok(false));
+		assert_eq!(block_weight(), <() as WeightInfo>::use_exemption());
+	});
+}
+
+#[test]
+fn encodes_as_the_wrapped_extension() {
+	use codec::Encode;
+	use scale_info::TypeInfo;
+
+	let extension = SkipFeeless::<Test, _>::from(ChargeFee);
+	assert_eq!(extension.encode(), ChargeFee.encode());
+	assert_eq!(SkipFeeless::<Test, ChargeFee>::IDENTIFIER, ChargeFee::IDENTIFIER);
+	assert_eq!(SkipFeeless::<Test, ChargeFee>::type_info(), ChargeFee::type_info());
+}
@@ -0,0 +1,115 @@
+//! Weights for pallet_feeless
+//!
+//! Estimated from the storage accesses of each call, to be replaced with the output of
+//! `scripts/benchmark-weights.sh feeless`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_feeless.
+pub trait WeightInfo {
+	fn whitelist_account() -> Weight;
+	fn remove_account() -> Weight;
+	fn whitelist_call() -> Weight;
+	fn remove_call() -> Weight;
+	fn use_exemption() -> Weight;
+}
+
+/// Weights for pallet_feeless using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Feeless Accounts (r:0 w:1)
+	/// Proof: Feeless Accounts (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	fn whitelist_account() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Accounts (r:1 w:1)
+	/// Proof: Feeless Accounts (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	fn remove_account() -> Weight {
+		Weight::from_parts(13_000_000, 3513)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Calls (r:0 w:1)
+	/// Proof: Feeless Calls (max_values: None, max_size: Some(11), added: 2486, mode: MaxEncodedLen)
+	fn whitelist_call() -> Weight {
+		Weight::from_parts(9_000_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Calls (r:1 w:1)
+	/// Proof: Feeless Calls (max_values: None, max_size: Some(11), added: 2486, mode: MaxEncodedLen)
+	fn remove_call() -> Weight {
+		Weight::from_parts(12_000_000, 3476)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Accounts (r:1 w:0)
+	/// Proof: Feeless Accounts (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	/// Storage: Feeless Calls (r:1 w:0)
+	/// Proof: Feeless Calls (max_values: None, max_size: Some(11), added: 2486, mode: MaxEncodedLen)
+	/// Storage: System Account (r:1 w:0)
+	/// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// Storage: Feeless Used (r:1 w:1)
+	/// Proof: Feeless Used (max_values: None, max_size: Some(58), added: 2533, mode: MaxEncodedLen)
+	/// Storage: Feeless AccountUsage (r:1 w:1)
+	/// Proof: Feeless AccountUsage (max_values: None, max_size: Some(56), added: 2531, mode: MaxEncodedLen)
+	/// Storage: Feeless PeriodUsage (r:1 w:1)
+	/// Proof: Feeless PeriodUsage (max_values: Some(1), max_size: Some(8), added: 503, mode: MaxEncodedLen)
+	fn use_exemption() -> Weight {
+		Weight::from_parts(22_000_000, 16169)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Feeless Accounts (r:0 w:1)
+	/// Proof: Feeless Accounts (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	fn whitelist_account() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Accounts (r:1 w:1)
+	/// Proof: Feeless Accounts (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	fn remove_account() -> Weight {
+		Weight::from_parts(13_000_000, 3513)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Calls (r:0 w:1)
+	/// Proof: Feeless Calls (max_values: None, max_size: Some(11), added: 2486, mode: MaxEncodedLen)
+	fn whitelist_call() -> Weight {
+		Weight::from_parts(9_000_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Calls (r:1 w:1)
+	/// Proof: Feeless Calls (max_values: None, max_size: Some(11), added: 2486, mode: MaxEncodedLen)
+	fn remove_call() -> Weight {
+		Weight::from_parts(12_000_000, 3476)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Feeless Accounts (r:1 w:0)
+	/// Proof: Feeless Accounts (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	/// Storage: Feeless Calls (r:1 w:0)
+	/// Proof: Feeless Calls (max_values: None, max_size: Some(11), added: 2486, mode: MaxEncodedLen)
+	/// Storage: System Account (r:1 w:0)
+	/// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// Storage: Feeless Used (r:1 w:1)
+	/// Proof: Feeless Used (max_values: None, max_size: Some(58), added: 2533, mode: MaxEncodedLen)
+	/// Storage: Feeless AccountUsage (r:1 w:1)
+	/// Proof: Feeless AccountUsage (max_values: None, max_size: Some(56), added: 2531, mode: MaxEncodedLen)
+	/// Storage: Feeless PeriodUsage (r:1 w:1)
+	/// Proof: Feeless PeriodUsage (max_values: Some(1), max_size: Some(8), added: 503, mode: MaxEncodedLen)
+	fn use_exemption() -> Weight {
+		Weight::from_parts(22_000_000, 16169)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+}
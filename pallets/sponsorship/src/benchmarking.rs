@@ -0,0 +1,77 @@
+//! Benchmarking setup for pallet-sponsorship
+#![cfg(feature = "runtime-benchmarks")]
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Sponsorship;
+use frame_benchmarking::v2::*;
+use frame_support::traits::{Currency, ReservableCurrency};
+use frame_system::RawOrigin;
+use sp_runtime::traits::{Bounded, Saturating, Zero};
+
+/// A sponsor able to reserve the deposit of a budget.
+fn funded_sponsor<T: Config>() -> T::AccountId {
+	let sponsor: T::AccountId = whitelisted_caller();
+	let balance = T::Currency::minimum_balance().saturating_add(T::BudgetDeposit::get());
+	T::Currency::make_free_balance_be(&sponsor, balance);
+	sponsor
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn sponsor() {
+		let caller = funded_sponsor::<T>();
+		let user: T::AccountId = account("user", 0, 0);
+		let budget = BalanceOf::<T>::max_value();
+		#[extrinsic_call]
+		sponsor(RawOrigin::Signed(caller.clone()), user.clone(), budget);
+
+		assert_eq!(Sponsorship::<T>::budget(&caller, &user), Some(budget));
+		assert_eq!(T::Currency::reserved_balance(&caller), T::BudgetDeposit::get());
+	}
+
+	#[benchmark]
+	fn revoke() -> Result<(), BenchmarkError> {
+		let caller = funded_sponsor::<T>();
+		let user: T::AccountId = account("user", 0, 0);
+		Sponsorship::<T>::sponsor(
+			RawOrigin::Signed(caller.clone()).into(),
+			user.clone(),
+			BalanceOf::<T>::max_value(),
+		)?;
+		#[extrinsic_call]
+		revoke(RawOrigin::Signed(caller.clone()), user.clone());
+
+		assert_eq!(Sponsorship::<T>::budget(&caller, &user), None);
+		assert!(T::Currency::reserved_balance(&caller).is_zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn spend() -> Result<(), BenchmarkError> {
+		let sponsor = funded_sponsor::<T>();
+		let user: T::AccountId = account("user", 0, 0);
+		Sponsorship::<T>::sponsor(
+			RawOrigin::Signed(sponsor.clone()).into(),
+			user.clone(),
+			BalanceOf::<T>::max_value(),
+		)?;
+		let fee: BalanceOf<T> = 1_000u32.into();
+
+		#[block]
+		{
+			Sponsorship::<T>::spend(sponsor.clone(), user.clone(), fee);
+		}
+
+		assert_eq!(
+			Sponsorship::<T>::budget(&sponsor, &user),
+			Some(BalanceOf::<T>::max_value().saturating_sub(fee))
+		);
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(Sponsorship, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,244 @@
+//! A signed extension charging the fee of a transaction to the sponsor it names.
+//!
+//! [`ChargeSponsored`] holds either the sponsor the signer chose or the fee extension of the
+//! runtime it wraps, e.g. `ChargeAssetTxPayment`, so that a sponsored transaction signs no tip nor
+//! asset that would go unused. A sponsored transaction is valid when its sponsor authorized the
+//! signer with a budget covering the fee, and the sponsor can pay it. The fee is then withdrawn
+//! from the sponsor, refunded and deducted from the budget as `ChargeTransactionPayment` would for
+//! the signer, in the native balance, and reported with its `TransactionFeePaid` event. Deducting
+//! the fee from the budget is added to the weight of the block.
+
+use crate::{BalanceOf, Config, Pallet, WeightInfo};
+use codec::{Decode, Encode};
+use frame_support::dispatch::{DispatchInfo, PostDispatchInfo};
+use pallet_transaction_payment::{ChargeTransactionPayment, OnChargeTransaction};
+use scale_info::{StaticTypeInfo, TypeInfo};
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SignedExtension, SignedExtensionMetadata,
+		Zero,
+	},
+	transaction_validity::{
+		InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+	DispatchResult, FixedPointOperand,
+};
+use sp_std::vec::Vec;
+
+/// The custom [`InvalidTransaction`] code of a transaction naming a sponsor that did not
+/// authorize the signer.
+pub const NOT_SPONSORED: u8 = 2;
+/// The custom [`InvalidTransaction`] code of a transaction whose fee exceeds what is left of the
+/// budget of the signer.
+pub const BUDGET_EXCEEDED: u8 = 3;
+
+type OnChargeTransactionOf<T> = <T as pallet_transaction_payment::Config>::OnChargeTransaction;
+/// The balance fees are charged in, converted into [`BalanceOf`] to be deducted from budgets.
+type FeeOf<T> = pallet_transaction_payment::BalanceOf<T>;
+type LiquidityInfoOf<T> = <OnChargeTransactionOf<T> as OnChargeTransaction<T>>::LiquidityInfo;
+
+/// Charges the fee to the sponsor the signer names, or with the fee extension `S`.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub enum ChargeSponsored<T: Config, S> {
+	/// The sponsor pays the fee, without tip.
+	Sponsored(T::AccountId),
+	/// The signer pays the fee, and the tip, through `S`.
+	Unsponsored(S),
+}
+
+impl<T: Config, S> ChargeSponsored<T, S> {
+	/// The sponsor paying the fee.
+	pub fn sponsor(&self) -> Option<&T::AccountId> {
+		match self {
+			Self::Sponsored(sponsor) => Some(sponsor),
+			Self::Unsponsored(_) => None,
+		}
+	}
+
+	/// The fee extension charging the signer when no sponsor is named.
+	pub fn charge(&self) -> Option<&S> {
+		match self {
+			Self::Sponsored(_) => None,
+			Self::Unsponsored(charge) => Some(charge),
+		}
+	}
+}
+
+impl<T: Config, S> From<S> for ChargeSponsored<T, S> {
+	fn from(charge: S) -> Self {
+		Self::Unsponsored(charge)
+	}
+}
+
+impl<T: Config, S: core::fmt::Debug> core::fmt::Debug for ChargeSponsored<T, S> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match self {
+			Self::Sponsored(sponsor) => write!(f, "ChargeSponsored::Sponsored({:?})", sponsor),
+			Self::Unsponsored(charge) => write!(f, "ChargeSponsored::Unsponsored({:?})", charge),
+		}
+	}
+}
+
+/// The pre-dispatch data of [`ChargeSponsored`].
+pub enum Pre<T: Config, S: SignedExtension> {
+	/// The fee was withdrawn from `sponsor` for `user`.
+	Sponsored { sponsor: T::AccountId, user: T::AccountId, withdrawn: LiquidityInfoOf<T> },
+	/// The fee was charged by the wrapped extension.
+	Unsponsored(S::Pre),
+}
+
+impl<T, S> ChargeSponsored<T, S>
+where
+	T: Config + Send + Sync,
+	T::RuntimeCall: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	FeeOf<T>: Send + Sync + FixedPointOperand + Into<BalanceOf<T>>,
+{
+	/// Withdraws the fee of a transaction of `user` from `sponsor`, checking the budget of `user`.
+	fn withdraw_fee(
+		sponsor: &T::AccountId,
+		user: &T::AccountId,
+		call: &T::RuntimeCall,
+		info: &DispatchInfoOf<T::RuntimeCall>,
+		len: usize,
+	) -> Result<(FeeOf<T>, LiquidityInfoOf<T>), TransactionValidityError> {
+		let budget =
+			Pallet::<T>::budget(sponsor, user).ok_or(InvalidTransaction::Custom(NOT_SPONSORED))?;
+		let fee =
+			pallet_transaction_payment::Pallet::<T>::compute_fee(len as u32, info, Zero::zero());
+		if Into::<BalanceOf<T>>::into(fee) > budget {
+			return Err(InvalidTransaction::Custom(BUDGET_EXCEEDED).into())
+		}
+		let withdrawn =
+			OnChargeTransactionOf::<T>::withdraw_fee(sponsor, call, info, fee, Zero::zero())?;
+		Ok((fee, withdrawn))
+	}
+}
+
+impl<T, S> SignedExtension for ChargeSponsored<T, S>
+where
+	T: Config + Send + Sync,
+	T::RuntimeCall: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	FeeOf<T>: Send + Sync + FixedPointOperand + Into<BalanceOf<T>>,
+	S: SignedExtension<AccountId = T::AccountId, Call = T::RuntimeCall, AdditionalSigned = ()>
+		+ StaticTypeInfo,
+{
+	const IDENTIFIER: &'static str = "ChargeSponsored";
+	type AccountId = T::AccountId;
+	type Call = T::RuntimeCall;
+	// Neither the sponsor nor, as fee extensions go, `S` add anything to the signed payload.
+	type AdditionalSigned = ();
+	type Pre = Pre<T, S>;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		match self {
+			Self::Sponsored(_) => Ok(()),
+			Self::Unsponsored(charge) => charge.additional_signed(),
+		}
+	}
+
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: scale_info::meta_type::<Self>(),
+			additional_signed: scale_info::meta_type::<()>(),
+		}]
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let sponsor = match self {
+			Self::Sponsored(sponsor) => sponsor,
+			Self::Unsponsored(charge) => return charge.validate(who, call, info, len),
+		};
+		let (fee, _) = Self::withdraw_fee(sponsor, who, call, info, len)?;
+		Ok(ValidTransaction {
+			priority: ChargeTransactionPayment::<T>::get_priority(info, len, Zero::zero(), fee),
+			..Default::default()
+		})
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		match self {
+			Self::Sponsored(sponsor) => {
+				let (_, withdrawn) = Self::withdraw_fee(&sponsor, who, call, info, len)?;
+				// The weight of the transaction does not include deducting its fee from the
+				// budget after dispatch.
+				frame_system::Pallet::<T>::register_extra_weight_unchecked(
+					<T as Config>::WeightInfo::spend(),
+					info.class,
+				);
+				Ok(Pre::Sponsored { sponsor, user: who.clone(), withdrawn })
+			},
+			Self::Unsponsored(charge) =>
+				Ok(Pre::Unsponsored(charge.pre_dispatch(who, call, info, len)?)),
+		}
+	}
+
+	fn validate_unsigned(
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		S::validate_unsigned(call, info, len)
+	}
+
+	fn pre_dispatch_unsigned(
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		S::pre_dispatch_unsigned(call, info, len)
+	}
+
+	fn post_dispatch(
+		pre: Option<Self::Pre>,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some(Pre::Sponsored { sponsor, user, withdrawn }) => {
+				let fee = pallet_transaction_payment::Pallet::<T>::compute_actual_fee(
+					len as u32,
+					info,
+					post_info,
+					Zero::zero(),
+				);
+				OnChargeTransactionOf::<T>::correct_and_deposit_fee(
+					&sponsor,
+					info,
+					post_info,
+					fee,
+					Zero::zero(),
+					withdrawn,
+				)?;
+				let paid: <T as pallet_transaction_payment::Config>::RuntimeEvent =
+					pallet_transaction_payment::Event::<T>::TransactionFeePaid {
+						who: sponsor.clone(),
+						actual_fee: fee,
+						tip: Zero::zero(),
+					}
+					.into();
+				frame_system::Pallet::<T>::deposit_event(paid);
+				Pallet::<T>::spend(sponsor, user, fee.into());
+				Ok(())
+			},
+			Some(Pre::Unsponsored(pre)) =>
+				S::post_dispatch(Some(pre), info, post_info, len, result),
+			None => S::post_dispatch(None, info, post_info, len, result),
+		}
+	}
+}
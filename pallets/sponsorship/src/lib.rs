@@ -0,0 +1,143 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Lets sponsors pay the transaction fees of the users they pre-authorize.
+//!
+//! A sponsor authorizes a user with [`Pallet::sponsor`], giving them a budget: the total fees the
+//! sponsor pays for them. The sponsor reserves [`BudgetDeposit`](Config::BudgetDeposit) for each
+//! user it authorizes, until it revokes them. A user names the sponsor paying for a transaction in
+//! the [`ChargeSponsored`] signed extension, which then withdraws the fee from the sponsor through
+//! the `OnChargeTransaction` of `pallet_transaction_payment` and deducts it from the budget, so
+//! that users with no balance can transact. Transactions naming no sponsor pay their fees through
+//! the fee extension `ChargeSponsored` wraps.
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod extension;
+pub mod weights;
+pub use extension::ChargeSponsored;
+pub use weights::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{Currency, ReservableCurrency},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::Saturating;
+
+	/// The balance of [`Currency`](Config::Currency), which budgets and deposits are kept in.
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		/// Type representing the weight of this pallet
+		type WeightInfo: WeightInfo;
+		/// The currency deposits are reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+		/// The deposit a sponsor reserves for each user it authorizes.
+		#[pallet::constant]
+		type BudgetDeposit: Get<BalanceOf<Self>>;
+	}
+
+	/// The fees each sponsor still pays for each user it authorized, with the deposit it reserved
+	/// for the user.
+	#[pallet::storage]
+	pub type Budgets<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		(BalanceOf<T>, BalanceOf<T>),
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A sponsor authorized a user, paying fees for them up to a budget.
+		Sponsored { sponsor: T::AccountId, user: T::AccountId, budget: BalanceOf<T> },
+		/// A sponsor stopped paying fees for a user.
+		Revoked { sponsor: T::AccountId, user: T::AccountId },
+		/// A sponsor paid the fee of a transaction of a user.
+		FeePaid { sponsor: T::AccountId, user: T::AccountId, fee: BalanceOf<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The user is not sponsored by the caller.
+		NotSponsored,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Authorizes `user` to name the caller as the sponsor of their transactions, up to
+		/// `budget` in fees, replacing any previous budget. Reserves `BudgetDeposit` from the
+		/// caller unless it already sponsors `user`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(<T as Config>::WeightInfo::sponsor())]
+		pub fn sponsor(
+			origin: OriginFor<T>,
+			user: T::AccountId,
+			budget: BalanceOf<T>,
+		) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let deposit = match Budgets::<T>::get(&sponsor, &user) {
+				Some((_, deposit)) => deposit,
+				None => {
+					let deposit = T::BudgetDeposit::get();
+					T::Currency::reserve(&sponsor, deposit)?;
+					deposit
+				},
+			};
+			Budgets::<T>::insert(&sponsor, &user, (budget, deposit));
+			Self::deposit_event(Event::Sponsored { sponsor, user, budget });
+			Ok(())
+		}
+
+		/// Stops paying the fees of `user`, unreserving the deposit of the caller for them.
+		#[pallet::call_index(1)]
+		#[pallet::weight(<T as Config>::WeightInfo::revoke())]
+		pub fn revoke(origin: OriginFor<T>, user: T::AccountId) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let (_, deposit) =
+				Budgets::<T>::take(&sponsor, &user).ok_or(Error::<T>::NotSponsored)?;
+			T::Currency::unreserve(&sponsor, deposit);
+			Self::deposit_event(Event::Revoked { sponsor, user });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The fees `sponsor` still pays for `user`, `None` if it does not sponsor them.
+		pub fn budget(sponsor: &T::AccountId, user: &T::AccountId) -> Option<BalanceOf<T>> {
+			Budgets::<T>::get(sponsor, user).map(|(budget, _)| budget)
+		}
+
+		/// Deducts `fee`, paid by `sponsor` for `user`, from the budget of `user`.
+		pub(crate) fn spend(sponsor: T::AccountId, user: T::AccountId, fee: BalanceOf<T>) {
+			Budgets::<T>::mutate(&sponsor, &user, |budget| {
+				if let Some((budget, _)) = budget {
+					*budget = budget.saturating_sub(fee);
+				}
+			});
+			Self::deposit_event(Event::FeePaid { sponsor, user, fee });
+		}
+	}
+}
@@ -0,0 +1,202 @@
+use crate::{
+	extension::{BUDGET_EXCEEDED, NOT_SPONSORED},
+	mock::*,
+	ChargeSponsored, Error, Event,
+};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchInfo, PostDispatchInfo},
+	weights::Weight,
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+type Extension = ChargeSponsored<Test, ChargeTransactionPayment<Test>>;
+
+const LEN: usize = 10;
+
+fn remark() -> RuntimeCall {
+	frame_system::Call::remark { remark: vec![1] }.into()
+}
+
+fn info() -> DispatchInfo {
+	DispatchInfo { weight: Weight::from_parts(1_000, 0), ..Default::default() }
+}
+
+/// The fee of a transaction of [`info`] that used `weight`.
+fn fee(weight: u64) -> u64 {
+	let post_info = PostDispatchInfo {
+		actual_weight: Some(Weight::from_parts(weight, 0)),
+		..Default::default()
+	};
+	TransactionPayment::compute_actual_fee(LEN as u32, &info(), &post_info, 0)
+}
+
+/// Runs the extension for a remark of `who` naming `sponsor`, which used `weight`.
+fn apply(sponsor: Option<u64>, who: u64, weight: u64) -> Result<(), TransactionValidityError> {
+	let extension = match sponsor {
+		Some(sponsor) => Extension::Sponsored(sponsor),
+		None => Extension::Unsponsored(ChargeTransactionPayment::from(0)),
+	};
+	let (call, info) = (remark(), info());
+	// Validating withdraws the fee too, on state the transaction pool throws away.
+	sp_io::storage::start_transaction();
+	let valid = extension.validate(&who, &call, &info, LEN);
+	sp_io::storage::rollback_transaction();
+	valid?;
+	let pre = extension.pre_dispatch(&who, &call, &info, LEN)?;
+	let post_info = PostDispatchInfo {
+		actual_weight: Some(Weight::from_parts(weight, 0)),
+		..Default::default()
+	};
+	Extension::post_dispatch(Some(pre), &info, &post_info, LEN, &Ok(()))
+}
+
+#[test]
+fn sponsors_manage_their_own_users() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, 100));
+		System::assert_last_event(
+			Event::Sponsored { sponsor: SPONSOR, user: USER, budget: 100 }.into(),
+		);
+		assert_eq!(Sponsorship::budget(&SPONSOR, &USER), Some(100));
+
+		assert_noop!(
+			Sponsorship::revoke(RuntimeOrigin::signed(PAYER), USER),
+			Error::<Test>::NotSponsored
+		);
+		assert_ok!(Sponsorship::revoke(RuntimeOrigin::signed(SPONSOR), USER));
+		System::assert_last_event(Event::Revoked { sponsor: SPONSOR, user: USER }.into());
+		assert_eq!(Sponsorship::budget(&SPONSOR, &USER), None);
+	});
+}
+
+#[test]
+fn sponsors_reserve_a_deposit_per_user() {
+	new_test_ext().execute_with(|| {
+		// Once per user, however often their budget is replaced.
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, 100));
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, 200));
+		assert_eq!(Balances::reserved_balance(SPONSOR), BUDGET_DEPOSIT);
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), PAYER, 100));
+		assert_eq!(Balances::reserved_balance(SPONSOR), 2 * BUDGET_DEPOSIT);
+
+		// Until they are revoked.
+		assert_ok!(Sponsorship::revoke(RuntimeOrigin::signed(SPONSOR), USER));
+		assert_eq!(Balances::reserved_balance(SPONSOR), BUDGET_DEPOSIT);
+		assert_ok!(Sponsorship::revoke(RuntimeOrigin::signed(SPONSOR), PAYER));
+		assert_eq!(Balances::free_balance(SPONSOR), INITIAL_BALANCE);
+
+		// Sponsors must be able to reserve it.
+		assert_noop!(
+			Sponsorship::sponsor(RuntimeOrigin::signed(USER), PAYER, 100),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn sponsors_pay_the_fees_of_their_users() {
+	new_test_ext().execute_with(|| {
+		let budget = 3 * fee(1_000);
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, budget));
+
+		// The sponsor pays the fee of the weight actually used, which comes off the budget.
+		assert_ok!(apply(Some(SPONSOR), USER, 400));
+		System::assert_has_event(
+			pallet_transaction_payment::Event::TransactionFeePaid {
+				who: SPONSOR,
+				actual_fee: fee(400),
+				tip: 0,
+			}
+			.into(),
+		);
+		System::assert_last_event(
+			Event::FeePaid { sponsor: SPONSOR, user: USER, fee: fee(400) }.into(),
+		);
+		assert_eq!(Balances::free_balance(SPONSOR), INITIAL_BALANCE - BUDGET_DEPOSIT - fee(400));
+		assert_eq!(Balances::free_balance(USER), 0);
+		assert_eq!(Sponsorship::budget(&SPONSOR, &USER), Some(budget - fee(400)));
+	});
+}
+
+#[test]
+fn sponsored_transactions_are_limited_to_the_budget() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			apply(Some(SPONSOR), USER, 1_000),
+			Err(InvalidTransaction::Custom(NOT_SPONSORED).into())
+		);
+
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, fee(1_000) - 1));
+		assert_eq!(
+			apply(Some(SPONSOR), USER, 1_000),
+			Err(InvalidTransaction::Custom(BUDGET_EXCEEDED).into())
+		);
+
+		// The budget covers a transaction using the whole weight, what is left of it does not.
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, fee(1_000)));
+		assert_ok!(apply(Some(SPONSOR), USER, 1_000));
+		assert_eq!(Sponsorship::budget(&SPONSOR, &USER), Some(0));
+		assert_eq!(
+			apply(Some(SPONSOR), USER, 1_000),
+			Err(InvalidTransaction::Custom(BUDGET_EXCEEDED).into())
+		);
+
+		// Sponsors must be able to pay.
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(BROKE_SPONSOR), USER, u64::MAX));
+		assert_eq!(
+			apply(Some(BROKE_SPONSOR), USER, 1_000),
+			Err(InvalidTransaction::Payment.into())
+		);
+	});
+}
+
+#[test]
+fn sponsored_transactions_are_weighted() {
+	use crate::WeightInfo;
+	use frame_support::dispatch::DispatchClass;
+
+	new_test_ext().execute_with(|| {
+		let block_weight = || *System::block_weight().get(DispatchClass::Normal);
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, u64::MAX));
+
+		// Deducting the fee from the budget is added to the block.
+		assert_ok!(apply(Some(SPONSOR), USER, 1_000));
+		assert_eq!(block_weight(), <() as WeightInfo>::spend());
+
+		// Transactions paying their own fees are weighted by the wrapped extension.
+		assert_ok!(apply(None, PAYER, 1_000));
+		assert_eq!(block_weight(), <() as WeightInfo>::spend());
+	});
+}
+
+#[test]
+fn unsponsored_transactions_pay_their_own_fees() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(apply(None, PAYER, 1_000));
+		assert_eq!(Balances::free_balance(PAYER), INITIAL_BALANCE - fee(1_000));
+		assert_eq!(Balances::free_balance(SPONSOR), INITIAL_BALANCE);
+
+		// Even when sponsored.
+		assert_ok!(Sponsorship::sponsor(RuntimeOrigin::signed(SPONSOR), USER, u64::MAX));
+		assert_eq!(apply(None, USER, 1_000), Err(InvalidTransaction::Payment.into()));
+	});
+}
+
+#[test]
+fn sponsored_transactions_encode_no_tip() {
+	use codec::Encode;
+
+	// Either the sponsor or the wrapped extension is signed, never both.
+	assert_eq!(Extension::Sponsored(SPONSOR).encode(), (0u8, SPONSOR).encode());
+	let charge = ChargeTransactionPayment::<Test>::from(5);
+	assert_eq!(Extension::from(charge.clone()).encode(), (1u8, charge).encode());
+
+	let identifiers: Vec<_> =
+		Extension::metadata().into_iter().map(|metadata| metadata.identifier).collect();
+	assert_eq!(identifiers, vec!["ChargeSponsored"]);
+}
@@ -0,0 +1,77 @@
+//! Weights for pallet_sponsorship
+//!
+//! Estimated from the storage accesses of each call, to be replaced with the output of
+//! `scripts/benchmark-weights.sh sponsorship`.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_sponsorship.
+pub trait WeightInfo {
+	fn sponsor() -> Weight;
+	fn revoke() -> Weight;
+	fn spend() -> Weight;
+}
+
+/// Weights for pallet_sponsorship using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Sponsorship Budgets (r:1 w:1)
+	/// Proof: Sponsorship Budgets (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// Storage: System Account (r:1 w:1)
+	/// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	fn sponsor() -> Weight {
+		Weight::from_parts(30_000_000, 6196)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Sponsorship Budgets (r:1 w:1)
+	/// Proof: Sponsorship Budgets (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// Storage: System Account (r:1 w:1)
+	/// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	fn revoke() -> Weight {
+		Weight::from_parts(30_000_000, 6196)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Sponsorship Budgets (r:1 w:1)
+	/// Proof: Sponsorship Budgets (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	fn spend() -> Weight {
+		Weight::from_parts(12_000_000, 3593)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Sponsorship Budgets (r:1 w:1)
+	/// Proof: Sponsorship Budgets (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// Storage: System Account (r:1 w:1)
+	/// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	fn sponsor() -> Weight {
+		Weight::from_parts(30_000_000, 6196)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Sponsorship Budgets (r:1 w:1)
+	/// Proof: Sponsorship Budgets (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// Storage: System Account (r:1 w:1)
+	/// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	fn revoke() -> Weight {
+		Weight::from_parts(30_000_000, 6196)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Sponsorship Budgets (r:1 w:1)
+	/// Proof: Sponsorship Budgets (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	fn spend() -> Weight {
+		Weight::from_parts(12_000_000, 3593)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+}
@@ -0,0 +1,9 @@
+//! Random sequences of `do_something` and `cause_error` calls from random origins, checked against
+//! the model of `pallet_template::fuzzing`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pallet_template::fuzzing::Scenario;
+
+fuzz_target!(|scenario: Scenario| scenario.run());
@@ -5,20 +5,24 @@ use super::*;
 #[allow(unused)]
 use crate::Pallet as Template;
 use frame_benchmarking::v2::*;
-use frame_system::RawOrigin;
+use frame_support::traits::{EnsureOrigin, Get};
+use frame_system::{pallet_prelude::BlockNumberFor, RawOrigin};
+use sp_runtime::traits::{One, Zero};
 
 #[benchmarks]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn do_something() {
+	fn do_something() -> Result<(), BenchmarkError> {
 		let value = 100u32.into();
-		let caller: T::AccountId = whitelisted_caller();
+		let origin = T::DoSomethingOrigin::try_successful_origin()
+			.map_err(|_| BenchmarkError::Weightless)?;
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		do_something(origin as T::RuntimeOrigin, value);
 
 		assert_eq!(Something::<T>::get(), Some(value));
+		Ok(())
 	}
 
 	#[benchmark]
@@ -31,5 +35,33 @@ mod benchmarks {
 		assert_eq!(Something::<T>::get(), Some(101u32));
 	}
 
+	#[benchmark]
+	fn store_random() {
+		let caller: T::AccountId = whitelisted_caller();
+		#[extrinsic_call]
+		store_random(RawOrigin::Signed(caller));
+
+		assert!(Something::<T>::get().is_some());
+	}
+
+	#[benchmark]
+	fn check_rate_limit() -> Result<(), BenchmarkError> {
+		// The caller is not exempt and called in a past period, so its count is read and
+		// overwritten.
+		let caller: T::AccountId = whitelisted_caller();
+		let now = T::RatePeriod::get().max(One::one());
+		frame_system::Pallet::<T>::set_block_number(now);
+		CallCounts::<T>::insert(&caller, (BlockNumberFor::<T>::zero(), 1));
+
+		#[block]
+		{
+			Template::<T>::count_call(&caller)
+				.map_err(|_| BenchmarkError::Stop("The caller is rate limited"))?;
+		}
+
+		assert_eq!(CallCounts::<T>::get(&caller), Some((now, 1)));
+		Ok(())
+	}
+
 	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
 }
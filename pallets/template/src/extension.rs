@@ -0,0 +1,113 @@
+//! A signed extension prioritizing the calls of this pallet and keeping those over the rate limit
+//! out of the transaction pool.
+//!
+//! Each account may call the pallet at most [`Config::MaxCallsPerPeriod`] times in every period
+//! of [`Config::RatePeriod`] blocks, periods starting at the multiples of `RatePeriod`. The calls
+//! count themselves when dispatched, so that the limit also holds for calls made by contracts or
+//! through the EVM, and fail with [`Error::RateLimited`](crate::Error::RateLimited) over it.
+//!
+//! [`CheckTemplateRateLimit`] raises the priority of transactions calling the pallet by
+//! [`Config::PriorityBoost`], and rejects a transaction over the limit until the next period
+//! starts rather than let it pay for a failing call. A transaction within the limit is only valid
+//! until the current period ends, so that the pool drops it rather than holding on to a call
+//! checked against a period that is over. The accounts of
+//! [`RateLimitExempt`](crate::RateLimitExempt) are not limited.
+//!
+//! The extension adds nothing to the encoded extrinsic nor to the signed payload.
+
+use crate::{Call, Config, Pallet};
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use frame_support::traits::{Get, IsSubType};
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, SignedExtension, UniqueSaturatedInto},
+	transaction_validity::{
+		InvalidTransaction, TransactionLongevity, TransactionValidity, TransactionValidityError,
+		ValidTransaction,
+	},
+};
+
+/// The custom [`InvalidTransaction`] code of a call over the rate limit.
+pub const RATE_LIMITED: u8 = 1;
+
+/// Prioritizes the calls of the pallet and rejects those of accounts over the rate limit.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckTemplateRateLimit<T: Config + Send + Sync>(PhantomData<T>);
+
+impl<T: Config + Send + Sync> CheckTemplateRateLimit<T> {
+	/// Creates a new instance of the extension.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+
+	/// Checks that `who` may call the pallet once more in the current period, returning the
+	/// number of blocks left in it, or `None` if `who` is exempt.
+	fn check(who: &T::AccountId) -> Result<Option<BlockNumberFor<T>>, TransactionValidityError> {
+		match Pallet::<T>::call_count(who) {
+			Some((_, _, calls)) if calls >= T::MaxCallsPerPeriod::get() =>
+				Err(InvalidTransaction::Custom(RATE_LIMITED).into()),
+			count => Ok(count.map(|(_, left, _)| left)),
+		}
+	}
+}
+
+impl<T: Config + Send + Sync> Default for CheckTemplateRateLimit<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Config + Send + Sync> core::fmt::Debug for CheckTemplateRateLimit<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "CheckTemplateRateLimit")
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckTemplateRateLimit<T>
+where
+	T::RuntimeCall: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckTemplateRateLimit";
+	type AccountId = T::AccountId;
+	type Call = T::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		if call.is_sub_type().is_none() {
+			return Ok(ValidTransaction::default())
+		}
+		let longevity = match Self::check(who)? {
+			Some(left) => left.unique_saturated_into(),
+			None => TransactionLongevity::MAX,
+		};
+		Ok(ValidTransaction { priority: T::PriorityBoost::get(), longevity, ..Default::default() })
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<(), TransactionValidityError> {
+		// The call counts itself once dispatched.
+		if call.is_sub_type().is_some() {
+			Self::check(who)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,192 @@
+//! Random sequences of calls against the mock runtime, checked against a model of the pallet.
+//!
+//! [`Scenario::run`] dispatches every call, one per block, and checks, after each one, that its
+//! result, the value of `Something` and the events deposited are the ones the model predicts, and
+//! that the `try_state` invariants of the pallet hold. It is driven by the property tests in
+//! `tests.rs` and by the fuzz target in `fuzz/`.
+
+use std::collections::BTreeMap;
+
+use crate::{mock::*, Config, Error, Event};
+use frame_support::traits::Get;
+use sp_runtime::{traits::Dispatchable, DispatchError, DispatchResult};
+
+/// The first block of the last rate limit period each account made a successful call in, with the
+/// number of its successful calls in that period.
+type Calls = BTreeMap<u64, (u64, u32)>;
+
+/// An origin a call is dispatched from.
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin {
+	/// A signed origin, from one of a few accounts so that holders are drawn often.
+	Signed(u8),
+	/// The root origin.
+	Root,
+	/// The unsigned origin.
+	None,
+}
+
+impl Origin {
+	fn account(self) -> Option<u64> {
+		match self {
+			Origin::Signed(who) => Some(who.into()),
+			Origin::Root | Origin::None => None,
+		}
+	}
+}
+
+impl From<Origin> for RuntimeOrigin {
+	fn from(origin: Origin) -> Self {
+		match origin {
+			Origin::Signed(who) => RuntimeOrigin::signed(who.into()),
+			Origin::Root => RuntimeOrigin::root(),
+			Origin::None => RuntimeOrigin::none(),
+		}
+	}
+}
+
+/// A call of the pallet.
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+	/// `do_something(something)` from `origin`.
+	DoSomething { origin: Origin, something: u32 },
+	/// `cause_error()` from `origin`.
+	CauseError { origin: Origin },
+}
+
+/// A sequence of calls, dispatched with `do_something` restricted to `holders` when given.
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scenario {
+	/// The accounts allowed to call `do_something`, any signed account when `None`.
+	pub holders: Option<Vec<u8>>,
+	/// The calls, in order.
+	pub actions: Vec<Action>,
+}
+
+impl Scenario {
+	/// Dispatches the calls on a fresh mock runtime, panicking at the first one breaking an
+	/// invariant.
+	pub fn run(&self) {
+		Holders::set(
+			self.holders.as_ref().map(|holders| holders.iter().map(|&h| h.into()).collect()),
+		);
+		new_test_ext().execute_with(|| {
+			let mut calls = Calls::new();
+			for (step, action) in self.actions.iter().enumerate() {
+				// Past the genesis block so events get deposited, and through a few rate limit
+				// periods.
+				let now = step as u64 + 1;
+				System::set_block_number(now);
+				let before = TemplateModule::something();
+				let (expected_result, expected_value, expected_events) =
+					self.expect(action, before, limited(&calls, action, now));
+				if expected_result.is_ok() {
+					count(&mut calls, action, now);
+				}
+
+				System::reset_events();
+				let result = dispatch(action);
+				let events: Vec<_> = System::events()
+					.into_iter()
+					.filter_map(|record| match record.event {
+						RuntimeEvent::TemplateModule(event) => Some(event),
+						_ => None,
+					})
+					.collect();
+
+				assert_eq!(result, expected_result, "result of step {step}: {action:?}");
+				assert_eq!(
+					TemplateModule::something(),
+					expected_value,
+					"value after step {step}: {action:?}"
+				);
+				assert_eq!(events, expected_events, "events of step {step}: {action:?}");
+				if result == Err(Error::<Test>::StorageOverflow.into()) {
+					assert_eq!(before, Some(u32::MAX), "overflow at step {step}: {action:?}");
+				}
+				TemplateModule::do_try_state()
+					.unwrap_or_else(|e| panic!("{e} after step {step}: {action:?}"));
+			}
+		});
+		Holders::set(None);
+	}
+
+	/// The result of `action` on a pallet storing `value`, with the account of its origin over the
+	/// rate limit if `limited`, the value it leaves behind and the events it deposits.
+	fn expect(
+		&self,
+		action: &Action,
+		value: Option<u32>,
+		limited: bool,
+	) -> (DispatchResult, Option<u32>, Vec<Event<Test>>) {
+		let bad_origin = (Err(DispatchError::BadOrigin), value, vec![]);
+		let rate_limited = (Err(Error::<Test>::RateLimited.into()), value, vec![]);
+		match *action {
+			Action::DoSomething { origin, something } => match origin.account() {
+				Some(who) if self.is_holder(who) && limited => rate_limited,
+				Some(who) if self.is_holder(who) =>
+					(Ok(()), Some(something), vec![Event::SomethingStored { something, who }]),
+				_ => bad_origin,
+			},
+			Action::CauseError { origin } => match (origin.account(), value) {
+				(None, _) => bad_origin,
+				(Some(_), _) if limited => rate_limited,
+				(Some(_), None) => (Err(Error::<Test>::NoneValue.into()), value, vec![]),
+				(Some(_), Some(u32::MAX)) =>
+					(Err(Error::<Test>::StorageOverflow.into()), value, vec![]),
+				(Some(_), Some(value)) => (Ok(()), Some(value + 1), vec![]),
+			},
+		}
+	}
+
+	fn is_holder(&self, who: u64) -> bool {
+		match &self.holders {
+			Some(holders) => holders.iter().any(|&holder| u64::from(holder) == who),
+			None => true,
+		}
+	}
+}
+
+impl Action {
+	fn origin(&self) -> Origin {
+		match *self {
+			Action::DoSomething { origin, .. } | Action::CauseError { origin } => origin,
+		}
+	}
+}
+
+/// Whether the account of the origin of `action` made the most calls allowed in the period of
+/// the block `now`.
+fn limited(calls: &Calls, action: &Action, now: u64) -> bool {
+	let Some(who) = action.origin().account() else { return false };
+	let period = <Test as Config>::RatePeriod::get();
+	matches!(
+		calls.get(&who),
+		Some(&(start, made)) if start == now - now % period &&
+			made >= <Test as Config>::MaxCallsPerPeriod::get()
+	)
+}
+
+/// Counts the successful `action` in `calls`, in the rate limit period of the block `now`.
+fn count(calls: &mut Calls, action: &Action, now: u64) {
+	let Some(who) = action.origin().account() else { return };
+	let start = now - now % <Test as Config>::RatePeriod::get();
+	let made = match calls.get(&who) {
+		Some(&(period, made)) if period == start => made,
+		_ => 0,
+	};
+	calls.insert(who, (start, made + 1));
+}
+
+/// Dispatches `action` as a call of the runtime.
+fn dispatch(action: &Action) -> DispatchResult {
+	let (origin, call) = match *action {
+		Action::DoSomething { origin, something } =>
+			(origin, crate::Call::<Test>::do_something { something }),
+		Action::CauseError { origin } => (origin, crate::Call::<Test>::cause_error {}),
+	};
+	RuntimeCall::from(call).dispatch(origin.into()).map(|_| ()).map_err(|e| e.error)
+}
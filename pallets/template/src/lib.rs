@@ -5,22 +5,35 @@
 /// <https://docs.substrate.io/reference/frame-pallets/>
 pub use pallet::*;
 
-#[cfg(test)]
-mod mock;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod mock;
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzzing;
 
 #[cfg(test)]
 mod tests;
 
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+pub mod extension;
 pub mod weights;
+pub use extension::CheckTemplateRateLimit;
 pub use weights::*;
 
+/// The subject passed to the randomness source so that values drawn by this pallet are
+/// independent of those drawn by other pallets.
+const RANDOMNESS_SUBJECT: &[u8] = b"pallet-template/something";
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
-	use frame_support::pallet_prelude::*;
+	use frame_support::{pallet_prelude::*, traits::Randomness};
 	use frame_system::pallet_prelude::*;
+	use sp_runtime::{
+		traits::{One, TrailingZeroInput, Zero},
+		transaction_validity::TransactionPriority,
+	};
 
 	#[pallet::pallet]
 	pub struct Pallet<T>(_);
@@ -32,6 +45,20 @@ pub mod pallet {
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 		/// Type representing the weight of this pallet
 		type WeightInfo: WeightInfo;
+		/// Source of randomness used by [`Pallet::store_random`].
+		type Randomness: Randomness<Self::Hash, BlockNumberFor<Self>>;
+		/// The origin allowed to call [`Pallet::do_something`], resolving to the account it acts
+		/// for. Runtimes can use it to restrict the call, for example to holders of an NFT.
+		type DoSomethingOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Self::AccountId>;
+		/// The priority [`CheckTemplateRateLimit`] adds to transactions calling this pallet.
+		#[pallet::constant]
+		type PriorityBoost: Get<TransactionPriority>;
+		/// The length in blocks of the periods calls to this pallet are limited over.
+		#[pallet::constant]
+		type RatePeriod: Get<BlockNumberFor<Self>>;
+		/// The most calls to this pallet an account may make in a period.
+		#[pallet::constant]
+		type MaxCallsPerPeriod: Get<u32>;
 	}
 
 	// The pallet's runtime storage items.
@@ -42,6 +69,26 @@ pub mod pallet {
 	// https://docs.substrate.io/main-docs/build/runtime-storage/#declaring-storage-items
 	pub type Something<T> = StorageValue<_, u32>;
 
+	/// The first block of the last period each account called this pallet in, with the number of
+	/// calls it made in that period. Counted by the calls themselves, whichever way they are
+	/// dispatched, and removed by [`Hooks::on_idle`] once the period is over.
+	#[pallet::storage]
+	pub type CallCounts<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (BlockNumberFor<T>, u32)>;
+
+	/// Where [`Hooks::on_idle`] is in removing the `CallCounts` of past periods: the first block
+	/// of the period the last pass started in, and the account it stopped after if the pass is not
+	/// over.
+	#[pallet::storage]
+	pub type CallCountsCursor<T: Config> =
+		StorageValue<_, (BlockNumberFor<T>, Option<T::AccountId>)>;
+
+	/// The accounts whose calls are not limited, such as the senders of the
+	/// transactions `benchmark extrinsic` fills blocks with. Root sets them with
+	/// `System::set_storage`, and the node writes them into the genesis of benchmarked chains.
+	#[pallet::storage]
+	pub type RateLimitExempt<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, ()>;
+
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/main-docs/build/events-errors/
 	#[pallet::event]
@@ -59,6 +106,20 @@ pub mod pallet {
 		NoneValue,
 		/// Errors should have helpful documentation associated with them.
 		StorageOverflow,
+		/// The account made the most calls allowed in the current period.
+		RateLimited,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			Self::prune_call_counts(now, remaining_weight)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_n: BlockNumberFor<T>) -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -67,14 +128,19 @@ pub mod pallet {
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// An example dispatchable that takes a singles value as a parameter, writes the value to
-		/// storage and emits an event. This function must be dispatched by a signed extrinsic.
+		/// storage and emits an event. This function must be dispatched by `DoSomethingOrigin`.
 		#[pallet::call_index(0)]
-		#[pallet::weight(T::WeightInfo::do_something())]
+		#[pallet::weight(
+			T::WeightInfo::do_something().saturating_add(T::WeightInfo::check_rate_limit())
+		)]
 		pub fn do_something(origin: OriginFor<T>, something: u32) -> DispatchResult {
-			// Check that the extrinsic was signed and get the signer.
-			// This function will return an error if the extrinsic is not signed.
+			// Check that the extrinsic was dispatched by an allowed origin and get the account it
+			// acts for. This function will return an error if the origin is not allowed.
 			// https://docs.substrate.io/main-docs/build/origins/
-			let who = ensure_signed(origin)?;
+			let who = T::DoSomethingOrigin::ensure_origin(origin)?;
+			// Count the call against the rate limit of the account, whether it comes from a
+			// transaction, a contract or the EVM.
+			Self::count_call(&who)?;
 
 			// Update storage.
 			<Something<T>>::put(something);
@@ -87,9 +153,12 @@ pub mod pallet {
 
 		/// An example dispatchable that may throw a custom error.
 		#[pallet::call_index(1)]
-		#[pallet::weight(T::WeightInfo::cause_error())]
+		#[pallet::weight(
+			T::WeightInfo::cause_error().saturating_add(T::WeightInfo::check_rate_limit())
+		)]
 		pub fn cause_error(origin: OriginFor<T>) -> DispatchResult {
-			let _who = ensure_signed(origin)?;
+			let who = ensure_signed(origin)?;
+			Self::count_call(&who)?;
 
 			// Read a value from storage.
 			match <Something<T>>::get() {
@@ -104,5 +173,137 @@ pub mod pallet {
 				},
 			}
 		}
+
+		/// An example dispatchable that draws a value from the configured randomness source,
+		/// writes it to storage and emits an event. This function must be dispatched by a signed
+		/// extrinsic.
+		#[pallet::call_index(2)]
+		#[pallet::weight(
+			T::WeightInfo::store_random().saturating_add(T::WeightInfo::check_rate_limit())
+		)]
+		pub fn store_random(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::count_call(&who)?;
+
+			// The subject includes the caller so that different accounts drawing in the same
+			// block do not end up with the same value.
+			let (seed, _) = T::Randomness::random(&(RANDOMNESS_SUBJECT, &who).encode());
+			// Take the first four bytes of the seed; shorter seeds are padded with zeros.
+			let something = u32::decode(&mut TrailingZeroInput::new(seed.as_ref()))
+				.expect("input is padded with zeroes; qed");
+
+			<Something<T>>::put(something);
+
+			Self::deposit_event(Event::SomethingStored { something, who });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The first block of the rate limit period `now` is in, and the number of blocks left in
+		/// it, `now` included.
+		pub fn rate_period(now: BlockNumberFor<T>) -> (BlockNumberFor<T>, BlockNumberFor<T>) {
+			let length = T::RatePeriod::get().max(One::one());
+			let start = now - now % length;
+			(start, start + length - now)
+		}
+
+		/// The first block of the current period, the number of blocks left in it and the calls
+		/// `who` made in it, or `None` if `who` is exempt from the rate limit.
+		pub fn call_count(
+			who: &T::AccountId,
+		) -> Option<(BlockNumberFor<T>, BlockNumberFor<T>, u32)> {
+			if <RateLimitExempt<T>>::contains_key(who) {
+				return None
+			}
+			let (start, left) = Self::rate_period(frame_system::Pallet::<T>::block_number());
+			let calls = match <CallCounts<T>>::get(who) {
+				Some((period, calls)) if period == start => calls,
+				_ => 0,
+			};
+			Some((start, left, calls))
+		}
+
+		/// Counts a call of `who` in the current period, failing if `who` already made
+		/// `MaxCallsPerPeriod` calls in it. Calls that fail are not counted, their changes being
+		/// reverted.
+		pub(crate) fn count_call(who: &T::AccountId) -> DispatchResult {
+			if let Some((start, _, calls)) = Self::call_count(who) {
+				ensure!(calls < T::MaxCallsPerPeriod::get(), Error::<T>::RateLimited);
+				<CallCounts<T>>::insert(who, (start, calls + 1));
+			}
+			Ok(())
+		}
+
+		/// Removes the `CallCounts` of periods that are over, within `limit`, returning the weight
+		/// used.
+		///
+		/// A pass goes over all the counts once per period, resuming in the next block where it
+		/// ran out of weight. Counts of a period that ends while a pass is under way may be left
+		/// until the pass of the next period.
+		pub(crate) fn prune_call_counts(now: BlockNumberFor<T>, limit: Weight) -> Weight {
+			let db = T::DbWeight::get();
+			// Reading and writing the cursor.
+			let mut used = db.reads_writes(1, 1);
+			// Reading a count, and removing it if its period is over.
+			let per_count = db.reads_writes(1, 1);
+			if used.saturating_add(per_count).any_gt(limit) {
+				return Weight::zero()
+			}
+
+			let (start, _) = Self::rate_period(now);
+			let resume = match <CallCountsCursor<T>>::get() {
+				Some((pass_start, None)) if pass_start == start => return db.reads(1),
+				Some((_, Some(last))) => Some(last),
+				_ => None,
+			};
+			let mut counts = match resume {
+				Some(last) => <CallCounts<T>>::iter_from(<CallCounts<T>>::hashed_key_for(last)),
+				None => <CallCounts<T>>::iter(),
+			};
+			let mut last = None;
+			let cursor = loop {
+				if used.saturating_add(per_count).any_gt(limit) {
+					break last
+				}
+				match counts.next() {
+					Some((who, (period, _))) => {
+						used.saturating_accrue(per_count);
+						// Removing the count just read leaves the iteration unaffected.
+						if period < start {
+							<CallCounts<T>>::remove(&who);
+						}
+						last = Some(who);
+					},
+					None => break None,
+				}
+			};
+			<CallCountsCursor<T>>::put((start, cursor));
+			used
+		}
+
+		/// Checks the invariants of the pallet's storage.
+		///
+		/// Every entry of `CallCounts` is written by a call within the rate limit: it counts at
+		/// least one call in a period starting at a multiple of `RatePeriod` no later than the
+		/// current block, and no more than `MaxCallsPerPeriod` calls if that period is the
+		/// current one.
+		#[cfg(any(feature = "try-runtime", feature = "fuzzing", test))]
+		pub fn do_try_state() -> Result<(), &'static str> {
+			let now = frame_system::Pallet::<T>::block_number();
+			let length = T::RatePeriod::get().max(One::one());
+			for (start, calls) in <CallCounts<T>>::iter_values() {
+				ensure!(
+					start <= now && (start % length).is_zero(),
+					"A call count is for a period that did not start"
+				);
+				ensure!(calls > 0, "A call count counts no calls");
+				ensure!(
+					start + length <= now || calls <= T::MaxCallsPerPeriod::get(),
+					"An account made more calls than allowed in the current period"
+				);
+			}
+			Ok(())
+		}
 	}
 }
@@ -1,9 +1,14 @@
 use crate as pallet_template;
-use frame_support::traits::{ConstU16, ConstU64};
+use frame_support::{
+	parameter_types,
+	traits::{ConstU16, ConstU64, EnsureOrigin, Randomness},
+	weights::constants::RocksDbWeight,
+};
+use frame_system::{EnsureSigned, RawOrigin};
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
-	traits::{BlakeTwo256, IdentityLookup},
+	traits::{BlakeTwo256, Hash, IdentityLookup},
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -25,7 +30,7 @@ impl frame_system::Config for Test {
 	type BaseCallFilter = frame_support::traits::Everything;
 	type BlockWeights = ();
 	type BlockLength = ();
-	type DbWeight = ();
+	type DbWeight = RocksDbWeight;
 	type RuntimeOrigin = RuntimeOrigin;
 	type RuntimeCall = RuntimeCall;
 	type Index = u64;
@@ -48,12 +53,66 @@ impl frame_system::Config for Test {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+/// Deterministic randomness source: the output only depends on the subject and the current block
+/// number, so tests can predict the values drawn by the pallet.
+pub struct TestRandomness;
+
+impl Randomness<H256, u64> for TestRandomness {
+	fn random(subject: &[u8]) -> (H256, u64) {
+		let block_number = System::block_number();
+		(BlakeTwo256::hash_of(&(subject, block_number)), block_number)
+	}
+}
+
+parameter_types! {
+	/// The accounts allowed to call `do_something`, or any signed account when `None`.
+	pub static Holders: Option<Vec<u64>> = None;
+}
+
+/// Stands in for the NFT ownership check of the runtime: signed origins pass if their account is
+/// one of the [`Holders`].
+pub struct EnsureHolder;
+
+impl EnsureOrigin<RuntimeOrigin> for EnsureHolder {
+	type Success = u64;
+
+	fn try_origin(o: RuntimeOrigin) -> Result<Self::Success, RuntimeOrigin> {
+		let who = EnsureSigned::<u64>::try_origin(o)?;
+		match Holders::get() {
+			Some(holders) if !holders.contains(&who) => Err(RawOrigin::Signed(who).into()),
+			_ => Ok(who),
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+		let who = Holders::get().and_then(|holders| holders.first().copied()).unwrap_or_default();
+		Ok(RawOrigin::Signed(who).into())
+	}
+}
+
 impl pallet_template::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
 	type WeightInfo = ();
+	type Randomness = TestRandomness;
+	type DoSomethingOrigin = EnsureHolder;
+	type PriorityBoost = ConstU64<100>;
+	type RatePeriod = ConstU64<10>;
+	type MaxCallsPerPeriod = frame_support::traits::ConstU32<2>;
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
 	frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
 }
+
+/// Runs `test` on a fresh mock runtime, then checks the invariants of the pallet.
+///
+/// The invariants are only checked once `test` returns, on the state it leaves: a state breaking
+/// them in between goes unnoticed. `fuzzing::Scenario` checks them after every call.
+pub fn build_and_execute(test: impl FnOnce()) {
+	new_test_ext().execute_with(|| {
+		test();
+		TemplateModule::do_try_state().unwrap();
+	});
+}
@@ -1,9 +1,14 @@
 use crate::{mock::*, Error, Event};
+use codec::Encode;
 use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{
+	traits::{BlakeTwo256, Hash},
+	DispatchError,
+};
 
 #[test]
 fn it_works_for_default_value() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
 		// Go past genesis block so events get deposited
 		System::set_block_number(1);
 		// Dispatch a signed extrinsic.
@@ -15,9 +20,31 @@ fn it_works_for_default_value() {
 	});
 }
 
+#[test]
+fn do_something_requires_allowed_origin() {
+	build_and_execute(|| {
+		System::set_block_number(1);
+		Holders::set(Some(vec![2]));
+
+		// Only holders may store a value.
+		assert_noop!(
+			TemplateModule::do_something(RuntimeOrigin::signed(1), 42),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(TemplateModule::do_something(RuntimeOrigin::signed(2), 42));
+		assert_eq!(TemplateModule::something(), Some(42));
+
+		// Unsigned origins are never allowed.
+		assert_noop!(
+			TemplateModule::do_something(RuntimeOrigin::none(), 43),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
 #[test]
 fn correct_error_for_none_value() {
-	new_test_ext().execute_with(|| {
+	build_and_execute(|| {
 		// Ensure the expected error is thrown when no value is present.
 		assert_noop!(
 			TemplateModule::cause_error(RuntimeOrigin::signed(1)),
@@ -25,3 +52,245 @@ fn correct_error_for_none_value() {
 		);
 	});
 }
+
+#[test]
+fn store_random_uses_randomness_source() {
+	build_and_execute(|| {
+		System::set_block_number(1);
+		assert_ok!(TemplateModule::store_random(RuntimeOrigin::signed(1)));
+
+		// The mock randomness is the hash of the subject and the block number, and the pallet keeps
+		// its first four bytes.
+		let subject = (b"pallet-template/something".as_slice(), 1u64).encode();
+		let seed = BlakeTwo256::hash_of(&(subject, 1u64));
+		let expected = u32::from_le_bytes(seed.as_ref()[..4].try_into().unwrap());
+		assert_eq!(TemplateModule::something(), Some(expected));
+		System::assert_last_event(Event::SomethingStored { something: expected, who: 1 }.into());
+	});
+}
+
+#[test]
+fn store_random_differs_per_caller() {
+	build_and_execute(|| {
+		System::set_block_number(1);
+		assert_ok!(TemplateModule::store_random(RuntimeOrigin::signed(1)));
+		let first = TemplateModule::something();
+		assert_ok!(TemplateModule::store_random(RuntimeOrigin::signed(2)));
+		assert_ne!(TemplateModule::something(), first);
+	});
+}
+
+#[test]
+fn try_state_detects_corrupt_call_counts() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(13);
+		let try_state_with = |count| {
+			crate::CallCounts::<Test>::insert(1, count);
+			TemplateModule::do_try_state()
+		};
+
+		assert_ok!(try_state_with((10, 2)));
+		// Counts of past periods are no longer limited.
+		assert_ok!(try_state_with((0, 3)));
+
+		assert!(try_state_with((10, 3)).is_err());
+		assert!(try_state_with((10, 0)).is_err());
+		assert!(try_state_with((12, 1)).is_err());
+		assert!(try_state_with((20, 1)).is_err());
+	});
+}
+
+#[test]
+fn rate_limit_extension_boosts_and_limits_template_calls() {
+	use crate::extension::{CheckTemplateRateLimit, RATE_LIMITED};
+	use sp_runtime::{
+		traits::SignedExtension,
+		transaction_validity::{InvalidTransaction, TransactionValidityError},
+	};
+
+	build_and_execute(|| {
+		let call: RuntimeCall = crate::Call::do_something { something: 42 }.into();
+		let info = Default::default();
+		let extension = CheckTemplateRateLimit::<Test>::new();
+		let limited = TransactionValidityError::Invalid(InvalidTransaction::Custom(RATE_LIMITED));
+		System::set_block_number(13);
+
+		// Calls are boosted and valid until the end of the period of blocks 10 to 19.
+		let valid = extension.validate(&1, &call, &info, 0).unwrap();
+		assert_eq!((valid.priority, valid.longevity), (100, 7));
+
+		// Checking a call does not count it.
+		assert_ok!(extension.clone().pre_dispatch(&1, &call, &info, 0));
+		assert_eq!(crate::CallCounts::<Test>::get(1), None);
+
+		// Two calls per period are allowed, for each account.
+		assert_ok!(TemplateModule::do_something(RuntimeOrigin::signed(1), 42));
+		assert_ok!(TemplateModule::store_random(RuntimeOrigin::signed(1)));
+		assert_eq!(crate::CallCounts::<Test>::get(1), Some((10, 2)));
+		assert_eq!(extension.validate(&1, &call, &info, 0), Err(limited));
+		assert_eq!(extension.clone().pre_dispatch(&1, &call, &info, 0), Err(limited));
+		assert_ok!(extension.clone().pre_dispatch(&2, &call, &info, 0));
+
+		// The count starts over with the next period.
+		System::set_block_number(20);
+		assert_ok!(extension.clone().pre_dispatch(&1, &call, &info, 0));
+		assert_ok!(TemplateModule::do_something(RuntimeOrigin::signed(1), 42));
+		assert_eq!(crate::CallCounts::<Test>::get(1), Some((20, 1)));
+	});
+}
+
+#[test]
+fn rate_limit_extension_exempts_accounts() {
+	use crate::extension::CheckTemplateRateLimit;
+	use sp_runtime::traits::SignedExtension;
+
+	build_and_execute(|| {
+		let call: RuntimeCall = crate::Call::do_something { something: 42 }.into();
+		let info = Default::default();
+		let extension = CheckTemplateRateLimit::<Test>::new();
+		crate::RateLimitExempt::<Test>::insert(1, ());
+
+		// Exempt calls are boosted, but neither limited nor counted.
+		let valid = extension.validate(&1, &call, &info, 0).unwrap();
+		assert_eq!((valid.priority, valid.longevity), (100, u64::MAX));
+		for _ in 0..3 {
+			assert_ok!(extension.clone().pre_dispatch(&1, &call, &info, 0));
+			assert_ok!(TemplateModule::do_something(RuntimeOrigin::signed(1), 42));
+		}
+		assert_eq!(crate::CallCounts::<Test>::get(1), None);
+	});
+}
+
+#[test]
+fn calls_over_the_rate_limit_fail_however_dispatched() {
+	build_and_execute(|| {
+		System::set_block_number(13);
+		assert_ok!(TemplateModule::do_something(RuntimeOrigin::signed(1), 42));
+		assert_ok!(TemplateModule::cause_error(RuntimeOrigin::signed(1)));
+
+		// Calls made without the extension, e.g. by a contract, are limited all the same.
+		assert_noop!(
+			TemplateModule::do_something(RuntimeOrigin::signed(1), 42),
+			Error::<Test>::RateLimited
+		);
+		assert_noop!(
+			TemplateModule::store_random(RuntimeOrigin::signed(1)),
+			Error::<Test>::RateLimited
+		);
+		assert_eq!(TemplateModule::something(), Some(43));
+
+		// Failed calls are not counted.
+		crate::Something::<Test>::put(u32::MAX);
+		assert_noop!(
+			TemplateModule::cause_error(RuntimeOrigin::signed(2)),
+			Error::<Test>::StorageOverflow
+		);
+		assert_eq!(crate::CallCounts::<Test>::get(2), None);
+	});
+}
+
+#[test]
+fn template_calls_are_weighted_with_the_rate_limit() {
+	use crate::WeightInfo;
+	use frame_support::{dispatch::GetDispatchInfo, traits::Get};
+
+	let check = <() as WeightInfo>::check_rate_limit();
+	let db = <Test as frame_system::Config>::DbWeight::get();
+	assert!(check.all_gte(db.reads_writes(2, 1)));
+
+	let calls: [(RuntimeCall, _); 3] = [
+		(crate::Call::do_something { something: 42 }.into(), <() as WeightInfo>::do_something()),
+		(crate::Call::cause_error {}.into(), <() as WeightInfo>::cause_error()),
+		(crate::Call::store_random {}.into(), <() as WeightInfo>::store_random()),
+	];
+	for (call, weight) in calls {
+		assert_eq!(call.get_dispatch_info().weight, weight + check);
+	}
+}
+
+#[test]
+fn call_counts_of_past_periods_are_pruned_when_idle() {
+	use frame_support::{
+		traits::{Get, Hooks},
+		weights::Weight,
+	};
+
+	build_and_execute(|| {
+		let db = <Test as frame_system::Config>::DbWeight::get();
+		System::set_block_number(13);
+		for who in 1..=4 {
+			crate::CallCounts::<Test>::insert(who, (0, 1));
+		}
+
+		// The cursor and two counts fit: the pass resumes in the next block.
+		let limit = db.reads_writes(3, 3);
+		assert_eq!(TemplateModule::on_idle(13, limit), limit);
+		assert_eq!(crate::CallCounts::<Test>::iter().count(), 2);
+		assert_eq!(TemplateModule::on_idle(14, Weight::MAX), db.reads_writes(3, 3));
+		assert_eq!(crate::CallCounts::<Test>::iter().count(), 0);
+
+		// Nothing is left to prune until the next period.
+		crate::CallCounts::<Test>::insert(1, (10, 1));
+		assert_eq!(TemplateModule::on_idle(15, Weight::MAX), db.reads(1));
+
+		// Which only prunes the counts of periods that are over.
+		System::set_block_number(20);
+		crate::CallCounts::<Test>::insert(2, (20, 1));
+		TemplateModule::on_idle(20, Weight::MAX);
+		assert_eq!(crate::CallCounts::<Test>::iter_keys().collect::<Vec<_>>(), vec![2]);
+	});
+}
+
+#[test]
+fn rate_limit_extension_ignores_other_calls() {
+	use crate::extension::CheckTemplateRateLimit;
+	use sp_runtime::{traits::SignedExtension, transaction_validity::ValidTransaction};
+
+	build_and_execute(|| {
+		let call: RuntimeCall = frame_system::Call::remark { remark: vec![] }.into();
+		let info = Default::default();
+		let extension = CheckTemplateRateLimit::<Test>::new();
+
+		for _ in 0..3 {
+			assert_eq!(extension.validate(&1, &call, &info, 0), Ok(ValidTransaction::default()));
+			assert_ok!(extension.clone().pre_dispatch(&1, &call, &info, 0));
+		}
+		assert_eq!(crate::CallCounts::<Test>::get(1), None);
+	});
+}
+
+mod properties {
+	use crate::fuzzing::{Action, Origin, Scenario};
+	use proptest::{collection::vec, option, prelude::*};
+
+	fn origin() -> impl Strategy<Value = Origin> {
+		prop_oneof![
+			8 => (0u8..4).prop_map(Origin::Signed),
+			1 => Just(Origin::Root),
+			1 => Just(Origin::None),
+		]
+	}
+
+	/// Values close to `u32::MAX` are drawn often, so that `cause_error` reaches the overflow.
+	fn something() -> impl Strategy<Value = u32> {
+		prop_oneof![any::<u32>(), (u32::MAX - 3)..=u32::MAX, 0u32..4]
+	}
+
+	fn action() -> impl Strategy<Value = Action> {
+		prop_oneof![
+			(origin(), something())
+				.prop_map(|(origin, something)| Action::DoSomething { origin, something }),
+			origin().prop_map(|origin| Action::CauseError { origin }),
+		]
+	}
+
+	proptest! {
+		#[test]
+		fn random_calls_match_the_model(
+			holders in option::of(vec(0u8..4, 0..3)),
+			actions in vec(action(), 1..64),
+		) {
+			Scenario { holders, actions }.run();
+		}
+	}
+}
@@ -6,6 +6,11 @@
 //! WORST CASE MAP SIZE: `1000000`
 //! HOSTNAME: `Alexs-MacBook-Pro-2.local`, CPU: `<UNKNOWN>`
 //! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 1024
+//!
+//! Not part of this run, estimated from their storage accesses until the file is regenerated
+//! with `scripts/benchmark-weights.sh template`: `do_something`, whose reads of the NFT
+//! ownership check of the runtime's `DoSomethingOrigin` were added, `store_random` and
+//! `check_rate_limit`.
 
 // Executed Command:
 // ../../target/release/node-template
@@ -37,19 +42,21 @@ use core::marker::PhantomData;
 pub trait WeightInfo {
 	fn do_something() -> Weight;
 	fn cause_error() -> Weight;
+	fn store_random() -> Weight;
+	fn check_rate_limit() -> Weight;
 }
 
 /// Weights for pallet_template using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Runtime TemplateAccessCollection (r:1 w:0)
+	/// Storage: Nfts Account (r:1 w:0)
+	/// Proof: Nfts Account (max_values: None, max_size: Some(88), added: 2563, mode: MaxEncodedLen)
 	/// Storage: TemplateModule Something (r:0 w:1)
 	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
 	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+		Weight::from_parts(20_000_000, 3553)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
 	/// Storage: TemplateModule Something (r:1 w:1)
@@ -63,18 +70,36 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
+	/// Storage: RandomnessCollectiveFlip RandomMaterial (r:1 w:0)
+	/// Proof: RandomnessCollectiveFlip RandomMaterial (max_values: Some(1), max_size: Some(2594), added: 3089, mode: MaxEncodedLen)
+	/// Storage: TemplateModule Something (r:0 w:1)
+	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	fn store_random() -> Weight {
+		Weight::from_parts(43_000_000, 4079)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: TemplateModule RateLimitExempt (r:1 w:0)
+	/// Proof: TemplateModule RateLimitExempt (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	/// Storage: TemplateModule CallCounts (r:1 w:1)
+	/// Proof: TemplateModule CallCounts (max_values: None, max_size: Some(56), added: 2531, mode: MaxEncodedLen)
+	fn check_rate_limit() -> Weight {
+		Weight::from_parts(9_000_000, 5054)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
+	/// Storage: Runtime TemplateAccessCollection (r:1 w:0)
+	/// Storage: Nfts Account (r:1 w:0)
+	/// Proof: Nfts Account (max_values: None, max_size: Some(88), added: 2563, mode: MaxEncodedLen)
 	/// Storage: TemplateModule Something (r:0 w:1)
 	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
 	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+		Weight::from_parts(20_000_000, 3553)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
 	/// Storage: TemplateModule Something (r:1 w:1)
@@ -88,4 +113,22 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
+	/// Storage: RandomnessCollectiveFlip RandomMaterial (r:1 w:0)
+	/// Proof: RandomnessCollectiveFlip RandomMaterial (max_values: Some(1), max_size: Some(2594), added: 3089, mode: MaxEncodedLen)
+	/// Storage: TemplateModule Something (r:0 w:1)
+	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	fn store_random() -> Weight {
+		Weight::from_parts(43_000_000, 4079)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: TemplateModule RateLimitExempt (r:1 w:0)
+	/// Proof: TemplateModule RateLimitExempt (max_values: None, max_size: Some(48), added: 2523, mode: MaxEncodedLen)
+	/// Storage: TemplateModule CallCounts (r:1 w:1)
+	/// Proof: TemplateModule CallCounts (max_values: None, max_size: Some(56), added: 2531, mode: MaxEncodedLen)
+	fn check_rate_limit() -> Weight {
+		Weight::from_parts(9_000_000, 5054)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
 }
@@ -0,0 +1,355 @@
+//! Verification of the GRANDPA finality proofs exported by the node's `finality-proof` command.
+//!
+//! An export holds the headers of justified blocks in ascending order, each with its GRANDPA
+//! justification. For an export of the trusted chain, identified by the hash of its genesis block,
+//! and starting from the genesis authority set, [`verify`] checks every justification
+//! against the authority set in charge at that block, and follows the authority set changes that
+//! the justified headers enact.
+//!
+//! Only changes enacted by the block that signals them are supported, which is how
+//! `pallet_grandpa` schedules changes at session boundaries. Those blocks always carry a
+//! justification, so following them is enough to know the authority set of any later block.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use codec::{Decode, Encode};
+use finality_grandpa::{Commit, Message};
+use node_template_runtime::{BlockNumber, Hash, Header};
+use serde::{Deserialize, Serialize};
+use sp_consensus_grandpa::{
+	AuthorityId, AuthorityList, AuthoritySignature, ConsensusLog, SetId, GRANDPA_ENGINE_ID,
+};
+use sp_core::{ed25519, Bytes, Pair};
+use sp_runtime::{generic::OpaqueDigestItemId, traits::Header as HeaderT};
+
+/// Justified blocks of a chain, as exported by the `finality-proof` command.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinalityProofs {
+	/// The hash of the genesis block of the chain.
+	pub genesis_hash: Hash,
+	/// The justified blocks, in ascending order of block number.
+	pub blocks: Vec<JustifiedBlock>,
+}
+
+/// A block header with the GRANDPA justification finalizing it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JustifiedBlock {
+	/// The header of the justified block.
+	pub header: Header,
+	/// The SCALE-encoded GRANDPA justification of the block.
+	pub justification: Bytes,
+}
+
+/// The GRANDPA authority set in charge of finalizing blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthoritySet {
+	/// The id of the set, incremented on every change.
+	pub set_id: SetId,
+	/// The authorities and their voting weights.
+	pub authorities: AuthorityList,
+}
+
+/// The layout of the justifications stored by `sc_consensus_grandpa`.
+#[derive(Encode, Decode)]
+struct GrandpaJustification {
+	round: u64,
+	commit: Commit<Hash, BlockNumber, AuthoritySignature, AuthorityId>,
+	votes_ancestries: Vec<Header>,
+}
+
+/// Reasons for [`FinalityProofs`] to be rejected.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+	/// The proofs are exported from another chain than the trusted one.
+	WrongChain(Hash),
+	/// The blocks are not in ascending order of block number.
+	UnorderedBlocks(BlockNumber),
+	/// The justification of the block cannot be decoded.
+	InvalidJustification(BlockNumber, codec::Error),
+	/// The justification finalizes another block than the one it is exported with.
+	WrongTarget(BlockNumber),
+	/// A precommit is signed by an account outside of the authority set.
+	UnknownAuthority(BlockNumber),
+	/// An authority signed several precommits.
+	DuplicateVote(BlockNumber),
+	/// A precommit signature is invalid for the round and set id of the justification.
+	BadSignature(BlockNumber),
+	/// A precommit targets a block that is not proven to descend from the justified one.
+	UnknownAncestry(BlockNumber),
+	/// The precommits do not carry more than two thirds of the voting weight.
+	NotEnoughWeight(BlockNumber),
+	/// The block signals a delayed or forced authority set change.
+	UnsupportedChange(BlockNumber),
+	/// The authority set in charge of the block is empty or has no voting weight.
+	NoVotingWeight(BlockNumber),
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Error::WrongChain(hash) =>
+				write!(f, "the proofs are of the chain with genesis {hash:?}"),
+			Error::UnorderedBlocks(n) => write!(f, "block #{n} is not above the previous block"),
+			Error::InvalidJustification(n, e) => write!(f, "invalid justification of #{n}: {e}"),
+			Error::WrongTarget(n) => write!(f, "justification of #{n} finalizes another block"),
+			Error::UnknownAuthority(n) => write!(f, "justification of #{n} has unknown voters"),
+			Error::DuplicateVote(n) => write!(f, "justification of #{n} has duplicate votes"),
+			Error::BadSignature(n) => write!(f, "justification of #{n} has a bad signature"),
+			Error::UnknownAncestry(n) => write!(f, "justification of #{n} lacks vote ancestries"),
+			Error::NotEnoughWeight(n) => write!(f, "justification of #{n} lacks votes"),
+			Error::UnsupportedChange(n) =>
+				write!(f, "block #{n} signals a delayed or forced authority set change"),
+			Error::NoVotingWeight(n) => write!(f, "the authority set of #{n} has no voting weight"),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+/// Returns the authorities that take over after `header`, if it enacts an authority set change.
+pub fn enacted_change(header: &Header) -> Result<Option<AuthorityList>, Error> {
+	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+	for log in header.digest.logs() {
+		match log.try_to::<ConsensusLog<BlockNumber>>(id) {
+			Some(ConsensusLog::ScheduledChange(change)) if change.delay == 0 =>
+				return Ok(Some(change.next_authorities)),
+			Some(ConsensusLog::ScheduledChange(_)) | Some(ConsensusLog::ForcedChange(..)) =>
+				return Err(Error::UnsupportedChange(header.number)),
+			_ => {},
+		}
+	}
+	Ok(None)
+}
+
+/// Verifies `proofs` of the chain whose genesis block is `genesis_hash`, starting from the genesis
+/// authority set, returning the authority set in charge after the last block. Every header of
+/// `proofs` is final once this returns `Ok`.
+///
+/// Chains sharing their genesis authorities, e.g. development chains, accept the justifications
+/// of one another: `genesis_hash` must come from a trusted source, not from `proofs`.
+pub fn verify(
+	proofs: &FinalityProofs,
+	genesis_hash: Hash,
+	genesis_authorities: AuthorityList,
+) -> Result<AuthoritySet, Error> {
+	if proofs.genesis_hash != genesis_hash {
+		return Err(Error::WrongChain(proofs.genesis_hash))
+	}
+	let mut set = AuthoritySet { set_id: 0, authorities: genesis_authorities };
+	let mut last_number = 0;
+
+	for JustifiedBlock { header, justification } in &proofs.blocks {
+		if header.number <= last_number {
+			return Err(Error::UnorderedBlocks(header.number))
+		}
+		last_number = header.number;
+
+		verify_justification(header, justification, &set)?;
+		if let Some(authorities) = enacted_change(header)? {
+			set = AuthoritySet { set_id: set.set_id + 1, authorities };
+		}
+	}
+
+	Ok(set)
+}
+
+/// Checks that `encoded` is a justification of `header` by `set`.
+fn verify_justification(header: &Header, encoded: &[u8], set: &AuthoritySet) -> Result<(), Error> {
+	let number = header.number;
+	// Two thirds of nothing would be reached without a single precommit.
+	let total: u64 = set.authorities.iter().map(|(_, weight)| weight).sum();
+	if total == 0 {
+		return Err(Error::NoVotingWeight(number))
+	}
+	let justification = GrandpaJustification::decode(&mut &encoded[..])
+		.map_err(|e| Error::InvalidJustification(number, e))?;
+	let hash = header.hash();
+	if (justification.commit.target_hash, justification.commit.target_number) != (hash, number) {
+		return Err(Error::WrongTarget(number))
+	}
+
+	let ancestry: BTreeMap<Hash, Hash> = justification
+		.votes_ancestries
+		.iter()
+		.map(|header| (header.hash(), header.parent_hash))
+		.collect();
+	let descends_from_target = |mut block: Hash| loop {
+		if block == hash {
+			return true
+		}
+		match ancestry.get(&block) {
+			Some(parent) => block = *parent,
+			None => return false,
+		}
+	};
+
+	let mut voters = BTreeSet::new();
+	let mut weight = 0;
+	for signed in &justification.commit.precommits {
+		let voter_weight = set
+			.authorities
+			.iter()
+			.find(|(id, _)| id == &signed.id)
+			.map(|(_, weight)| *weight)
+			.ok_or(Error::UnknownAuthority(number))?;
+		if !voters.insert(signed.id.clone()) {
+			return Err(Error::DuplicateVote(number))
+		}
+
+		let message = Message::Precommit(signed.precommit.clone());
+		let payload =
+			sp_consensus_grandpa::localized_payload(justification.round, set.set_id, &message);
+		let signature: &ed25519::Signature = signed.signature.as_ref();
+		if !ed25519::Pair::verify(signature, payload, signed.id.as_ref()) {
+			return Err(Error::BadSignature(number))
+		}
+
+		if !descends_from_target(signed.precommit.target_hash) {
+			return Err(Error::UnknownAncestry(number))
+		}
+		weight += voter_weight;
+	}
+
+	// Like `finality_grandpa::VoterSet`, tolerate less than a third of faulty weight.
+	if weight < total - total.saturating_sub(1) / 3 {
+		return Err(Error::NotEnoughWeight(number))
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use finality_grandpa::{Precommit, SignedPrecommit};
+	use sp_consensus_grandpa::ScheduledChange;
+	use sp_keyring::Ed25519Keyring::{self, *};
+	use sp_runtime::{Digest, DigestItem};
+
+	fn authorities(keys: &[Ed25519Keyring]) -> AuthorityList {
+		keys.iter().map(|key| (key.public().into(), 1)).collect()
+	}
+
+	fn header(number: BlockNumber, parent_hash: Hash, change: Option<AuthorityList>) -> Header {
+		let logs = change
+			.map(|next_authorities| {
+				let log = ConsensusLog::<BlockNumber>::ScheduledChange(ScheduledChange {
+					next_authorities,
+					delay: 0,
+				});
+				DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode())
+			})
+			.into_iter()
+			.collect();
+		Header::new(number, Default::default(), Default::default(), parent_hash, Digest { logs })
+	}
+
+	fn justify(header: Header, set_id: SetId, signers: &[Ed25519Keyring]) -> JustifiedBlock {
+		let round = 1;
+		let precommit = Precommit { target_hash: header.hash(), target_number: header.number };
+		let payload = sp_consensus_grandpa::localized_payload(
+			round,
+			set_id,
+			&Message::<Hash, BlockNumber>::Precommit(precommit.clone()),
+		);
+		let precommits = signers
+			.iter()
+			.map(|key| SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: key.sign(&payload).into(),
+				id: key.public().into(),
+			})
+			.collect();
+		let justification = GrandpaJustification {
+			round,
+			commit: Commit { target_hash: header.hash(), target_number: header.number, precommits },
+			votes_ancestries: Vec::new(),
+		};
+
+		JustifiedBlock { header, justification: justification.encode().into() }
+	}
+
+	const GENESIS: Hash = Hash::repeat_byte(0x69);
+
+	fn proofs(blocks: Vec<JustifiedBlock>) -> FinalityProofs {
+		FinalityProofs { genesis_hash: GENESIS, blocks }
+	}
+
+	#[test]
+	fn rejects_proofs_of_another_chain() {
+		let genesis = authorities(&[Alice]);
+		let proofs = proofs(vec![justify(header(1, Default::default(), None), 0, &[Alice])]);
+
+		// Valid proofs, that another chain with the same authorities would produce too.
+		assert!(verify(&proofs, GENESIS, genesis.clone()).is_ok());
+		let other = Hash::repeat_byte(0x42);
+		assert_eq!(verify(&proofs, other, genesis), Err(Error::WrongChain(GENESIS)));
+	}
+
+	#[test]
+	fn follows_authority_set_changes() {
+		let genesis = authorities(&[Alice, Bob, Charlie, Dave]);
+		let first = header(1, Default::default(), Some(authorities(&[Eve])));
+		let second = header(2, first.hash(), None);
+		let proofs =
+			proofs(vec![justify(first, 0, &[Alice, Bob, Charlie]), justify(second, 1, &[Eve])]);
+
+		assert_eq!(
+			verify(&proofs, GENESIS, genesis),
+			Ok(AuthoritySet { set_id: 1, authorities: authorities(&[Eve]) })
+		);
+	}
+
+	#[test]
+	fn rejects_justification_by_previous_set() {
+		let genesis = authorities(&[Alice, Bob, Charlie, Dave]);
+		let first = header(1, Default::default(), Some(authorities(&[Eve])));
+		let second = header(2, first.hash(), None);
+		let proofs = proofs(vec![
+			justify(first, 0, &[Alice, Bob, Charlie]),
+			justify(second, 0, &[Alice, Bob, Charlie]),
+		]);
+
+		assert_eq!(verify(&proofs, GENESIS, genesis), Err(Error::UnknownAuthority(2)));
+	}
+
+	#[test]
+	fn rejects_missing_supermajority() {
+		let genesis = authorities(&[Alice, Bob, Charlie, Dave]);
+		let proofs = proofs(vec![justify(header(1, Default::default(), None), 0, &[Alice, Bob])]);
+
+		assert_eq!(verify(&proofs, GENESIS, genesis), Err(Error::NotEnoughWeight(1)));
+	}
+
+	#[test]
+	fn rejects_authority_sets_without_voting_weight() {
+		let unjustified = || proofs(vec![justify(header(1, Default::default(), None), 0, &[])]);
+		assert_eq!(verify(&unjustified(), GENESIS, Vec::new()), Err(Error::NoVotingWeight(1)));
+		let weightless = vec![(Alice.public().into(), 0)];
+		assert_eq!(verify(&unjustified(), GENESIS, weightless), Err(Error::NoVotingWeight(1)));
+
+		// Nor can a set hand over to an empty one.
+		let first = header(1, Default::default(), Some(Vec::new()));
+		let second = header(2, first.hash(), None);
+		let proofs = proofs(vec![justify(first, 0, &[Alice]), justify(second, 1, &[])]);
+		assert_eq!(verify(&proofs, GENESIS, authorities(&[Alice])), Err(Error::NoVotingWeight(2)));
+	}
+
+	#[test]
+	fn rejects_signature_for_another_set() {
+		let genesis = authorities(&[Alice]);
+		let proofs = proofs(vec![justify(header(1, Default::default(), None), 1, &[Alice])]);
+
+		assert_eq!(verify(&proofs, GENESIS, genesis), Err(Error::BadSignature(1)));
+	}
+
+	#[test]
+	fn rejects_justification_of_another_block() {
+		let genesis = authorities(&[Alice]);
+		let mut block = justify(header(1, Default::default(), None), 0, &[Alice]);
+		block.header.parent_hash = Hash::repeat_byte(1);
+
+		assert_eq!(verify(&proofs(vec![block]), GENESIS, genesis), Err(Error::WrongTarget(1)));
+	}
+}
@@ -0,0 +1,165 @@
+//! Verification of the storage proofs served by `template_somethingWithProof`.
+//!
+//! The RPC returns `pallet_template::Something` together with the header of a finalized block
+//! and the trie nodes proving the value against that header's state root. A client that trusts
+//! the hash of the block, for example because it follows GRANDPA finality, can check the value
+//! with [`verify`] without trusting the RPC node.
+//!
+//! The [`finality`] module establishes such trusted hashes from the GRANDPA justifications
+//! exported by the node's `finality-proof` command.
+
+use std::fmt;
+
+use codec::Decode;
+use node_template_runtime::{pallet_template, BlakeTwo256, Hash, Header, Runtime};
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use sp_runtime::traits::Header as HeaderT;
+use sp_trie::{LayoutV1, StorageProof};
+
+pub mod finality;
+
+/// `pallet_template::Something` at a block, with a proof of its value.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SomethingProof {
+	/// The header of the block the value is read at.
+	pub header: Header,
+	/// The value of `Something`, `None` when it is not set.
+	pub value: Option<u32>,
+	/// The trie nodes proving `value` against `header.state_root`.
+	pub proof: Vec<Bytes>,
+}
+
+/// Reasons for a [`SomethingProof`] to be rejected.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+	/// The header does not hash to the trusted block hash.
+	UntrustedHeader { expected: Hash, found: Hash },
+	/// The trie nodes do not prove any value of `Something` against the state root.
+	InvalidProof(String),
+	/// The proven storage value is not a valid `u32`.
+	InvalidValue(codec::Error),
+	/// The proven value differs from the one claimed in the response.
+	ValueMismatch { claimed: Option<u32>, proven: Option<u32> },
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::UntrustedHeader { expected, found } =>
+				write!(f, "header hashes to {found:?}, expected {expected:?}"),
+			Error::InvalidProof(e) => write!(f, "invalid storage proof: {e}"),
+			Error::InvalidValue(e) => write!(f, "invalid storage value: {e}"),
+			Error::ValueMismatch { claimed, proven } =>
+				write!(f, "claimed value {claimed:?} but the proof contains {proven:?}"),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+/// The storage key of `pallet_template::Something`.
+pub fn something_key() -> Vec<u8> {
+	pallet_template::Something::<Runtime>::hashed_key().to_vec()
+}
+
+/// Reads `Something` out of the trie nodes of `proof`, checking them against `state_root`.
+pub fn read_something(state_root: &Hash, proof: &[Bytes]) -> Result<Option<u32>, Error> {
+	let db =
+		StorageProof::new(proof.iter().map(|node| node.0.clone())).into_memory_db::<BlakeTwo256>();
+	let value = sp_trie::read_trie_value::<LayoutV1<BlakeTwo256>, _>(
+		&db,
+		state_root,
+		&something_key(),
+		None,
+		None,
+	)
+	.map_err(|e| Error::InvalidProof(e.to_string()))?;
+
+	value
+		.map(|value| u32::decode(&mut &value[..]))
+		.transpose()
+		.map_err(Error::InvalidValue)
+}
+
+/// Verifies `proof` against the hash of a block the caller trusts, returning the proven value of
+/// `Something` at that block.
+pub fn verify(proof: &SomethingProof, trusted_hash: Hash) -> Result<Option<u32>, Error> {
+	let found = proof.header.hash();
+	if found != trusted_hash {
+		return Err(Error::UntrustedHeader { expected: trusted_hash, found })
+	}
+
+	let proven = read_something(&proof.header.state_root, &proof.proof)?;
+	if proven != proof.value {
+		return Err(Error::ValueMismatch { claimed: proof.value, proven })
+	}
+	Ok(proven)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use sp_runtime::StateVersion;
+	use sp_state_machine::{prove_read, InMemoryBackend};
+
+	/// Builds a state holding `value` and an unrelated entry, and a proof of `Something` in it.
+	fn prove(value: Option<u32>) -> SomethingProof {
+		let mut entries = vec![(b"unrelated".to_vec(), Some(vec![1, 2, 3]))];
+		entries.extend(value.map(|value| (something_key(), Some(value.encode()))));
+		let backend =
+			InMemoryBackend::<BlakeTwo256>::from((vec![(None, entries)], StateVersion::V1));
+		let state_root = *backend.root();
+		let proof = prove_read(backend, [something_key()]).unwrap();
+
+		SomethingProof {
+			header: Header::new(
+				1,
+				Default::default(),
+				state_root,
+				Default::default(),
+				Default::default(),
+			),
+			value,
+			proof: proof.into_iter_nodes().map(Bytes).collect(),
+		}
+	}
+
+	#[test]
+	fn verifies_set_and_unset_values() {
+		let proof = prove(Some(42));
+		assert_eq!(verify(&proof, proof.header.hash()), Ok(Some(42)));
+
+		let proof = prove(None);
+		assert_eq!(verify(&proof, proof.header.hash()), Ok(None));
+	}
+
+	#[test]
+	fn rejects_untrusted_header() {
+		let proof = prove(Some(42));
+		let trusted_hash = Hash::repeat_byte(1);
+		assert_eq!(
+			verify(&proof, trusted_hash),
+			Err(Error::UntrustedHeader { expected: trusted_hash, found: proof.header.hash() })
+		);
+	}
+
+	#[test]
+	fn rejects_tampered_value() {
+		let mut proof = prove(Some(42));
+		proof.value = Some(43);
+		assert_eq!(
+			verify(&proof, proof.header.hash()),
+			Err(Error::ValueMismatch { claimed: Some(43), proven: Some(42) })
+		);
+	}
+
+	#[test]
+	fn rejects_proof_of_another_state() {
+		let mut proof = prove(Some(42));
+		proof.proof = prove(Some(43)).proof;
+		assert!(matches!(verify(&proof, proof.header.hash()), Err(Error::InvalidProof(_))));
+	}
+}
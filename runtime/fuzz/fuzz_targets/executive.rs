@@ -0,0 +1,21 @@
+//! Blocks of arbitrary signed extrinsics executed on the genesis of the development chain.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use libfuzzer_sys::fuzz_target;
+use node_template_runtime_fuzz::{execute_blocks, FuzzBlock};
+use sp_runtime::{BuildStorage, Storage};
+
+/// The genesis state of the development chain, built once.
+static GENESIS: OnceLock<Storage> = OnceLock::new();
+
+fuzz_target!(|blocks: Vec<FuzzBlock>| {
+	let genesis = GENESIS.get_or_init(|| {
+		node_template::chain_spec::development_config()
+			.and_then(|spec| spec.build_storage())
+			.expect("The development chain spec builds")
+	});
+	execute_blocks(genesis, &blocks);
+});
@@ -0,0 +1,178 @@
+//! Blocks of arbitrary calls executed through the `Executive` of the runtime, checked against
+//! runtime-wide invariants.
+//!
+//! The calls are decoded from raw bytes as any `RuntimeCall`, signed by one of the development
+//! accounts and applied with `Executive::apply_extrinsic` on top of a genesis state, the one of the
+//! development chain spec in the fuzz targets. After every extrinsic, the total issuance must be
+//! the sum of the balances of all accounts; after every block, the weight of each dispatch class
+//! and the length of the block must be within their limits, and the `try_state` hooks of all
+//! pallets must pass.
+
+use codec::{DecodeLimit, Encode};
+use frame_support::{
+	dispatch::DispatchClass,
+	traits::{TryState, TryStateSelect},
+};
+use node_template_runtime::{
+	pallet_sponsorship, pallet_template, AllPalletsWithSystem, Balance, Balances, BlockLength,
+	BlockNumber, BlockWeights, Executive, Hash, Header, Runtime, RuntimeCall, SignedExtra,
+	SignedPayload, System, TimestampCall, UncheckedExtrinsic, SLOT_DURATION,
+};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_core::traits::{ReadRuntimeVersion, ReadRuntimeVersionExt};
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::{
+	generic::{self, Digest, DigestItem},
+	traits::Header as _,
+	Storage,
+};
+
+/// The accounts signing the extrinsics, endowed or not in the genesis state.
+const SIGNERS: [Sr25519Keyring; 6] = [
+	Sr25519Keyring::Alice,
+	Sr25519Keyring::Bob,
+	Sr25519Keyring::Charlie,
+	Sr25519Keyring::Dave,
+	Sr25519Keyring::Eve,
+	Sr25519Keyring::Ferdie,
+];
+
+/// A signed extrinsic of arbitrary content.
+#[derive(Clone, Debug, arbitrary::Arbitrary)]
+pub struct FuzzExtrinsic {
+	/// The signer, an index into the development accounts.
+	pub signer: u8,
+	/// The tip paid on top of the fee.
+	pub tip: u32,
+	/// The encoded call. Extrinsics whose call does not decode are skipped.
+	pub call: Vec<u8>,
+}
+
+/// A block of arbitrary extrinsics.
+#[derive(Clone, Debug, arbitrary::Arbitrary)]
+pub struct FuzzBlock {
+	/// The extrinsics of the block, after the timestamp inherent.
+	pub extrinsics: Vec<FuzzExtrinsic>,
+}
+
+/// Refuses to read the version of runtime code, so that `set_code` fails instead of panicking
+/// for the lack of an executor.
+struct NoRuntimeVersion;
+
+impl ReadRuntimeVersion for NoRuntimeVersion {
+	fn read_runtime_version(
+		&self,
+		_wasm_code: &[u8],
+		_ext: &mut dyn sp_externalities::Externalities,
+	) -> Result<Vec<u8>, String> {
+		Err("Runtime code is not executed while fuzzing".into())
+	}
+}
+
+/// Executes `blocks` on top of the `genesis` state, panicking at the first invariant broken.
+pub fn execute_blocks(genesis: &Storage, blocks: &[FuzzBlock]) {
+	let mut ext = sp_io::TestExternalities::new(genesis.clone());
+	ext.register_extension(ReadRuntimeVersionExt::new(NoRuntimeVersion));
+	ext.execute_with(|| {
+		let mut parent_hash = System::block_hash(0);
+		for (index, block) in blocks.iter().enumerate() {
+			let number = index as BlockNumber + 1;
+			parent_hash = execute_block(number, parent_hash, block).hash();
+		}
+	});
+}
+
+/// Executes `block` as the block `number`, returning its header.
+fn execute_block(number: BlockNumber, parent_hash: Hash, block: &FuzzBlock) -> Header {
+	// Aura requires the slot of every block in its digest, and the timestamp to match it.
+	let slot = Slot::from(u64::from(number));
+	let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+	Executive::initialize_block(&Header::new(
+		number,
+		Default::default(),
+		Default::default(),
+		parent_hash,
+		digest,
+	));
+
+	let now = *slot * SLOT_DURATION;
+	let timestamp = UncheckedExtrinsic::new_unsigned(TimestampCall::set { now }.into());
+	Executive::apply_extrinsic(timestamp)
+		.expect("The timestamp inherent is valid")
+		.expect("The timestamp inherent dispatches");
+	check_issuance(number);
+
+	for xt in &block.extrinsics {
+		let Ok(call) =
+			RuntimeCall::decode_with_depth_limit(sp_api::MAX_EXTRINSIC_DEPTH, &mut &xt.call[..])
+		else {
+			continue
+		};
+		let signer = SIGNERS[xt.signer as usize % SIGNERS.len()];
+		// Invalid extrinsics are rejected and failing dispatches are part of the block, both are
+		// expected outcomes of arbitrary calls.
+		let _ = Executive::apply_extrinsic(sign(call, signer, xt.tip.into()));
+		check_issuance(number);
+	}
+
+	check_limits(number);
+	AllPalletsWithSystem::try_state(number, TryStateSelect::All)
+		.unwrap_or_else(|e| panic!("try_state fails at block #{number}: {e:?}"));
+	Executive::finalize_block()
+}
+
+/// Signs `call` by `signer` at its current nonce, as an immortal transaction.
+fn sign(call: RuntimeCall, signer: Sr25519Keyring, tip: Balance) -> UncheckedExtrinsic {
+	let account = signer.to_account_id();
+	let extra: SignedExtra = (
+		frame_system::CheckNonZeroSender::new(),
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(generic::Era::Immortal),
+		frame_system::CheckNonce::from(System::account_nonce(&account)),
+		frame_system::CheckWeight::new(),
+		pallet_template::CheckTemplateRateLimit::new(),
+		pallet_sponsorship::ChargeSponsored::Unsponsored(
+			pallet_asset_tx_payment::ChargeAssetTxPayment::from(tip, None),
+		)
+		.into(),
+	);
+	let payload =
+		SignedPayload::new(call, extra).expect("The signed extensions of the runtime are valid");
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(call, account.into(), signature.into(), extra)
+}
+
+/// Checks that the total issuance is the sum of the free and reserved balances of all accounts.
+fn check_issuance(number: BlockNumber) {
+	let balances = frame_system::Account::<Runtime>::iter_values()
+		.fold(0u128, |total, account| total + account.data.free + account.data.reserved);
+	assert_eq!(
+		Balances::total_issuance(),
+		balances,
+		"The total issuance differs from the balances of the accounts at block #{number}"
+	);
+}
+
+/// Checks that the weight and the length of each dispatch class are within their limits.
+fn check_limits(number: BlockNumber) {
+	let weights = BlockWeights::get();
+	let lengths = BlockLength::get();
+	let consumed = System::block_weight();
+	let length = frame_system::AllExtrinsicsLen::<Runtime>::get().unwrap_or_default();
+	for class in [DispatchClass::Normal, DispatchClass::Operational] {
+		if let Some(max) = weights.get(class).max_total {
+			assert!(
+				consumed.get(class).all_lte(max),
+				"{class:?} weight {:?} exceeds {max:?} at block #{number}",
+				consumed.get(class)
+			);
+		}
+	}
+	assert!(
+		length <= *lengths.max.get(DispatchClass::Operational),
+		"Extrinsics length {length} exceeds the limit at block #{number}"
+	);
+}
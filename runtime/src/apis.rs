@@ -0,0 +1,55 @@
+//! Runtime APIs declared by this runtime, in addition to the ones provided by FRAME pallets.
+
+use codec::{Codec, Decode, Encode};
+use pallet_transaction_payment::RuntimeDispatchInfo;
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// The fee of a transaction, in the native balance and in the asset it pays with.
+#[derive(Eq, PartialEq, Encode, Decode, Default, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct AssetDispatchInfo<Balance, AssetId> {
+	/// The dispatch info and fee of the transaction, as returned by
+	/// `TransactionPaymentApi::query_info`.
+	pub native: RuntimeDispatchInfo<Balance>,
+	/// The asset that `ChargeAssetTxPayment` selects, `None` when paying in the native balance.
+	pub asset_id: Option<AssetId>,
+	/// `native.partial_fee` converted into `asset_id`, `None` when paying in the native balance.
+	#[cfg_attr(feature = "std", serde(with = "option_balance"))]
+	pub asset_fee: Option<Balance>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Fee queries for transactions paying through `pallet_asset_tx_payment`.
+	pub trait AssetTxPaymentApi<Balance, AssetId> where
+		Balance: Codec,
+		AssetId: Codec,
+	{
+		/// Returns the fee of `uxt`, denominated in the asset its signed extension selects.
+		fn query_asset_info(uxt: Block::Extrinsic, len: u32) -> AssetDispatchInfo<Balance, AssetId>;
+	}
+}
+
+/// Serializes balances as strings, like `RuntimeDispatchInfo` does, since JSON numbers cannot
+/// represent a `u128`.
+#[cfg(feature = "std")]
+mod option_balance {
+	use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+	pub fn serialize<S: Serializer, T: std::fmt::Display>(
+		value: &Option<T>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		value.as_ref().map(ToString::to_string).serialize(serializer)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>, T: std::str::FromStr>(
+		deserializer: D,
+	) -> Result<Option<T>, D::Error> {
+		Option::<String>::deserialize(deserializer)?
+			.map(|s| s.parse().map_err(|_| serde::de::Error::custom("Parse from string failed")))
+			.transpose()
+	}
+}
@@ -0,0 +1,228 @@
+//! Chain extension giving ink! contracts access to the template pallet.
+//!
+//! Contracts reach this extension through `seal_call_chain_extension`. The function id selects
+//! the operation:
+//!
+//! - [`GET_SOMETHING`]: returns the SCALE-encoded `Option<u32>` stored in
+//!   `pallet_template::Something`.
+//! - [`SET_SOMETHING`]: reads a SCALE-encoded `u32` from the input buffer and stores it through
+//!   `pallet_template::do_something`, with the contract's own account as the signer. Like any other
+//!   caller, the contract must pass `TemplateAccessCollection`'s NFT check, and its calls count
+//!   against the rate limit of the pallet.
+
+use crate::Runtime;
+use codec::Encode;
+use frame_support::traits::Get;
+use pallet_contracts::chain_extension::{
+	ChainExtension, Environment, Ext, InitState, Result, RetVal,
+};
+use pallet_template::WeightInfo;
+use sp_runtime::DispatchError;
+
+/// Function id that reads `pallet_template::Something`.
+pub const GET_SOMETHING: u16 = 0x0001;
+/// Function id that writes `pallet_template::Something`.
+pub const SET_SOMETHING: u16 = 0x0002;
+
+/// The chain extension registered in `pallet_contracts::Config::ChainExtension`.
+#[derive(Default)]
+pub struct TemplateExtension;
+
+impl ChainExtension<Runtime> for TemplateExtension {
+	fn call<E: Ext<T = Runtime>>(&mut self, env: Environment<E, InitState>) -> Result<RetVal> {
+		let func_id = env.func_id();
+		let mut env = env.buf_in_buf_out();
+
+		match func_id {
+			GET_SOMETHING => {
+				env.charge_weight(<Runtime as frame_system::Config>::DbWeight::get().reads(1))?;
+				let something = pallet_template::Pallet::<Runtime>::something();
+				env.write(&something.encode(), false, None)?;
+			},
+			SET_SOMETHING => {
+				let something: u32 = env.read_as()?;
+				env.charge_weight(
+					<Runtime as pallet_template::Config>::WeightInfo::do_something()
+						.saturating_add(
+							<Runtime as pallet_template::Config>::WeightInfo::check_rate_limit(),
+						),
+				)?;
+				let caller = env.ext().address().clone();
+				pallet_template::Pallet::<Runtime>::do_something(
+					frame_system::RawOrigin::Signed(caller).into(),
+					something,
+				)?;
+			},
+			_ => return Err(DispatchError::Other("TemplateExtension: unknown function id")),
+		}
+
+		Ok(RetVal::Converging(0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		AccountId, Address, Balance, BlockWeights, Contracts, Nfts, RuntimeOrigin,
+		TemplateAccessCollection, TemplateModule, UNIT,
+	};
+	use pallet_contracts::Determinism;
+	use pallet_contracts_primitives::{Code, ContractExecResult};
+	use pallet_nfts::{CollectionConfig, CollectionSettings, ItemConfig, MintSettings};
+
+	/// A contract calling the extension with the function id in the first four bytes of its
+	/// input and the rest as the input of the function, and returning the output of the
+	/// function.
+	const CONTRACT: &str = r#"
+(module
+	(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+	(import "seal0" "seal_call_chain_extension"
+		(func $seal_call_chain_extension (param i32 i32 i32 i32 i32) (result i32)))
+	(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 4) the length of the input buffer, [4, 36) the input buffer
+	(data (i32.const 0) "\20")
+	;; [40, 44) the length of the output buffer, [44, 76) the output buffer
+	(data (i32.const 40) "\20")
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		(call $seal_input (i32.const 4) (i32.const 0))
+		(drop (call $seal_call_chain_extension
+			(i32.load (i32.const 4))
+			(i32.const 8)
+			(i32.sub (i32.load (i32.const 0)) (i32.const 4))
+			(i32.const 44)
+			(i32.const 40)
+		))
+		(call $seal_return (i32.const 0) (i32.const 44) (i32.load (i32.const 40)))
+	)
+)
+"#;
+
+	fn alice() -> AccountId {
+		AccountId::new([1; 32])
+	}
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut storage =
+			frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> { balances: vec![(alice(), 1_000 * UNIT)] }
+			.assimilate_storage(&mut storage)
+			.unwrap();
+		let mut ext = sp_io::TestExternalities::new(storage);
+		ext.execute_with(|| frame_system::Pallet::<Runtime>::set_block_number(1));
+		ext
+	}
+
+	/// Deploys [`CONTRACT`] from Alice, returning its address.
+	fn instantiate() -> AccountId {
+		let wasm = wat::parse_str(CONTRACT).unwrap();
+		Contracts::bare_instantiate(
+			alice(),
+			0,
+			BlockWeights::get().max_block,
+			None,
+			Code::Upload(wasm),
+			vec![],
+			vec![],
+			false,
+		)
+		.result
+		.unwrap()
+		.account_id
+	}
+
+	/// Calls the function `func_id` of the extension with `input` through `contract`.
+	fn call(contract: &AccountId, func_id: u16, input: Vec<u8>) -> ContractExecResult<Balance> {
+		let data = (func_id as u32).to_le_bytes().into_iter().chain(input).collect();
+		Contracts::bare_call(
+			alice(),
+			contract.clone(),
+			0,
+			BlockWeights::get().max_block,
+			None,
+			data,
+			false,
+			Determinism::Enforced,
+		)
+	}
+
+	#[test]
+	fn contracts_set_and_get_something() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate();
+			let get = || call(&contract, GET_SOMETHING, vec![]).result.unwrap().data;
+			assert_eq!(get(), None::<u32>.encode());
+
+			assert!(call(&contract, SET_SOMETHING, 42u32.encode()).result.is_ok());
+			assert_eq!(TemplateModule::something(), Some(42));
+			assert_eq!(get(), Some(42u32).encode());
+		});
+	}
+
+	#[test]
+	fn contracts_pass_the_nft_check_and_pay_for_it_up_front() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate();
+			let config = CollectionConfig {
+				settings: CollectionSettings::all_enabled(),
+				max_supply: None,
+				mint_settings: MintSettings::default(),
+			};
+			Nfts::force_create(RuntimeOrigin::root(), Address::Id(alice()), config).unwrap();
+			TemplateAccessCollection::set(&Some(0));
+
+			// The weight of `do_something` is charged before dispatching it, even when the
+			// contract does not hold an item of the collection.
+			let rejected = call(&contract, SET_SOMETHING, 42u32.encode());
+			assert_eq!(rejected.result.err(), Some(DispatchError::BadOrigin));
+			assert!(rejected
+				.gas_consumed
+				.all_gte(<Runtime as pallet_template::Config>::WeightInfo::do_something()));
+			assert_eq!(TemplateModule::something(), None);
+
+			Nfts::force_mint(
+				RuntimeOrigin::root(),
+				0,
+				0,
+				Address::Id(contract.clone()),
+				ItemConfig::default(),
+			)
+			.unwrap();
+			assert!(call(&contract, SET_SOMETHING, 42u32.encode()).result.is_ok());
+			assert_eq!(TemplateModule::something(), Some(42));
+		});
+	}
+
+	#[test]
+	fn contracts_are_rate_limited_like_transactions() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate();
+			let limit = <Runtime as pallet_template::Config>::MaxCallsPerPeriod::get();
+			for something in 0..limit {
+				assert!(call(&contract, SET_SOMETHING, something.encode()).result.is_ok());
+			}
+
+			assert_eq!(
+				call(&contract, SET_SOMETHING, 42u32.encode()).result.err(),
+				Some(pallet_template::Error::<Runtime>::RateLimited.into())
+			);
+			assert_eq!(TemplateModule::something(), Some(limit - 1));
+		});
+	}
+
+	#[test]
+	fn rejects_unknown_function_ids() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate();
+			assert_eq!(
+				call(&contract, 0x0003, vec![]).result.err(),
+				Some(DispatchError::Other("TemplateExtension: unknown function id"))
+			);
+		});
+	}
+}
@@ -0,0 +1,366 @@
+//! Ethereum compatibility, compiled in with the `evm` feature.
+//!
+//! Ethereum accounts are 20-byte `H160` addresses, while the rest of the runtime uses the
+//! `MultiSignature`-derived `AccountId`. `pallet_evm` bridges the two with
+//! [`HashedAddressMapping`]: the substrate account backing an EVM address `a` is
+//! `blake2_256(b"evm:" ++ a)`. Substrate accounts may in turn act on behalf of the EVM address
+//! made of the first 20 bytes of their account id (see [`EnsureAddressTruncated`]).
+
+use crate::{
+	Aura, Balances, BlakeTwo256, Runtime, RuntimeCall, RuntimeEvent, RuntimeOrigin, Weight,
+	NORMAL_DISPATCH_RATIO, WEIGHT_REF_TIME_PER_SECOND,
+};
+use codec::{Decode, Encode};
+use fp_evm::{
+	ExitRevert, ExitSucceed, IsPrecompileResult, Precompile, PrecompileFailure, PrecompileHandle,
+	PrecompileOutput, PrecompileResult, PrecompileSet,
+};
+use frame_support::{
+	parameter_types,
+	traits::{ConstU32, ConstU64, FindAuthor, Get},
+	ConsensusEngineId,
+};
+use pallet_ethereum::PostLogContent;
+use pallet_evm::{
+	AddressMapping, EnsureAddressTruncated, FeeCalculator, GasWeightMapping, HashedAddressMapping,
+};
+use pallet_evm_precompile_simple::{ECRecover, Identity, Ripemd160, Sha256};
+use pallet_template::WeightInfo;
+use sp_core::{H160, U256};
+use sp_runtime::{
+	traits::{DispatchInfoOf, Dispatchable, PostDispatchInfoOf},
+	transaction_validity::{TransactionValidity, TransactionValidityError},
+};
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// The chain id reported to Ethereum tooling, `eth_chainId`.
+pub const EVM_CHAIN_ID: u64 = 42;
+
+/// The maximum amount of gas that a block may consume.
+pub const BLOCK_GAS_LIMIT: u64 = 75_000_000;
+
+/// The address of [`TemplatePrecompile`].
+pub const TEMPLATE_PRECOMPILE_ADDRESS: u64 = 0x0800;
+
+/// The gas price returned by [`FixedGasPrice`], in the smallest unit of the native balance.
+const MIN_GAS_PRICE: u64 = 1_000_000_000;
+
+parameter_types! {
+	pub BlockGasLimit: U256 = U256::from(BLOCK_GAS_LIMIT);
+	/// Normal extrinsics may fill `NORMAL_DISPATCH_RATIO` of the 2 second block; a block full of
+	/// EVM transactions uses `BLOCK_GAS_LIMIT` gas over that same share.
+	pub WeightPerGas: Weight = Weight::from_parts(
+		NORMAL_DISPATCH_RATIO * (2 * WEIGHT_REF_TIME_PER_SECOND) / BLOCK_GAS_LIMIT,
+		0,
+	);
+	pub PrecompilesValue: TemplatePrecompiles<Runtime> = TemplatePrecompiles::<_>::new();
+}
+
+/// A gas price that never changes, which is enough for a development chain.
+pub struct FixedGasPrice;
+
+impl FeeCalculator for FixedGasPrice {
+	fn min_gas_price() -> (U256, Weight) {
+		(MIN_GAS_PRICE.into(), Weight::zero())
+	}
+}
+
+/// Reports the Aura author of a block, truncated to an `H160`, as the EVM `coinbase`.
+pub struct FindAuthorTruncated<F>(PhantomData<F>);
+
+impl<F: FindAuthor<u32>> FindAuthor<H160> for FindAuthorTruncated<F> {
+	fn find_author<'a, I>(digests: I) -> Option<H160>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		let author_index = F::find_author(digests)?;
+		let authority_id = Aura::authorities().get(author_index as usize)?.clone();
+		Some(H160::from_slice(&authority_id.encode()[4..24]))
+	}
+}
+
+impl pallet_evm::Config for Runtime {
+	type FeeCalculator = FixedGasPrice;
+	type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+	type WeightPerGas = WeightPerGas;
+	type BlockHashMapping = pallet_ethereum::EthereumBlockHashMapping<Self>;
+	type CallOrigin = EnsureAddressTruncated;
+	type WithdrawOrigin = EnsureAddressTruncated;
+	type AddressMapping = HashedAddressMapping<BlakeTwo256>;
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type PrecompilesType = TemplatePrecompiles<Self>;
+	type PrecompilesValue = PrecompilesValue;
+	type ChainId = ConstU64<EVM_CHAIN_ID>;
+	type BlockGasLimit = BlockGasLimit;
+	type Runner = pallet_evm::runner::stack::Runner<Self>;
+	type OnChargeTransaction = ();
+	type OnCreate = ();
+	type FindAuthor = FindAuthorTruncated<Aura>;
+}
+
+parameter_types! {
+	pub const PostBlockAndTxnHashes: PostLogContent = PostLogContent::BlockAndTxnHashes;
+}
+
+impl pallet_ethereum::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type StateRoot = pallet_ethereum::IntermediateStateRoot<Self>;
+	type PostLogContent = PostBlockAndTxnHashes;
+	type ExtraDataLength = ConstU32<30>;
+}
+
+impl fp_self_contained::SelfContainedCall for RuntimeCall {
+	type SignedInfo = H160;
+
+	fn is_self_contained(&self) -> bool {
+		match self {
+			RuntimeCall::Ethereum(call) => call.is_self_contained(),
+			_ => false,
+		}
+	}
+
+	fn check_self_contained(&self) -> Option<Result<Self::SignedInfo, TransactionValidityError>> {
+		match self {
+			RuntimeCall::Ethereum(call) => call.check_self_contained(),
+			_ => None,
+		}
+	}
+
+	fn validate_self_contained(
+		&self,
+		info: &Self::SignedInfo,
+		dispatch_info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> Option<TransactionValidity> {
+		match self {
+			RuntimeCall::Ethereum(call) => call.validate_self_contained(info, dispatch_info, len),
+			_ => None,
+		}
+	}
+
+	fn pre_dispatch_self_contained(
+		&self,
+		info: &Self::SignedInfo,
+		dispatch_info: &DispatchInfoOf<RuntimeCall>,
+		len: usize,
+	) -> Option<Result<(), TransactionValidityError>> {
+		match self {
+			RuntimeCall::Ethereum(call) =>
+				call.pre_dispatch_self_contained(info, dispatch_info, len),
+			_ => None,
+		}
+	}
+
+	fn apply_self_contained(
+		self,
+		info: Self::SignedInfo,
+	) -> Option<sp_runtime::DispatchResultWithInfo<PostDispatchInfoOf<Self>>> {
+		match self {
+			call @ RuntimeCall::Ethereum(pallet_ethereum::Call::transact { .. }) =>
+				Some(call.dispatch(RuntimeOrigin::from(
+					pallet_ethereum::RawOrigin::EthereumTransaction(info),
+				))),
+			_ => None,
+		}
+	}
+}
+
+/// The precompiles available to EVM contracts: the standard Ethereum ones that need no extra
+/// host functions, followed by the project-specific ones.
+pub struct TemplatePrecompiles<R>(PhantomData<R>);
+
+impl<R> Default for TemplatePrecompiles<R> {
+	fn default() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<R> TemplatePrecompiles<R> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn used_addresses() -> [H160; 5] {
+		[hash(1), hash(2), hash(3), hash(4), hash(TEMPLATE_PRECOMPILE_ADDRESS)]
+	}
+}
+
+impl<R> PrecompileSet for TemplatePrecompiles<R>
+where
+	R: pallet_evm::Config + pallet_template::Config,
+{
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		match handle.code_address() {
+			a if a == hash(1) => Some(ECRecover::execute(handle)),
+			a if a == hash(2) => Some(Sha256::execute(handle)),
+			a if a == hash(3) => Some(Ripemd160::execute(handle)),
+			a if a == hash(4) => Some(Identity::execute(handle)),
+			a if a == hash(TEMPLATE_PRECOMPILE_ADDRESS) =>
+				Some(TemplatePrecompile::<R>::execute(handle)),
+			_ => None,
+		}
+	}
+
+	fn is_precompile(&self, address: H160, _gas: u64) -> IsPrecompileResult {
+		IsPrecompileResult::Answer {
+			is_precompile: Self::used_addresses().contains(&address),
+			extra_cost: 0,
+		}
+	}
+}
+
+/// Converts Ethereum transactions submitted through `eth_sendRawTransaction` into extrinsics.
+#[derive(Clone)]
+pub struct TransactionConverter;
+
+impl fp_rpc::ConvertTransaction<crate::UncheckedExtrinsic> for TransactionConverter {
+	fn convert_transaction(
+		&self,
+		transaction: pallet_ethereum::Transaction,
+	) -> crate::UncheckedExtrinsic {
+		crate::UncheckedExtrinsic::new_unsigned(
+			pallet_ethereum::Call::<Runtime>::transact { transaction }.into(),
+		)
+	}
+}
+
+impl fp_rpc::ConvertTransaction<crate::opaque::UncheckedExtrinsic> for TransactionConverter {
+	fn convert_transaction(
+		&self,
+		transaction: pallet_ethereum::Transaction,
+	) -> crate::opaque::UncheckedExtrinsic {
+		let extrinsic = crate::UncheckedExtrinsic::new_unsigned(
+			pallet_ethereum::Call::<Runtime>::transact { transaction }.into(),
+		);
+		let encoded = extrinsic.encode();
+		crate::opaque::UncheckedExtrinsic::decode(&mut &encoded[..])
+			.expect("Encoded extrinsic is always valid")
+	}
+}
+
+fn hash(a: u64) -> H160 {
+	H160::from_low_u64_be(a)
+}
+
+/// Exposes `pallet_template` to EVM contracts with the following Solidity interface:
+///
+/// ```solidity
+/// interface Template {
+///     function something() external view returns (uint32);
+///     function doSomething(uint32 something) external;
+/// }
+/// ```
+///
+/// `doSomething` is dispatched with the substrate account that `AddressMapping` derives from the
+/// caller's address as the signer, and counts against the rate limit of that account.
+pub struct TemplatePrecompile<R>(PhantomData<R>);
+
+/// `bytes4(keccak256("something()"))`
+const SELECTOR_SOMETHING: [u8; 4] = [0xa7, 0xa0, 0xd5, 0x37];
+/// `bytes4(keccak256("doSomething(uint32)"))`
+const SELECTOR_DO_SOMETHING: [u8; 4] = [0x3e, 0x2a, 0xef, 0x0a];
+
+impl<R> Precompile for TemplatePrecompile<R>
+where
+	R: pallet_evm::Config + pallet_template::Config,
+{
+	fn execute(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+		let input = handle.input().to_vec();
+		if input.len() < 4 {
+			return Err(revert("input too short"))
+		}
+		let (selector, arguments) = input.split_at(4);
+
+		match selector {
+			s if s == SELECTOR_SOMETHING => {
+				handle.record_cost(R::GasWeightMapping::weight_to_gas(
+					<R as frame_system::Config>::DbWeight::get().reads(1),
+				))?;
+				let something = pallet_template::Pallet::<R>::something().unwrap_or_default();
+
+				Ok(PrecompileOutput {
+					exit_status: ExitSucceed::Returned,
+					output: encode_u32(something),
+				})
+			},
+			s if s == SELECTOR_DO_SOMETHING => {
+				if handle.is_static() {
+					return Err(revert("cannot modify state in a static call"))
+				}
+				let something = decode_u32(arguments)?;
+				handle.record_cost(R::GasWeightMapping::weight_to_gas(
+					<R as pallet_template::Config>::WeightInfo::do_something().saturating_add(
+						<R as pallet_template::Config>::WeightInfo::check_rate_limit(),
+					),
+				))?;
+
+				let who = R::AddressMapping::into_account_id(handle.context().caller);
+				pallet_template::Pallet::<R>::do_something(
+					frame_system::RawOrigin::Signed(who).into(),
+					something,
+				)
+				.map_err(|e| revert(<&'static str>::from(e)))?;
+
+				Ok(PrecompileOutput { exit_status: ExitSucceed::Stopped, output: Vec::new() })
+			},
+			_ => Err(revert("unknown selector")),
+		}
+	}
+}
+
+/// ABI-encodes a `uint32` as a single 32-byte word.
+fn encode_u32(value: u32) -> Vec<u8> {
+	let mut word = [0u8; 32];
+	word[28..].copy_from_slice(&value.to_be_bytes());
+	word.to_vec()
+}
+
+/// ABI-decodes a `uint32` from the first 32-byte word of `arguments`.
+fn decode_u32(arguments: &[u8]) -> Result<u32, PrecompileFailure> {
+	let word = arguments.get(..32).ok_or_else(|| revert("missing argument"))?;
+	if word[..28].iter().any(|b| *b != 0) {
+		return Err(revert("argument out of bounds"))
+	}
+	let mut bytes = [0u8; 4];
+	bytes.copy_from_slice(&word[28..]);
+	Ok(u32::from_be_bytes(bytes))
+}
+
+fn revert(message: &str) -> PrecompileFailure {
+	PrecompileFailure::Revert {
+		exit_status: ExitRevert::Reverted,
+		output: message.as_bytes().to_vec(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Perbill;
+
+	#[test]
+	fn u32_abi_round_trip() {
+		let encoded = encode_u32(0xdead_beef);
+		assert_eq!(encoded.len(), 32);
+		assert_eq!(decode_u32(&encoded).ok(), Some(0xdead_beef));
+	}
+
+	#[test]
+	fn decode_u32_rejects_wide_values() {
+		let mut word = encode_u32(1);
+		word[27] = 1;
+		assert!(decode_u32(&word).is_err());
+		assert!(decode_u32(&word[..31]).is_err());
+	}
+
+	#[test]
+	fn weight_per_gas_fills_normal_dispatch_share() {
+		let normal = NORMAL_DISPATCH_RATIO * (2 * WEIGHT_REF_TIME_PER_SECOND);
+		let per_gas = WeightPerGas::get().ref_time();
+		assert!(per_gas * BLOCK_GAS_LIMIT <= normal);
+		assert!(
+			Perbill::from_rational(per_gas * BLOCK_GAS_LIMIT, normal) > Perbill::from_percent(99)
+		);
+	}
+}
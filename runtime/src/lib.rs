@@ -6,28 +6,49 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+pub mod apis;
+mod chain_extension;
+#[cfg(feature = "evm")]
+mod evm;
+
+use codec::{Decode, Encode};
+use frame_support::traits::{
+	tokens::{nonfungibles_v2::InspectEnumerable, ConversionToAssetBalance},
+	EnsureOrigin, Get,
+};
 use pallet_grandpa::AuthorityId as GrandpaId;
+use pallet_nfts::PalletFeatures;
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{
-		AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, One, Verify,
+		AccountIdLookup, Block as BlockT, ConvertInto, IdentifyAccount, NumberFor, One, Verify,
 	},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
+#[cfg(feature = "evm")]
+use {
+	frame_support::traits::OnFinalize,
+	pallet_ethereum::{Call::transact, Transaction as EthereumTransaction, TransactionStatus},
+	pallet_evm::{Account as EVMAccount, FeeCalculator, Runner},
+	sp_core::{H160, H256, U256},
+	sp_runtime::traits::UniqueSaturatedInto,
+};
 
 // A few exports that help ease life for downstream crates.
+use frame_support::traits::AsEnsureOriginWithArg;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo,
+		ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, Contains, KeyOwnerProofSystem,
+		Randomness, StorageInfo,
 	},
 	weights::{
 		constants::{
@@ -38,16 +59,26 @@ pub use frame_support::{
 	StorageValue,
 };
 pub use frame_system::Call as SystemCall;
+use frame_system::{EnsureRoot, EnsureSigned};
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{ConstFeeMultiplier, CurrencyAdapter, Multiplier};
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{traits::BlakeTwo256, Perbill, Permill};
 
+/// Import the sponsorship pallet, whose extension transactions are built with.
+pub use pallet_sponsorship;
 /// Import the template pallet.
 pub use pallet_template;
 
+#[cfg(feature = "evm")]
+pub use evm::{
+	TemplatePrecompile, TransactionConverter, EVM_CHAIN_ID, TEMPLATE_PRECOMPILE_ADDRESS,
+};
+#[cfg(feature = "evm")]
+pub use pallet_evm::GenesisAccount as EVMGenesisAccount;
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -64,6 +95,15 @@ pub type Balance = u128;
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
+/// Identifier of a fungible asset of `pallet_assets`.
+pub type AssetId = u32;
+
+/// Identifier of an NFT collection.
+pub type CollectionId = u32;
+
+/// Identifier of an NFT, unique within its collection.
+pub type ItemId = u32;
+
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
@@ -101,12 +141,13 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// The version of the runtime specification. A full node will not attempt to use its native
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
-	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
-	//   the compatible custom types.
-	spec_version: 100,
+	// This value started at 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
+	//   the compatible custom types, and is bumped for every upgrade of the runtime.
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	// Bumped whenever the encoding of transactions changes, e.g. their signed extensions.
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -231,6 +272,15 @@ impl pallet_timestamp::Config for Runtime {
 /// Existential deposit.
 pub const EXISTENTIAL_DEPOSIT: u128 = 500;
 
+// Currency units, used to express deposits.
+pub const UNIT: Balance = 1_000_000_000_000;
+pub const MILLIUNIT: Balance = UNIT / 1_000;
+
+/// The deposit required to store `items` storage items occupying `bytes` bytes in total.
+pub const fn deposit(items: u32, bytes: u32) -> Balance {
+	items as Balance * 15 * MILLIUNIT + (bytes as Balance) * 6 * MILLIUNIT / 100
+}
+
 impl pallet_balances::Config for Runtime {
 	type MaxLocks = ConstU32<50>;
 	type MaxReserves = ();
@@ -262,15 +312,252 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ConstFeeMultiplier<FeeMultiplier>;
 }
 
+parameter_types! {
+	pub const AssetDeposit: Balance = 10 * UNIT;
+	pub const AssetAccountDeposit: Balance = deposit(1, 16);
+	pub const MetadataDepositBase: Balance = deposit(1, 68);
+	pub const MetadataDepositPerByte: Balance = deposit(0, 1);
+	pub const ApprovalDeposit: Balance = EXISTENTIAL_DEPOSIT;
+}
+
+impl pallet_assets::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type AssetIdParameter = codec::Compact<AssetId>;
+	type Currency = Balances;
+	/// Anyone may create an asset by reserving `AssetDeposit`.
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type AssetDeposit = AssetDeposit;
+	type AssetAccountDeposit = AssetAccountDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = ConstU32<50>;
+	type Freezer = ();
+	type Extra = ();
+	type CallbackHandle = ();
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+	type RemoveItemsLimit = ConstU32<1000>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
+}
+
+impl pallet_asset_tx_payment::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Fungibles = Assets;
+	/// Fees paid in an asset are converted from the native fee using the ratio between the asset's
+	/// minimum balance and the existential deposit. Like native fees, they are burned.
+	type OnChargeAssetTransaction = pallet_asset_tx_payment::FungiblesAdapter<
+		pallet_assets::BalanceToAssetBalance<Balances, Runtime, ConvertInto>,
+		(),
+	>;
+}
+
+parameter_types! {
+	pub const CollectionDeposit: Balance = 10 * UNIT;
+	pub const ItemDeposit: Balance = deposit(1, 0);
+	pub const MaxDeadlineDuration: BlockNumber = 365 * DAYS;
+	pub Features: PalletFeatures = PalletFeatures::all_enabled();
+}
+
+impl pallet_nfts::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type CollectionId = CollectionId;
+	type ItemId = ItemId;
+	type Currency = Balances;
+	/// Anyone may create a collection by reserving `CollectionDeposit`.
+	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	type ForceOrigin = EnsureRoot<AccountId>;
+	type Locker = ();
+	type CollectionDeposit = CollectionDeposit;
+	type ItemDeposit = ItemDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type AttributeDepositBase = MetadataDepositBase;
+	type DepositPerByte = MetadataDepositPerByte;
+	type StringLimit = ConstU32<256>;
+	type KeyLimit = ConstU32<64>;
+	type ValueLimit = ConstU32<256>;
+	type ApprovalsLimit = ConstU32<20>;
+	type ItemAttributesApprovalsLimit = ConstU32<20>;
+	type MaxTips = ConstU32<10>;
+	type MaxDeadlineDuration = MaxDeadlineDuration;
+	type MaxAttributesPerCall = ConstU32<10>;
+	type Features = Features;
+	type OffchainSignature = Signature;
+	type OffchainPublic = <Signature as Verify>::Signer;
+	type WeightInfo = pallet_nfts::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type Helper = ();
+}
+
+impl pallet_insecure_randomness_collective_flip::Config for Runtime {}
+
+/// The dispatchables that contracts are allowed to call through `seal_call_runtime`.
+///
+/// Calls exposed to contracts must stay stable: changing them, or the index of their pallet in
+/// `construct_runtime!`, breaks contracts that are already deployed.
+pub struct ContractsCallFilter;
+
+impl Contains<RuntimeCall> for ContractsCallFilter {
+	fn contains(call: &RuntimeCall) -> bool {
+		matches!(
+			call,
+			RuntimeCall::Balances(
+				pallet_balances::Call::transfer { .. } |
+					pallet_balances::Call::transfer_keep_alive { .. }
+			) | RuntimeCall::TemplateModule(pallet_template::Call::do_something { .. })
+		)
+	}
+}
+
+/// Whether contract dry-runs through `ContractsApi` collect the output of `seal_debug_message`.
+const CONTRACTS_DEBUG_OUTPUT: bool = true;
+
+parameter_types! {
+	pub const DepositPerItem: Balance = deposit(1, 0);
+	pub const DepositPerByte: Balance = deposit(0, 1);
+	pub const DefaultDepositLimit: Balance = deposit(1024, 1024 * 1024);
+	/// The default schedule is calibrated against the reference hardware used for the weights of
+	/// this runtime.
+	pub Schedule: pallet_contracts::Schedule<Runtime> = Default::default();
+}
+
+impl pallet_contracts::Config for Runtime {
+	type Time = Timestamp;
+	type Randomness = RandomnessCollectiveFlip;
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type CallFilter = ContractsCallFilter;
+	type DepositPerItem = DepositPerItem;
+	type DepositPerByte = DepositPerByte;
+	type DefaultDepositLimit = DefaultDepositLimit;
+	type CallStack = [pallet_contracts::Frame<Self>; 5];
+	type WeightPrice = pallet_transaction_payment::Pallet<Self>;
+	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
+	type ChainExtension = chain_extension::TemplateExtension;
+	type Schedule = Schedule;
+	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+	type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+	type MaxStorageKeyLen = ConstU32<128>;
+	type UnsafeUnstableInterface = ConstBool<false>;
+	type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
+}
+
 impl pallet_sudo::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type RuntimeCall = RuntimeCall;
 }
 
+parameter_types! {
+	/// The NFT collection whose holders may call `TemplateModule::do_something`, any signed
+	/// account may call it when `None`.
+	///
+	/// Collections are created on chain, so their ids are only known once created: Root, i.e.
+	/// sudo or governance, sets the collection with `System::set_storage`, writing the
+	/// SCALE-encoded `Option<CollectionId>` under `TemplateAccessCollection::key()`, the
+	/// `twox_128` hash of `:TemplateAccessCollection:`.
+	pub storage TemplateAccessCollection: Option<CollectionId> = None;
+}
+
+/// Ensures that the origin is signed by an account holding at least one item of the
+/// [`TemplateAccessCollection`] NFT collection. Any signed origin passes while it is `None`.
+pub struct EnsureNftHolder;
+
+impl EnsureOrigin<RuntimeOrigin> for EnsureNftHolder {
+	type Success = AccountId;
+
+	fn try_origin(o: RuntimeOrigin) -> Result<Self::Success, RuntimeOrigin> {
+		let who = EnsureSigned::<AccountId>::try_origin(o)?;
+		match TemplateAccessCollection::get() {
+			Some(collection)
+				if <Nfts as InspectEnumerable<AccountId>>::owned_in_collection(
+					&collection,
+					&who,
+				)
+				.next()
+				.is_none() =>
+				Err(RuntimeOrigin::signed(who)),
+			_ => Ok(who),
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+		use frame_support::traits::{tokens::nonfungibles_v2::Create, Currency};
+		use pallet_nfts::{CollectionConfig, CollectionSettings, ItemConfig, MintSettings};
+
+		// An access collection is configured and the origin holds one of its items, so that the
+		// check reads the NFT storage as it does on chain.
+		let holder = AccountId::new([0; 32]);
+		Balances::make_free_balance_be(&holder, 100 * UNIT);
+		let config = CollectionConfig {
+			settings: CollectionSettings::all_enabled(),
+			max_supply: None,
+			mint_settings: MintSettings::default(),
+		};
+		let collection =
+			<Nfts as Create<AccountId, _>>::create_collection(&holder, &holder, &config)
+				.map_err(|_| ())?;
+		Nfts::force_mint(
+			RuntimeOrigin::root(),
+			collection,
+			0,
+			Address::Id(holder.clone()),
+			ItemConfig::default(),
+		)
+		.map_err(|_| ())?;
+		TemplateAccessCollection::set(&Some(collection));
+		Ok(RuntimeOrigin::signed(holder))
+	}
+}
+
+parameter_types! {
+	// About the priority a tip of 1 MILLIUNIT gives to a transaction that fits a thousand times in
+	// a block.
+	pub const TemplatePriorityBoost: TransactionPriority = 1_000_000_000_000;
+	pub const TemplateRatePeriod: BlockNumber = MINUTES;
+	pub const TemplateMaxCallsPerPeriod: u32 = 5;
+}
+
 /// Configure the pallet-template in pallets/template.
 impl pallet_template::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type WeightInfo = pallet_template::weights::SubstrateWeight<Runtime>;
+	type Randomness = RandomnessCollectiveFlip;
+	type DoSomethingOrigin = EnsureNftHolder;
+	type PriorityBoost = TemplatePriorityBoost;
+	type RatePeriod = TemplateRatePeriod;
+	type MaxCallsPerPeriod = TemplateMaxCallsPerPeriod;
+}
+
+parameter_types! {
+	pub const FeelessPeriod: BlockNumber = HOURS;
+	pub const FeelessPerAccount: u32 = 10;
+	pub const FeelessPerPeriod: u32 = 1_000;
+}
+
+impl pallet_feeless::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = pallet_feeless::weights::SubstrateWeight<Runtime>;
+	type ManagerOrigin = EnsureRoot<AccountId>;
+	type Period = FeelessPeriod;
+	type MaxPerAccount = FeelessPerAccount;
+	type MaxPerPeriod = FeelessPerPeriod;
+}
+
+parameter_types! {
+	// The sponsor, the user, the budget and the deposit itself.
+	pub const SponsorshipBudgetDeposit: Balance = deposit(1, 128);
+}
+
+impl pallet_sponsorship::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = pallet_sponsorship::weights::SubstrateWeight<Runtime>;
+	type Currency = Balances;
+	type BudgetDeposit = SponsorshipBudgetDeposit;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -281,15 +568,28 @@ construct_runtime!(
 		NodeBlock = opaque::Block,
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
-		System: frame_system,
-		Timestamp: pallet_timestamp,
-		Aura: pallet_aura,
-		Grandpa: pallet_grandpa,
-		Balances: pallet_balances,
-		TransactionPayment: pallet_transaction_payment,
-		Sudo: pallet_sudo,
+		// The indices are pinned: calls encode the index of their pallet, so appending a pallet
+		// must not move the others, whichever features the runtime is built with.
+		System: frame_system = 0,
+		Timestamp: pallet_timestamp = 1,
+		Aura: pallet_aura = 2,
+		Grandpa: pallet_grandpa = 3,
+		Balances: pallet_balances = 4,
+		TransactionPayment: pallet_transaction_payment = 5,
+		Sudo: pallet_sudo = 6,
 		// Include the custom logic from the pallet-template in the runtime.
-		TemplateModule: pallet_template,
+		TemplateModule: pallet_template = 7,
+		RandomnessCollectiveFlip: pallet_insecure_randomness_collective_flip = 8,
+		Contracts: pallet_contracts = 9,
+		#[cfg(feature = "evm")]
+		EVM: pallet_evm = 10,
+		#[cfg(feature = "evm")]
+		Ethereum: pallet_ethereum = 11,
+		Assets: pallet_assets = 12,
+		AssetTxPayment: pallet_asset_tx_payment = 13,
+		Nfts: pallet_nfts = 14,
+		Feeless: pallet_feeless = 15,
+		Sponsorship: pallet_sponsorship = 16,
 	}
 );
 
@@ -308,12 +608,25 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_template::CheckTemplateRateLimit<Runtime>,
+	pallet_feeless::SkipFeeless<
+		Runtime,
+		pallet_sponsorship::ChargeSponsored<
+			Runtime,
+			pallet_asset_tx_payment::ChargeAssetTxPayment<Runtime>,
+		>,
+	>,
 );
 
 /// Unchecked extrinsic type as expected by this runtime.
+#[cfg(not(feature = "evm"))]
 pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
+/// Unchecked extrinsic type as expected by this runtime. Ethereum transactions are submitted as
+/// unsigned extrinsics that carry their own signature.
+#[cfg(feature = "evm")]
+pub type UncheckedExtrinsic =
+	fp_self_contained::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<RuntimeCall, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
@@ -325,6 +638,60 @@ pub type Executive = frame_executive::Executive<
 	AllPalletsWithSystem,
 >;
 
+/// The asset that the `ChargeAssetTxPayment` extension of `uxt` selects to pay fees with.
+fn fee_asset_id(uxt: &UncheckedExtrinsic) -> Option<AssetId> {
+	#[cfg(not(feature = "evm"))]
+	let signature = &uxt.signature;
+	#[cfg(feature = "evm")]
+	let signature = &uxt.0.signature;
+
+	// Sponsors pay in the native balance: sponsored transactions select no asset.
+	let (_, _, extra) = signature.as_ref()?;
+	let charge = extra.8 .0.charge()?;
+	// The fields of `ChargeAssetTxPayment` are private, but its encoding is stable: the compact
+	// tip followed by the optional asset id.
+	let (_tip, asset_id) =
+		<(codec::Compact<Balance>, Option<AssetId>)>::decode(&mut &charge.encode()[..]).ok()?;
+	asset_id
+}
+
+/// Whether `pallet_feeless` exempts `uxt` from fees, for the fee queries of the runtime APIs.
+fn is_feeless(uxt: &UncheckedExtrinsic) -> bool {
+	#[cfg(not(feature = "evm"))]
+	let (signature, call) = (&uxt.signature, &uxt.function);
+	#[cfg(feature = "evm")]
+	let (signature, call) = (&uxt.0.signature, &uxt.0.function);
+
+	match signature {
+		Some((Address::Id(who), _, _)) => Feeless::exemption(who, call).is_some(),
+		_ => false,
+	}
+}
+
+/// The dispatch info of `uxt`, with the fee `pallet_feeless` exempts it from waived.
+fn query_info(
+	uxt: UncheckedExtrinsic,
+	len: u32,
+) -> pallet_transaction_payment::RuntimeDispatchInfo<Balance> {
+	let feeless = is_feeless(&uxt);
+	let mut info = TransactionPayment::query_info(uxt, len);
+	if feeless {
+		info.partial_fee = 0;
+	}
+	info
+}
+
+/// The fee details of `uxt`, without fee when `pallet_feeless` exempts it.
+fn query_fee_details(
+	uxt: UncheckedExtrinsic,
+	len: u32,
+) -> pallet_transaction_payment::FeeDetails<Balance> {
+	match is_feeless(&uxt) {
+		true => pallet_transaction_payment::FeeDetails { inclusion_fee: None, tip: 0 },
+		false => TransactionPayment::query_fee_details(uxt, len),
+	}
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -334,7 +701,12 @@ mod benches {
 	define_benchmarks!(
 		[frame_benchmarking, BaselineBench::<Runtime>]
 		[frame_system, SystemBench::<Runtime>]
+		[pallet_assets, Assets]
+		[pallet_nfts, Nfts]
 		[pallet_balances, Balances]
+		[pallet_contracts, Contracts]
+		[pallet_feeless, Feeless]
+		[pallet_sponsorship, Sponsorship]
 		[pallet_timestamp, Timestamp]
 		[pallet_template, TemplateModule]
 	);
@@ -469,13 +841,13 @@ impl_runtime_apis! {
 			uxt: <Block as BlockT>::Extrinsic,
 			len: u32,
 		) -> pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo<Balance> {
-			TransactionPayment::query_info(uxt, len)
+			query_info(uxt, len)
 		}
 		fn query_fee_details(
 			uxt: <Block as BlockT>::Extrinsic,
 			len: u32,
 		) -> pallet_transaction_payment::FeeDetails<Balance> {
-			TransactionPayment::query_fee_details(uxt, len)
+			query_fee_details(uxt, len)
 		}
 		fn query_weight_to_fee(weight: Weight) -> Balance {
 			TransactionPayment::weight_to_fee(weight)
@@ -492,13 +864,22 @@ impl_runtime_apis! {
 			call: RuntimeCall,
 			len: u32,
 		) -> pallet_transaction_payment::RuntimeDispatchInfo<Balance> {
-			TransactionPayment::query_call_info(call, len)
+			// Without a signer, only the calls that are feeless for every account are reflected.
+			let feeless = Feeless::is_feeless_call(&call);
+			let mut info = TransactionPayment::query_call_info(call, len);
+			if feeless {
+				info.partial_fee = 0;
+			}
+			info
 		}
 		fn query_call_fee_details(
 			call: RuntimeCall,
 			len: u32,
 		) -> pallet_transaction_payment::FeeDetails<Balance> {
-			TransactionPayment::query_call_fee_details(call, len)
+			match Feeless::is_feeless_call(&call) {
+				true => pallet_transaction_payment::FeeDetails { inclusion_fee: None, tip: 0 },
+				false => TransactionPayment::query_call_fee_details(call, len),
+			}
 		}
 		fn query_weight_to_fee(weight: Weight) -> Balance {
 			TransactionPayment::weight_to_fee(weight)
@@ -508,6 +889,255 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl apis::AssetTxPaymentApi<Block, Balance, AssetId> for Runtime {
+		fn query_asset_info(
+			uxt: <Block as BlockT>::Extrinsic,
+			len: u32,
+		) -> apis::AssetDispatchInfo<Balance, AssetId> {
+			let asset_id = fee_asset_id(&uxt);
+			let native = query_info(uxt, len);
+			let asset_fee = asset_id.and_then(|asset_id| {
+				pallet_assets::BalanceToAssetBalance::<Balances, Runtime, ConvertInto>::to_asset_balance(
+					native.partial_fee,
+					asset_id,
+				)
+				.ok()
+			});
+
+			apis::AssetDispatchInfo { native, asset_id, asset_fee }
+		}
+	}
+
+	impl pallet_contracts::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash> for Runtime {
+		fn call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> pallet_contracts_primitives::ContractExecResult<Balance> {
+			let gas_limit = gas_limit.unwrap_or(BlockWeights::get().max_block);
+			Contracts::bare_call(
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data,
+				CONTRACTS_DEBUG_OUTPUT,
+				pallet_contracts::Determinism::Enforced,
+			)
+		}
+
+		fn instantiate(
+			origin: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			code: pallet_contracts_primitives::Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+		) -> pallet_contracts_primitives::ContractInstantiateResult<AccountId, Balance> {
+			let gas_limit = gas_limit.unwrap_or(BlockWeights::get().max_block);
+			Contracts::bare_instantiate(
+				origin,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				code,
+				data,
+				salt,
+				CONTRACTS_DEBUG_OUTPUT,
+			)
+		}
+
+		fn upload_code(
+			origin: AccountId,
+			code: Vec<u8>,
+			storage_deposit_limit: Option<Balance>,
+			determinism: pallet_contracts::Determinism,
+		) -> pallet_contracts_primitives::CodeUploadResult<Hash, Balance> {
+			Contracts::bare_upload_code(origin, code, storage_deposit_limit, determinism)
+		}
+
+		fn get_storage(
+			address: AccountId,
+			key: Vec<u8>,
+		) -> pallet_contracts_primitives::GetStorageResult {
+			Contracts::get_storage(address, key)
+		}
+	}
+
+	#[cfg(feature = "evm")]
+	impl fp_rpc::EthereumRuntimeRPCApi<Block> for Runtime {
+		fn chain_id() -> u64 {
+			<Runtime as pallet_evm::Config>::ChainId::get()
+		}
+
+		fn account_basic(address: H160) -> EVMAccount {
+			let (account, _) = pallet_evm::Pallet::<Runtime>::account_basic(&address);
+			account
+		}
+
+		fn gas_price() -> U256 {
+			let (gas_price, _) = <Runtime as pallet_evm::Config>::FeeCalculator::min_gas_price();
+			gas_price
+		}
+
+		fn account_code_at(address: H160) -> Vec<u8> {
+			pallet_evm::AccountCodes::<Runtime>::get(address)
+		}
+
+		fn author() -> H160 {
+			<pallet_evm::Pallet<Runtime>>::find_author()
+		}
+
+		fn storage_at(address: H160, index: U256) -> H256 {
+			let mut tmp = [0u8; 32];
+			index.to_big_endian(&mut tmp);
+			pallet_evm::AccountStorages::<Runtime>::get(address, H256::from_slice(&tmp[..]))
+		}
+
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			max_fee_per_gas: Option<U256>,
+			max_priority_fee_per_gas: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+			access_list: Option<Vec<(H160, Vec<H256>)>>,
+		) -> Result<pallet_evm::CallInfo, sp_runtime::DispatchError> {
+			let config = if estimate {
+				let mut config = <Runtime as pallet_evm::Config>::config().clone();
+				config.estimate = true;
+				Some(config)
+			} else {
+				None
+			};
+
+			<Runtime as pallet_evm::Config>::Runner::call(
+				from,
+				to,
+				data,
+				value,
+				gas_limit.unique_saturated_into(),
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				access_list.unwrap_or_default(),
+				false,
+				true,
+				config.as_ref().unwrap_or(<Runtime as pallet_evm::Config>::config()),
+			)
+			.map_err(|err| err.error.into())
+		}
+
+		fn create(
+			from: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			max_fee_per_gas: Option<U256>,
+			max_priority_fee_per_gas: Option<U256>,
+			nonce: Option<U256>,
+			estimate: bool,
+			access_list: Option<Vec<(H160, Vec<H256>)>>,
+		) -> Result<pallet_evm::CreateInfo, sp_runtime::DispatchError> {
+			let config = if estimate {
+				let mut config = <Runtime as pallet_evm::Config>::config().clone();
+				config.estimate = true;
+				Some(config)
+			} else {
+				None
+			};
+
+			<Runtime as pallet_evm::Config>::Runner::create(
+				from,
+				data,
+				value,
+				gas_limit.unique_saturated_into(),
+				max_fee_per_gas,
+				max_priority_fee_per_gas,
+				nonce,
+				access_list.unwrap_or_default(),
+				false,
+				true,
+				config.as_ref().unwrap_or(<Runtime as pallet_evm::Config>::config()),
+			)
+			.map_err(|err| err.error.into())
+		}
+
+		fn current_transaction_statuses() -> Option<Vec<TransactionStatus>> {
+			pallet_ethereum::CurrentTransactionStatuses::<Runtime>::get()
+		}
+
+		fn current_block() -> Option<pallet_ethereum::Block> {
+			pallet_ethereum::CurrentBlock::<Runtime>::get()
+		}
+
+		fn current_receipts() -> Option<Vec<pallet_ethereum::Receipt>> {
+			pallet_ethereum::CurrentReceipts::<Runtime>::get()
+		}
+
+		fn current_all() -> (
+			Option<pallet_ethereum::Block>,
+			Option<Vec<pallet_ethereum::Receipt>>,
+			Option<Vec<TransactionStatus>>,
+		) {
+			(
+				pallet_ethereum::CurrentBlock::<Runtime>::get(),
+				pallet_ethereum::CurrentReceipts::<Runtime>::get(),
+				pallet_ethereum::CurrentTransactionStatuses::<Runtime>::get(),
+			)
+		}
+
+		fn extrinsic_filter(
+			xts: Vec<<Block as BlockT>::Extrinsic>,
+		) -> Vec<EthereumTransaction> {
+			xts.into_iter()
+				.filter_map(|xt| match xt.0.function {
+					RuntimeCall::Ethereum(transact { transaction }) => Some(transaction),
+					_ => None,
+				})
+				.collect::<Vec<EthereumTransaction>>()
+		}
+
+		fn elasticity() -> Option<Permill> {
+			// The gas price is fixed, see `evm::FixedGasPrice`.
+			None
+		}
+
+		fn gas_limit_multiplier_support() {}
+
+		fn pending_block(
+			xts: Vec<<Block as BlockT>::Extrinsic>,
+		) -> (Option<pallet_ethereum::Block>, Option<Vec<TransactionStatus>>) {
+			for ext in xts.into_iter() {
+				let _ = Executive::apply_extrinsic(ext);
+			}
+
+			Ethereum::on_finalize(System::block_number() + 1);
+
+			(
+				pallet_ethereum::CurrentBlock::<Runtime>::get(),
+				pallet_ethereum::CurrentTransactionStatuses::<Runtime>::get(),
+			)
+		}
+	}
+
+	#[cfg(feature = "evm")]
+	impl fp_rpc::ConvertTransactionRuntimeApi<Block> for Runtime {
+		fn convert_transaction(transaction: EthereumTransaction) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_unsigned(
+				pallet_ethereum::Call::<Runtime>::transact { transaction }.into(),
+			)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
@@ -579,6 +1209,94 @@ mod tests {
 	use sp_core::hexdisplay::HexDisplay;
 	use std::collections::HashSet;
 
+	#[test]
+	fn fee_asset_id_reads_charge_asset_tx_payment() {
+		let signed = |sponsor: Option<AccountId>, asset_id: Option<AssetId>| {
+			let extra: SignedExtra = (
+				frame_system::CheckNonZeroSender::new(),
+				frame_system::CheckSpecVersion::new(),
+				frame_system::CheckTxVersion::new(),
+				frame_system::CheckGenesis::new(),
+				frame_system::CheckEra::from(generic::Era::Immortal),
+				frame_system::CheckNonce::from(0),
+				frame_system::CheckWeight::new(),
+				pallet_template::CheckTemplateRateLimit::new(),
+				match sponsor {
+					Some(sponsor) => pallet_sponsorship::ChargeSponsored::Sponsored(sponsor),
+					None => pallet_sponsorship::ChargeSponsored::Unsponsored(
+						pallet_asset_tx_payment::ChargeAssetTxPayment::from(7, asset_id),
+					),
+				}
+				.into(),
+			);
+			UncheckedExtrinsic::new_signed(
+				SystemCall::remark { remark: vec![] }.into(),
+				Address::Id(AccountId::new([1; 32])),
+				Signature::Sr25519(sp_core::sr25519::Signature::from_raw([0; 64])),
+				extra,
+			)
+		};
+
+		assert_eq!(fee_asset_id(&signed(None, Some(3))), Some(3));
+		assert_eq!(fee_asset_id(&signed(None, None)), None);
+		// Sponsors pay in the native balance.
+		assert_eq!(fee_asset_id(&signed(Some(AccountId::new([2; 32])), None)), None);
+		assert_eq!(
+			fee_asset_id(&UncheckedExtrinsic::new_unsigned(
+				SystemCall::remark { remark: vec![] }.into()
+			)),
+			None
+		);
+	}
+
+	#[test]
+	fn only_nft_holders_pass_template_access_check() {
+		use pallet_nfts::{CollectionConfig, CollectionSettings, ItemConfig, MintSettings};
+
+		type EnsureHolder = EnsureNftHolder;
+		let alice = AccountId::new([1; 32]);
+		let bob = AccountId::new([2; 32]);
+
+		let mut storage =
+			frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> { balances: vec![(alice.clone(), 100 * UNIT)] }
+			.assimilate_storage(&mut storage)
+			.unwrap();
+		sp_io::TestExternalities::new(storage).execute_with(|| {
+			let config = CollectionConfig {
+				settings: CollectionSettings::all_enabled(),
+				max_supply: None,
+				mint_settings: MintSettings::default(),
+			};
+			Nfts::force_create(RuntimeOrigin::root(), Address::Id(alice.clone()), config).unwrap();
+			Nfts::force_mint(
+				RuntimeOrigin::root(),
+				0,
+				0,
+				Address::Id(alice.clone()),
+				ItemConfig::default(),
+			)
+			.unwrap();
+
+			// Any signed account passes until a collection is configured.
+			assert!(EnsureHolder::try_origin(RuntimeOrigin::signed(bob.clone())).is_ok());
+			assert!(EnsureHolder::try_origin(RuntimeOrigin::none()).is_err());
+
+			TemplateAccessCollection::set(&Some(0));
+			assert!(EnsureHolder::try_origin(RuntimeOrigin::signed(bob)).is_err());
+			assert_eq!(
+				EnsureHolder::try_origin(RuntimeOrigin::signed(alice.clone())).ok(),
+				Some(alice)
+			);
+
+			// Holding an item of another collection is not enough.
+			TemplateAccessCollection::set(&Some(1));
+			assert!(
+				EnsureHolder::try_origin(RuntimeOrigin::signed(AccountId::new([1; 32]))).is_err()
+			);
+		});
+	}
+
 	#[test]
 	fn check_whitelist() {
 		let whitelist: HashSet<String> = AllPalletsWithSystem::whitelisted_storage_keys()
@@ -0,0 +1,36 @@
+#!/usr/bin/env bash
+# Regenerates the `weights.rs` files of the pallets of this workspace by benchmarking them with
+# the node, built with the `runtime-benchmarks` feature.
+#
+# Usage: ./scripts/benchmark-weights.sh [pallet...], e.g. `./scripts/benchmark-weights.sh feeless`.
+# All pallets are benchmarked when none is given.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+PALLETS=("$@")
+if [ ${#PALLETS[@]} -eq 0 ]; then
+	PALLETS=(template feeless sponsorship)
+fi
+
+# The template producing pallet weight files with a `WeightInfo` trait, as FRAME pallets have.
+TEMPLATE=$(mktemp)
+trap 'rm -f "$TEMPLATE"' EXIT
+curl -sSfL -o "$TEMPLATE" \
+	https://raw.githubusercontent.com/paritytech/substrate/polkadot-v0.9.42/.maintain/frame-weight-template.hbs
+
+cargo build --release --features runtime-benchmarks
+
+for pallet in "${PALLETS[@]}"; do
+	echo "*** Benchmarking pallet_$pallet"
+	./target/release/node-template benchmark pallet \
+		--chain dev \
+		--pallet "pallet_$pallet" \
+		--extrinsic '*' \
+		--steps=50 \
+		--repeat=20 \
+		--execution=wasm \
+		--wasm-execution=compiled \
+		--output "pallets/$pallet/src/weights.rs" \
+		--template "$TEMPLATE"
+done
@@ -0,0 +1,406 @@
+//! An in-process node template for end-to-end tests of the runtime and the node.
+//!
+//! A [`TestNode`] runs the full service of the node on the development chain, with a temporary
+//! database and the keys of Alice, inside the tokio runtime of the test. Its RPC methods are
+//! called directly through the RPC handlers of the service, and blocks are either authored as the
+//! node would, or sealed on demand with [`Sealing::Manual`].
+
+use std::{net::TcpListener, sync::Arc, time::Duration};
+
+use clap::Parser;
+use codec::Encode;
+use futures::{channel::oneshot, SinkExt, StreamExt};
+use jsonrpsee::{core::traits::ToRpcParams, rpc_params, RpcModule};
+use node_template::{
+	chain_spec,
+	service::{self, FullClient, FullComponents},
+};
+use node_template_client::{
+	events::{decode_events, events_key, extrinsic_events, EventRecord},
+	tx::{sign, ChainContext, ExtraBuilder},
+};
+use node_template_runtime::{
+	BlockNumber, Hash, Index, RuntimeCall, RuntimeEvent, UncheckedExtrinsic,
+};
+use sc_cli::{CliConfiguration, RunCmd, SubstrateCli};
+use sc_client_api::{BlockBackend, BlockchainEvents, StorageProvider};
+use sc_consensus_manual_seal::EngineCommand;
+use serde::de::DeserializeOwned;
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::StorageKey, Bytes};
+use sp_keyring::Sr25519Keyring;
+use tempfile::TempDir;
+
+pub use service::Sealing;
+
+/// How long [`TestNode::wait_for_block`] waits for a block before panicking.
+const BLOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A development node running in the current process, stopped when dropped.
+pub struct TestNode {
+	components: FullComponents,
+	rpc_port: u16,
+	// Dropped after the components, which hold the database.
+	_base_path: TempDir,
+}
+
+impl TestNode {
+	/// Starts a `--dev` node authoring blocks with `sealing`.
+	///
+	/// Must be called from within a multi-threaded tokio runtime, which runs the tasks of the node.
+	pub fn start(sealing: Sealing) -> Self {
+		let base_path = TempDir::new().expect("Temporary directories can be created; qed");
+		let rpc_port = free_port();
+		let cmd = RunCmd::try_parse_from([
+			"test-node",
+			"--dev",
+			"--base-path",
+			&base_path.path().to_string_lossy(),
+			"--no-telemetry",
+			"--no-prometheus",
+			"--no-mdns",
+			"--port",
+			&free_port().to_string(),
+			"--rpc-port",
+			&rpc_port.to_string(),
+		])
+		.expect("The arguments are valid; qed");
+		let config = cmd
+			.create_configuration(&TestCli, tokio::runtime::Handle::current())
+			.expect("The development configuration is valid");
+
+		#[cfg(not(feature = "evm"))]
+		let components = service::new_full_components(config, sealing);
+		#[cfg(feature = "evm")]
+		let components = service::new_full_components(
+			config,
+			sealing,
+			node_template::eth::EthConfiguration::parse_from(["test-node"]),
+		);
+
+		Self {
+			components: components.expect("The development node starts"),
+			rpc_port,
+			_base_path: base_path,
+		}
+	}
+
+	/// The client of the node.
+	pub fn client(&self) -> &Arc<FullClient> {
+		&self.components.client
+	}
+
+	/// The RPC methods of the node.
+	pub fn rpc(&self) -> Arc<RpcModule<()>> {
+		self.components.rpc_handlers.handle()
+	}
+
+	/// The WebSocket endpoint of the RPC server of the node, for `node_template_client::Client`.
+	pub fn ws_url(&self) -> String {
+		format!("ws://127.0.0.1:{}", self.rpc_port)
+	}
+
+	/// Calls the RPC `method` of the node, panicking if it fails.
+	pub async fn rpc_call<T: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: impl ToRpcParams + Send,
+	) -> T {
+		self.rpc()
+			.call(method, params)
+			.await
+			.unwrap_or_else(|e| panic!("RPC call {method} failed: {e}"))
+	}
+
+	/// The hash of the best block.
+	pub fn best_hash(&self) -> Hash {
+		self.client().info().best_hash
+	}
+
+	/// The next nonce of `signer`, counting its transactions in the pool.
+	pub async fn next_nonce(&self, signer: Sr25519Keyring) -> Index {
+		self.rpc_call("system_accountNextIndex", rpc_params![signer.to_account_id()])
+			.await
+	}
+
+	/// Signs `call` with `signer` at its next nonce, as an immortal transaction without tip.
+	pub async fn sign(
+		&self,
+		call: impl Into<RuntimeCall>,
+		signer: Sr25519Keyring,
+	) -> UncheckedExtrinsic {
+		let context = ChainContext::native(self.client().info().genesis_hash);
+		let extra = ExtraBuilder::new(self.next_nonce(signer).await);
+		sign(call, &signer.pair(), &extra, &context)
+	}
+
+	/// Submits `xt` to the transaction pool through `author_submitExtrinsic`, returning its hash.
+	pub async fn submit_extrinsic(&self, xt: &UncheckedExtrinsic) -> Hash {
+		self.rpc_call("author_submitExtrinsic", rpc_params![Bytes(xt.encode())]).await
+	}
+
+	/// Signs `call` with `signer` and submits it, returning the transaction.
+	pub async fn submit(
+		&self,
+		call: impl Into<RuntimeCall>,
+		signer: Sr25519Keyring,
+	) -> UncheckedExtrinsic {
+		let xt = self.sign(call, signer).await;
+		self.submit_extrinsic(&xt).await;
+		xt
+	}
+
+	/// Seals and finalizes a block on top of the best block, with the transactions of the pool,
+	/// returning its hash.
+	///
+	/// Panics unless the node was started with [`Sealing::Manual`].
+	pub async fn seal_block(&self) -> Hash {
+		let mut sink = self
+			.components
+			.manual_seal
+			.clone()
+			.expect("Blocks are only sealed on demand with `Sealing::Manual`");
+		let (sender, receiver) = oneshot::channel();
+		sink.send(EngineCommand::SealNewBlock {
+			create_empty: true,
+			finalize: true,
+			parent_hash: None,
+			sender: Some(sender),
+		})
+		.await
+		.expect("The authoring task is running");
+		receiver
+			.await
+			.expect("The authoring task answers every command")
+			.unwrap_or_else(|e| panic!("Sealing a block failed: {e}"))
+			.hash
+	}
+
+	/// Waits for the best chain to reach the block `number`, returning its hash.
+	///
+	/// Panics if no block is imported for a minute.
+	pub async fn wait_for_block(&self, number: BlockNumber) -> Hash {
+		let mut imported = self.client().import_notification_stream();
+		loop {
+			if let Some(hash) = self.client().hash(number).expect("The database is readable") {
+				return hash
+			}
+			tokio::time::timeout(BLOCK_TIMEOUT, imported.next())
+				.await
+				.expect("A block is imported within the timeout")
+				.expect("The client is running");
+		}
+	}
+
+	/// Submits `call` signed by `signer`, seals a block and returns the events of the call.
+	///
+	/// Panics unless the node was started with [`Sealing::Manual`], or if the call is not in the
+	/// sealed block.
+	pub async fn execute(
+		&self,
+		call: impl Into<RuntimeCall>,
+		signer: Sr25519Keyring,
+	) -> Vec<RuntimeEvent> {
+		let xt = self.submit(call, signer).await.encode();
+		let hash = self.seal_block().await;
+		let index = self
+			.client()
+			.block(hash)
+			.expect("The sealed block is readable")
+			.expect("The sealed block is imported")
+			.block
+			.extrinsics
+			.iter()
+			.position(|included| included.encode() == xt)
+			.expect("The submitted transaction is in the sealed block");
+		extrinsic_events(self.events(hash), index as u32)
+	}
+
+	/// The events deposited in the block `at`.
+	pub fn events(&self, at: Hash) -> Vec<EventRecord> {
+		self.client()
+			.storage(at, &StorageKey(events_key()))
+			.expect("The state of the block is readable")
+			.map(|events| decode_events(&events.0).expect("The events are valid"))
+			.unwrap_or_default()
+	}
+
+	/// Asserts that `event` was deposited in the block `at`, listing the events of the block if
+	/// not.
+	#[track_caller]
+	pub fn assert_event(&self, at: Hash, event: impl Into<RuntimeEvent>) {
+		let event = event.into();
+		let events: Vec<_> = self.events(at).into_iter().map(|record| record.event).collect();
+		assert!(events.contains(&event), "{event:?} not deposited in {at:?}, events: {events:#?}");
+	}
+}
+
+/// The command line interface the configuration of a [`TestNode`] is built with.
+struct TestCli;
+
+impl SubstrateCli for TestCli {
+	fn impl_name() -> String {
+		"Substrate Test Node".into()
+	}
+
+	fn impl_version() -> String {
+		env!("CARGO_PKG_VERSION").into()
+	}
+
+	fn description() -> String {
+		env!("CARGO_PKG_DESCRIPTION").into()
+	}
+
+	fn author() -> String {
+		env!("CARGO_PKG_AUTHORS").into()
+	}
+
+	fn support_url() -> String {
+		"support.anonymous.an".into()
+	}
+
+	fn copyright_start_year() -> i32 {
+		2017
+	}
+
+	fn load_spec(&self, id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
+		match id {
+			"dev" => Ok(Box::new(chain_spec::development_config()?)),
+			_ => Err(format!("A test node only runs the development chain, not {id}")),
+		}
+	}
+
+	fn native_runtime_version(
+		_: &Box<dyn sc_service::ChainSpec>,
+	) -> &'static sc_cli::RuntimeVersion {
+		&node_template_runtime::VERSION
+	}
+}
+
+fn free_port() -> u16 {
+	TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use frame_support::dispatch::DispatchClass;
+	use node_template::rpc::contracts::{Code, CodeUploadRequest, Determinism, InstantiateRequest};
+	use node_template_client::{payment::TipEstimate, utilization::BlockUtilization};
+	use node_template_runtime::{
+		pallet_template, AccountId, Balance, BalancesCall, BlakeTwo256, Runtime, UNIT,
+	};
+	use pallet_contracts_primitives::{CodeUploadResult, ContractInstantiateResult};
+	use sp_runtime::traits::Hash as _;
+
+	/// A contract doing nothing: `(module (import "env" "memory" (memory 1 1)) (func (export
+	/// "call")) (func (export "deploy")))`.
+	const DUMMY_CONTRACT: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+		0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type 0: () -> ()
+		0x02, 0x10, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79, 0x02,
+		0x01, 0x01, 0x01, // import env.memory, one page at most
+		0x03, 0x03, 0x02, 0x00, 0x00, // two functions of type 0
+		0x07, 0x11, 0x02, 0x04, 0x63, 0x61, 0x6c, 0x6c, 0x00, 0x00, 0x06, 0x64, 0x65, 0x70, 0x6c,
+		0x6f, 0x79, 0x00, 0x01, // export them as call and deploy
+		0x0a, 0x07, 0x02, 0x02, 0x00, 0x0b, 0x02, 0x00, 0x0b, // empty bodies
+	];
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn manual_seal_executes_calls() {
+		let node = TestNode::start(Sealing::Manual);
+		let alice = Sr25519Keyring::Alice;
+
+		let events =
+			node.execute(pallet_template::Call::do_something { something: 42 }, alice).await;
+
+		assert!(events.contains(&RuntimeEvent::TemplateModule(
+			pallet_template::Event::SomethingStored { something: 42, who: alice.to_account_id() }
+		)));
+		let blocks: Vec<BlockUtilization> =
+			node.rpc_call("utilization_blocks", rpc_params![1, 1]).await;
+		assert_eq!(blocks[0].class(DispatchClass::Normal).extrinsics, 1);
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn no_tip_is_suggested_on_an_idle_chain() {
+		let node = TestNode::start(Sealing::Manual);
+		node.seal_block().await;
+		let call = RuntimeCall::from(pallet_template::Call::do_something { something: 42 });
+
+		let estimate: TipEstimate =
+			node.rpc_call("payment_estimate", rpc_params![Bytes(call.encode()), 0.5]).await;
+
+		assert_eq!(estimate.tip, 0);
+		assert_eq!((estimate.blocks, estimate.full_blocks, estimate.pool), (1, 0, 0));
+		assert!(estimate.partial_fee > 0);
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn contracts_uploads_and_instantiations_are_dry_run() {
+		let node = TestNode::start(Sealing::Manual);
+		let alice = Sr25519Keyring::Alice.to_account_id();
+		let upload = |code: &[u8]| CodeUploadRequest {
+			origin: alice.clone(),
+			code: Bytes(code.to_vec()),
+			storage_deposit_limit: None,
+			determinism: Determinism::Enforced,
+		};
+
+		let result: Bytes =
+			node.rpc_call("contracts_uploadCode", rpc_params![upload(DUMMY_CONTRACT)]).await;
+		let uploaded = CodeUploadResult::<Hash, Balance>::decode(&mut &result[..])
+			.unwrap()
+			.expect("The dummy contract is valid");
+		assert_eq!(uploaded.code_hash, BlakeTwo256::hash(DUMMY_CONTRACT));
+		assert!(uploaded.deposit > 0);
+
+		let result: Bytes =
+			node.rpc_call("contracts_uploadCode", rpc_params![upload(b"not wasm")]).await;
+		assert!(CodeUploadResult::<Hash, Balance>::decode(&mut &result[..]).unwrap().is_err());
+
+		let instantiate = |code: Code| InstantiateRequest {
+			origin: alice.clone(),
+			value: 0,
+			gas_limit: None,
+			storage_deposit_limit: None,
+			code,
+			data: Bytes(vec![]),
+			salt: Bytes(vec![]),
+		};
+		let request = instantiate(Code::Upload(Bytes(DUMMY_CONTRACT.to_vec())));
+		let result: Bytes = node.rpc_call("contracts_instantiate", rpc_params![request]).await;
+		let instantiated =
+			ContractInstantiateResult::<AccountId, Balance>::decode(&mut &result[..])
+				.unwrap()
+				.result
+				.expect("The dummy contract deploys");
+		assert!(!instantiated.result.did_revert());
+
+		// Nothing was persisted: the code of the dry runs is not on chain.
+		let request = instantiate(Code::Existing(uploaded.code_hash));
+		let result: Bytes = node.rpc_call("contracts_instantiate", rpc_params![request]).await;
+		let result = ContractInstantiateResult::<AccountId, Balance>::decode(&mut &result[..]);
+		assert!(result.unwrap().result.is_err());
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn instant_seal_includes_submitted_transactions() {
+		let node = TestNode::start(Sealing::Instant);
+		let bob = Sr25519Keyring::Bob.to_account_id();
+
+		let call = BalancesCall::transfer_keep_alive { dest: bob.clone().into(), value: UNIT };
+		node.submit(call, Sr25519Keyring::Alice).await;
+		let hash = node.wait_for_block(1).await;
+
+		node.assert_event(
+			hash,
+			pallet_balances::Event::<Runtime>::Transfer {
+				from: Sr25519Keyring::Alice.to_account_id(),
+				to: bob,
+				amount: UNIT,
+			},
+		);
+	}
+}